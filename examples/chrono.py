@@ -11,9 +11,8 @@ y = [100, 200, 150]
 
 # draw
 canvas = ez.Canvas()
-top, bottom = canvas.split_vertically()
-left, right = bottom.split_horizontally()
-del bottom
+top = canvas.grid_span(2, 2, 0, slice(None))
+left, right = canvas.grid(2, 2)[1]
 
 # datetime
 c = ez.Chart(top, caption='datetime vs f64', x_range=ez.Range.datetime(dt(2020, 1, 1), dt(2020, 12, 31)), y_range=ez.Range.f64(0, 200))
@@ -0,0 +1,177 @@
+//! Reference lines, spans and text in data coordinates.
+//! `Chart.axhline` and `Chart.axvline` span the whole chart, `Chart.axhspan` and `Chart.axvspan`
+//! fill a band, and `Chart.text` and `Chart.annotate` place a text, optionally with an arrow.
+//!
+//! A value is a number, a datetime.datetime/date/time/timedelta, or a str on a categorical axis.
+//! It's kept as a series of a single value, so that it's read like any other series.
+
+use crate::range::{self, RangeEnum};
+use crate::{Dtype, Series};
+use plotters::element::{Drawable, PointCollection};
+use plotters::prelude::*;
+use plotters::style::text_anchor::{HPos, Pos, VPos};
+use plotters_backend::{BackendCoord, DrawingErrorKind};
+use pyo3::prelude::*;
+use pyo3::types::PyList;
+use std::cmp::Ordering;
+
+/// A single value on an axis.
+pub struct Value(pub Series);
+
+impl<'source> FromPyObject<'source> for Value {
+    fn extract(x: &'source PyAny) -> PyResult<Self> {
+        Ok(Value(PyList::new(x.py(), &[x]).extract()?))
+    }
+}
+
+/// A text and its font.
+pub struct TextLabel {
+    pub text: String,
+    pub font: String,
+    pub size: u32,
+    /// The point of the text placed at the position. None places the text away from the arrow,
+    /// or at the lower left without an arrow.
+    pub anchor: Option<Pos>,
+}
+
+pub enum Annotation {
+    HLine(Value),
+    VLine(Value),
+    HSpan(Value, Value),
+    VSpan(Value, Value),
+    /// A text at xytext, or at xy if xytext is None. An arrow points from xytext to xy.
+    Text {
+        label: TextLabel,
+        xy: (Value, Value),
+        xytext: Option<(Value, Value)>,
+        arrow: bool,
+    },
+}
+
+impl Annotation {
+    /// The x and y range covered by the annotation. Lines and spans cover their own axis only.
+    pub fn extent(
+        &self,
+        py: Python,
+        x_dtype: Dtype,
+        y_dtype: Dtype,
+    ) -> PyResult<(Option<RangeEnum>, Option<RangeEnum>)> {
+        let extent = |values: &[&Value], dtype: Dtype| -> PyResult<Option<RangeEnum>> {
+            let mut extent: Option<RangeEnum> = None;
+            for v in values {
+                extent = match (extent, range::series_extent(py, &v.0, dtype)?) {
+                    (Some(a), Some(b)) => Some(a.union(b)),
+                    (a, b) => a.or(b),
+                };
+            }
+            Ok(extent)
+        };
+        Ok(match self {
+            Annotation::HLine(y) => (None, extent(&[y], y_dtype)?),
+            Annotation::VLine(x) => (extent(&[x], x_dtype)?, None),
+            Annotation::HSpan(lo, hi) => (None, extent(&[lo, hi], y_dtype)?),
+            Annotation::VSpan(lo, hi) => (extent(&[lo, hi], x_dtype)?, None),
+            Annotation::Text { xy, xytext, .. } => match xytext {
+                Some(text) => (
+                    extent(&[&xy.0, &text.0], x_dtype)?,
+                    extent(&[&xy.1, &text.1], y_dtype)?,
+                ),
+                None => (extent(&[&xy.0], x_dtype)?, extent(&[&xy.1], y_dtype)?),
+            },
+        })
+    }
+}
+
+/// A text anchor named like a legend position, e.g. "upper left" places the upper left corner
+/// of the text at the position.
+pub fn text_anchor(name: &str) -> PyResult<Pos> {
+    let (v_pos, h_pos) = match name {
+        "upper left" => (VPos::Top, HPos::Left),
+        "upper center" => (VPos::Top, HPos::Center),
+        "upper right" => (VPos::Top, HPos::Right),
+        "center left" => (VPos::Center, HPos::Left),
+        "center" => (VPos::Center, HPos::Center),
+        "center right" => (VPos::Center, HPos::Right),
+        "lower left" => (VPos::Bottom, HPos::Left),
+        "lower center" => (VPos::Bottom, HPos::Center),
+        "lower right" => (VPos::Bottom, HPos::Right),
+        _ => {
+            return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                "unknown anchor {:?}, expected \"upper left\", \"upper center\", \"upper right\", \"center left\", \"center\", \"center right\", \"lower left\", \"lower center\" or \"lower right\"",
+                name
+            )))
+        }
+    };
+    Ok(Pos::new(h_pos, v_pos))
+}
+
+/// The anchor that places a text at `at` on the far side from `target`, both in pixels.
+pub fn anchor_away(at: BackendCoord, target: BackendCoord) -> Pos {
+    let h_pos = match at.0.cmp(&target.0) {
+        Ordering::Less => HPos::Right,
+        Ordering::Equal => HPos::Center,
+        Ordering::Greater => HPos::Left,
+    };
+    // pixel y grows downwards
+    let v_pos = match at.1.cmp(&target.1) {
+        Ordering::Less => VPos::Bottom,
+        Ordering::Equal => VPos::Center,
+        Ordering::Greater => VPos::Top,
+    };
+    Pos::new(h_pos, v_pos)
+}
+
+/// The length of the arrow head in pixels.
+const HEAD: f64 = 10.0;
+
+/// A line from the first point to the second, with a head at the second.
+pub struct Arrow<Coord> {
+    points: [Coord; 2],
+    style: ShapeStyle,
+}
+
+impl<Coord> Arrow<Coord> {
+    pub fn new(from: Coord, to: Coord, style: ShapeStyle) -> Self {
+        Arrow {
+            points: [from, to],
+            style,
+        }
+    }
+}
+
+impl<'a, Coord: 'a> PointCollection<'a, Coord> for &'a Arrow<Coord> {
+    type Point = &'a Coord;
+    type IntoIter = &'a [Coord];
+    fn point_iter(self) -> Self::IntoIter {
+        &self.points
+    }
+}
+
+impl<Coord, DB: DrawingBackend> Drawable<DB> for Arrow<Coord> {
+    fn draw<I: Iterator<Item = BackendCoord>>(
+        &self,
+        points: I,
+        backend: &mut DB,
+        _: (u32, u32),
+    ) -> Result<(), DrawingErrorKind<DB::ErrorType>> {
+        let points: Vec<BackendCoord> = points.take(2).collect();
+        if let [from, to] = points[..] {
+            backend.draw_line(from, to, &self.style)?;
+            let (dx, dy) = ((to.0 - from.0) as f64, (to.1 - from.1) as f64);
+            let len = dx.hypot(dy);
+            if len == 0.0 {
+                return Ok(());
+            }
+            // the head is 30 degrees off the line on both sides
+            let (ux, uy) = (dx / len * HEAD, dy / len * HEAD);
+            let (cos, sin) = (30f64.to_radians().cos(), 30f64.to_radians().sin());
+            for s in [-1.0, 1.0].iter() {
+                let hx = ux * cos - s * uy * sin;
+                let hy = s * ux * sin + uy * cos;
+                let end = (to.0 - hx.round() as i32, to.1 - hy.round() as i32);
+                backend.draw_line(to, end, &self.style)?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -1,25 +1,350 @@
 //! A type-erased wrapper around plotters::DrawingBackend and buffer.
 //! This is not exposed to Python user and only used internally.
 //!
-//! For now, it only supports bitmap.
+//! It supports bitmap and SVG.
+//! `EzelBackend` forwards every drawing call to the concrete plotters backend,
+//! so that charts are built on a single backend type.
 
 use crate::hack::static_slice_mut;
 use plotters::prelude::*;
+use plotters_backend::{
+    BackendColor, BackendCoord, BackendStyle, BackendTextStyle, DrawingErrorKind,
+};
+use pyo3::prelude::*;
 use std::cell::RefCell;
 use std::rc::Rc;
 
+type BitMapError = <BitMapBackend<'static> as DrawingBackend>::ErrorType;
+type SVGError = <SVGBackend<'static> as DrawingBackend>::ErrorType;
+
+pub enum EzelBackend {
+    BitMap(BitMapBackend<'static>),
+    SVG(OwnedSVG),
+}
+
+/// A SVGBackend that owns the String it writes into.
+/// SVGBackend keeps a `&mut String` until it's dropped, and writes the closing tags on drop,
+/// so the String is read only while no backend is alive, and freed only after the backend.
+pub struct OwnedSVG {
+    /// Declared before `buffer`, so it's dropped first.
+    backend: SVGBackend<'static>,
+    buffer: SVGBuffer,
+    size: (u32, u32),
+    /// The drawings flushed from the previous backends, without the root <svg> tags.
+    body: String,
+}
+
+/// The String a SVGBackend writes into, freed on drop.
+struct SVGBuffer(*mut String);
+
+impl SVGBuffer {
+    fn new() -> Self {
+        Self(Box::into_raw(Box::new(String::new())))
+    }
+}
+
+impl Drop for SVGBuffer {
+    fn drop(&mut self) {
+        drop(unsafe { Box::from_raw(self.0) });
+    }
+}
+
+impl OwnedSVG {
+    fn new(size: (u32, u32)) -> Self {
+        let buffer = SVGBuffer::new();
+        Self {
+            backend: SVGBackend::with_string(unsafe { &mut *buffer.0 }, size),
+            buffer,
+            size,
+            body: String::new(),
+        }
+    }
+
+    /// The SVG document drawn so far.
+    /// A presented SVGBackend can't draw anymore, so the backend is replaced by a new one
+    /// writing into a new buffer, and dropped to close its tags.
+    fn document(&mut self) -> String {
+        let buffer = SVGBuffer::new();
+        let backend = SVGBackend::with_string(unsafe { &mut *buffer.0 }, self.size);
+        // dropping the backend presents it, and ends its borrow of the buffer
+        drop(std::mem::replace(&mut self.backend, backend));
+        let old_buffer = std::mem::replace(&mut self.buffer, buffer);
+        let old = unsafe { &*old_buffer.0 };
+        // the buffer is the root <svg> tag on the first line, the drawings, and </svg>
+        let start = old.find('\n').map_or(0, |i| i + 1);
+        let end = old.rfind("</svg>").unwrap_or(old.len());
+        self.body.push_str(&old[start..end]);
+        format!("{}{}</svg>\n", &old[..start], self.body)
+    }
+}
+
+#[derive(Debug)]
+pub enum EzelBackendError {
+    BitMap(BitMapError),
+    SVG(SVGError),
+}
+
+impl std::fmt::Display for EzelBackendError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            EzelBackendError::BitMap(e) => write!(f, "bitmap backend: {}", e),
+            EzelBackendError::SVG(e) => write!(f, "svg backend: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for EzelBackendError {}
+
+fn bitmap_err(e: DrawingErrorKind<BitMapError>) -> DrawingErrorKind<EzelBackendError> {
+    match e {
+        DrawingErrorKind::DrawingError(e) => {
+            DrawingErrorKind::DrawingError(EzelBackendError::BitMap(e))
+        }
+        DrawingErrorKind::FontError(e) => DrawingErrorKind::FontError(e),
+    }
+}
+
+fn svg_err(e: DrawingErrorKind<SVGError>) -> DrawingErrorKind<EzelBackendError> {
+    match e {
+        DrawingErrorKind::DrawingError(e) => {
+            DrawingErrorKind::DrawingError(EzelBackendError::SVG(e))
+        }
+        DrawingErrorKind::FontError(e) => DrawingErrorKind::FontError(e),
+    }
+}
+
+/// forward a DrawingBackend call to the concrete backend.
+macro_rules! forward {
+    ($self:expr, $b:ident => $call:expr) => {
+        match $self {
+            EzelBackend::BitMap($b) => $call.map_err(bitmap_err),
+            EzelBackend::SVG(svg) => {
+                let $b = &mut svg.backend;
+                $call.map_err(svg_err)
+            }
+        }
+    };
+}
+
+impl DrawingBackend for EzelBackend {
+    type ErrorType = EzelBackendError;
+
+    fn get_size(&self) -> (u32, u32) {
+        match self {
+            EzelBackend::BitMap(b) => b.get_size(),
+            EzelBackend::SVG(svg) => svg.size,
+        }
+    }
+
+    fn ensure_prepared(&mut self) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        forward!(self, b => b.ensure_prepared())
+    }
+
+    fn present(&mut self) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        forward!(self, b => b.present())
+    }
+
+    fn draw_pixel(
+        &mut self,
+        point: BackendCoord,
+        color: BackendColor,
+    ) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        forward!(self, b => b.draw_pixel(point, color))
+    }
+
+    fn draw_line<S: BackendStyle>(
+        &mut self,
+        from: BackendCoord,
+        to: BackendCoord,
+        style: &S,
+    ) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        forward!(self, b => b.draw_line(from, to, style))
+    }
+
+    fn draw_rect<S: BackendStyle>(
+        &mut self,
+        upper_left: BackendCoord,
+        bottom_right: BackendCoord,
+        style: &S,
+        fill: bool,
+    ) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        forward!(self, b => b.draw_rect(upper_left, bottom_right, style, fill))
+    }
+
+    fn draw_path<S: BackendStyle, I: IntoIterator<Item = BackendCoord>>(
+        &mut self,
+        path: I,
+        style: &S,
+    ) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        forward!(self, b => b.draw_path(path, style))
+    }
+
+    fn draw_circle<S: BackendStyle>(
+        &mut self,
+        center: BackendCoord,
+        radius: u32,
+        style: &S,
+        fill: bool,
+    ) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        forward!(self, b => b.draw_circle(center, radius, style, fill))
+    }
+
+    fn fill_polygon<S: BackendStyle, I: IntoIterator<Item = BackendCoord>>(
+        &mut self,
+        vert: I,
+        style: &S,
+    ) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        forward!(self, b => b.fill_polygon(vert, style))
+    }
+
+    fn draw_text<TStyle: BackendTextStyle>(
+        &mut self,
+        text: &str,
+        style: &TStyle,
+        pos: BackendCoord,
+    ) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        forward!(self, b => b.draw_text(text, style, pos))
+    }
+
+    fn estimate_text_size<TStyle: BackendTextStyle>(
+        &self,
+        text: &str,
+        style: &TStyle,
+    ) -> Result<(u32, u32), DrawingErrorKind<Self::ErrorType>> {
+        match self {
+            EzelBackend::BitMap(b) => b.estimate_text_size(text, style).map_err(bitmap_err),
+            EzelBackend::SVG(svg) => svg.backend.estimate_text_size(text, style).map_err(svg_err),
+        }
+    }
+
+    fn blit_bitmap<'a>(
+        &mut self,
+        pos: BackendCoord,
+        size: (u32, u32),
+        src: &'a [u8],
+    ) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        forward!(self, b => b.blit_bitmap(pos, size, src))
+    }
+}
+
+/// A drawing postponed until the canvas is rendered,
+/// e.g. a chart whose range is inferred from its data.
+pub trait Deferred {
+    fn render(&self, py: Python) -> PyResult<()>;
+}
+
 pub struct Backend {
-    pub buffer: Vec<u8>,                            // or String
-    pub inner: Rc<RefCell<BitMapBackend<'static>>>, // TODO: generalize to SVG and wasm
+    // fields are dropped in order. deferred charts and inner reference buffer, so they're dropped first.
+    pub deferred: RefCell<Vec<Rc<dyn Deferred>>>,
+    pub inner: Rc<RefCell<EzelBackend>>,
+    pub buffer: Vec<u8>, // RGB pixels of the bitmap backend
 }
+
 impl Backend {
-    pub fn new(width: usize, height: usize) -> Self {
+    pub fn bitmap(width: usize, height: usize) -> Self {
         let mut buffer = vec![0; width * height * 3];
-        let inner = Rc::new(RefCell::new(BitMapBackend::with_buffer(
-            unsafe { static_slice_mut(&mut buffer) },
-            (width as u32, height as u32),
+        let inner = Rc::new(RefCell::new(EzelBackend::BitMap(
+            BitMapBackend::with_buffer(
+                unsafe { static_slice_mut(&mut buffer) },
+                (width as u32, height as u32),
+            ),
         )));
-        // let inner = Rc::new(RefCell::new(BitMapBackend::new("ezel.png", (width as u32, height as u32))));
-        Self { buffer, inner }
+        Self {
+            buffer,
+            inner,
+            deferred: RefCell::new(Vec::new()),
+        }
+    }
+
+    pub fn svg(width: usize, height: usize) -> Self {
+        let inner = Rc::new(RefCell::new(EzelBackend::SVG(OwnedSVG::new((
+            width as u32,
+            height as u32,
+        )))));
+        Self {
+            buffer: Vec::new(),
+            inner,
+            deferred: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Renders all deferred drawings. Each is rendered once.
+    /// A drawing that fails stays deferred, and the first error is returned
+    /// after the others are rendered.
+    pub fn render_deferred(&self, py: Python) -> PyResult<()> {
+        let deferred = std::mem::take(&mut *self.deferred.borrow_mut());
+        let mut error = None;
+        for d in deferred {
+            if let Err(e) = d.render(py) {
+                error.get_or_insert(e);
+                self.deferred.borrow_mut().push(d);
+            }
+        }
+        error.map_or(Ok(()), Err)
+    }
+
+    pub fn is_svg(&self) -> bool {
+        matches!(*self.inner.borrow(), EzelBackend::SVG(..))
+    }
+
+    /// Encode the bitmap into an image file in memory.
+    pub fn encode(&self, format: image::ImageFormat) -> image::ImageResult<Vec<u8>> {
+        let (w, h) = self.inner.borrow().get_size();
+        let img = image::RgbImage::from_raw(w, h, self.buffer.clone()).ok_or_else(|| {
+            // an SVG backend has no pixels
+            image::ImageError::Parameter(image::error::ParameterError::from_kind(
+                image::error::ParameterErrorKind::DimensionMismatch,
+            ))
+        })?;
+        let mut bytes = Vec::new();
+        image::DynamicImage::ImageRgb8(img).write_to(&mut bytes, format)?;
+        Ok(bytes)
+    }
+
+    /// The SVG document drawn so far. Drawing can continue after it.
+    pub fn svg_document(&self) -> String {
+        match &mut *self.inner.borrow_mut() {
+            EzelBackend::SVG(svg) => svg.document(),
+            EzelBackend::BitMap(..) => String::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn svg_document_keeps_drawing() {
+        let backend = Backend::svg(40, 30);
+        let style = RGBColor(255, 0, 0);
+        backend
+            .inner
+            .borrow_mut()
+            .draw_rect((1, 1), (5, 5), &style, true)
+            .unwrap();
+        let first = backend.svg_document();
+        assert!(first.starts_with("<svg"));
+        assert!(first.ends_with("</svg>\n"));
+        assert_eq!(first.matches("<rect").count(), 1);
+
+        backend
+            .inner
+            .borrow_mut()
+            .draw_rect((10, 10), (20, 20), &style, false)
+            .unwrap();
+        let second = backend.svg_document();
+        assert_eq!(second.matches("<svg").count(), 1);
+        assert_eq!(second.matches("<rect").count(), 2);
+        assert!(second.starts_with(&first[..first.len() - "</svg>\n".len()]));
+    }
+
+    #[test]
+    fn svg_backend_outlives_backend() {
+        let backend = Backend::svg(40, 30);
+        let inner = backend.inner.clone();
+        drop(backend);
+        // the SVGBackend writes its closing tags into its own buffer on drop
+        drop(inner);
     }
 }
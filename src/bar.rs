@@ -0,0 +1,453 @@
+//! Bar charts. A bar spans `width` on the position axis and `height` on the length axis.
+//! `Chart.bar` puts positions on the x-axis, and `Chart.barh` on the y-axis.
+//!
+//! The position axis can be numeric or chrono. A width is a number in the axis unit (days on chrono axes),
+//! or a calendar unit, in which case the bar spans the whole unit containing its position.
+//! Bars on a categorical axis are placed at their category, and a width is a fraction of the segment.
+//! The length axis must be numeric.
+
+use crate::error::{self, Error};
+use crate::hist::{self, CalendarUnit};
+use crate::range::{self, RangeEnum};
+use crate::{Dtype, Series};
+use chrono::{Duration, NaiveDate, NaiveDateTime, NaiveTime};
+use pyo3::prelude::*;
+
+pub enum BarWidth {
+    Number(f64),
+    Unit(CalendarUnit),
+}
+
+impl<'source> FromPyObject<'source> for BarWidth {
+    fn extract(x: &'source PyAny) -> PyResult<Self> {
+        if let Ok(w) = x.extract::<f64>() {
+            if w.is_nan() || w <= 0.0 {
+                return Err(pyo3::exceptions::PyValueError::new_err(
+                    "width should be positive",
+                ));
+            }
+            return Ok(BarWidth::Number(w));
+        }
+        if let Ok(s) = x.extract::<&str>() {
+            return CalendarUnit::parse(s).map(BarWidth::Unit).ok_or_else(|| {
+                pyo3::exceptions::PyValueError::new_err(format!(
+                    "unknown calendar unit {:?}, expected one of year, month, week, day, hour, minute, second",
+                    s
+                ))
+            });
+        }
+        Err(pyo3::exceptions::PyValueError::new_err(
+            "width should be a number or a calendar unit",
+        ))
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Align {
+    /// The bar is centered at its position.
+    Center,
+    /// The bar starts at its position.
+    Edge,
+}
+
+impl Align {
+    pub fn parse(s: &str) -> PyResult<Self> {
+        match s {
+            "center" => Ok(Align::Center),
+            "edge" => Ok(Align::Edge),
+            _ => Err(pyo3::exceptions::PyValueError::new_err(format!(
+                "unknown align {:?}, expected \"center\" or \"edge\"",
+                s
+            ))),
+        }
+    }
+}
+
+/// The start of the bars on the length axis. A scalar, or a value per bar to stack bars.
+pub enum Baseline {
+    Scalar(f64),
+    Series(Series),
+}
+
+impl<'source> FromPyObject<'source> for Baseline {
+    fn extract(x: &'source PyAny) -> PyResult<Self> {
+        if let Ok(v) = x.extract::<f64>() {
+            return Ok(Baseline::Scalar(v));
+        }
+        Ok(Baseline::Series(x.extract()?))
+    }
+}
+
+impl Baseline {
+    pub fn values<'a>(&'a self, py: Python<'a>) -> PyResult<Box<dyn Iterator<Item = f64> + 'a>> {
+        match self {
+            Baseline::Scalar(v) => Ok(Box::new(std::iter::repeat(*v))),
+            Baseline::Series(s) => s.iter_f64(py),
+        }
+    }
+}
+
+/// Bars to draw. `positions` and `lengths` have the same length.
+pub struct BarSeries {
+    pub positions: Series,
+    pub lengths: Series,
+    pub baseline: Baseline,
+    pub width: Option<BarWidth>,
+    pub align: Align,
+    /// positions are on the y-axis.
+    pub horizontal: bool,
+}
+
+/// A value of a bar chart axis.
+pub trait BarValue: Sized {
+    /// The (start, end) of a bar at this position.
+    fn bar_span(&self, width: &Option<BarWidth>, align: Align) -> PyResult<(Self, Self)>;
+    /// A value of the length axis.
+    fn from_length(v: f64) -> PyResult<Self>;
+}
+
+fn unit_on_numeric_axis() -> PyErr {
+    pyo3::exceptions::PyValueError::new_err("a calendar unit width needs a chrono axis")
+}
+
+fn unit_mismatch(unit: CalendarUnit, axis: &str) -> PyErr {
+    pyo3::exceptions::PyValueError::new_err(format!(
+        "a {:?} width doesn't fit bars on a {} axis",
+        unit, axis
+    ))
+}
+
+fn length_not_numeric(dtype: Dtype) -> PyErr {
+    Error::DtypeMismatch {
+        expected: "a numeric axis for bar lengths and box values".to_string(),
+        actual: format!("a {:?} axis", dtype),
+    }
+    .into()
+}
+
+/// Addition that fails instead of panicking past the end of the type.
+trait CheckedShift<D>: Sized {
+    fn shift(self, d: D) -> Option<Self>;
+}
+
+impl CheckedShift<f64> for f64 {
+    fn shift(self, d: f64) -> Option<Self> {
+        Some(self + d)
+    }
+}
+
+impl CheckedShift<Duration> for NaiveDateTime {
+    fn shift(self, d: Duration) -> Option<Self> {
+        self.checked_add_signed(d)
+    }
+}
+
+impl CheckedShift<Duration> for NaiveDate {
+    fn shift(self, d: Duration) -> Option<Self> {
+        self.checked_add_signed(d)
+    }
+}
+
+/// A time can't cross midnight.
+impl CheckedShift<Duration> for NaiveTime {
+    fn shift(self, d: Duration) -> Option<Self> {
+        match self.overflowing_add_signed(d) {
+            (t, 0) => Some(t),
+            _ => None,
+        }
+    }
+}
+
+impl CheckedShift<Duration> for Duration {
+    fn shift(self, d: Duration) -> Option<Self> {
+        self.checked_add(&d)
+    }
+}
+
+fn align_span<T, D>(x: T, w: D, align: Align, half: impl Fn(D) -> D) -> error::Result<(T, T)>
+where
+    T: Copy + CheckedShift<D>,
+    D: Copy + std::ops::Neg<Output = D>,
+{
+    let start = match align {
+        Align::Center => x.shift(-half(w)),
+        Align::Edge => Some(x),
+    };
+    start
+        .and_then(|start| Some((start, start.shift(w)?)))
+        .ok_or_else(|| Error::OutOfRange {
+            what: "the end of a bar".to_string(),
+        })
+}
+
+/// `w` days. Fails if it doesn't fit a chrono::Duration in nanoseconds, about 292 years.
+pub fn days(w: f64) -> error::Result<Duration> {
+    let ns = (w * 86400e9).round();
+    if ns.is_nan() || ns.abs() >= i64::MAX as f64 {
+        return Err(Error::OutOfRange {
+            what: format!("{} days", w),
+        });
+    }
+    Ok(Duration::nanoseconds(ns as i64))
+}
+
+/// `n` whole days. Fails if it's longer than any date range.
+pub fn whole_days(n: i64) -> error::Result<Duration> {
+    if !(-(1 << 30)..=1 << 30).contains(&n) {
+        return Err(Error::OutOfRange {
+            what: format!("{} days", n),
+        });
+    }
+    Ok(Duration::days(n))
+}
+
+impl BarValue for f64 {
+    fn bar_span(&self, width: &Option<BarWidth>, align: Align) -> PyResult<(Self, Self)> {
+        let w = match width {
+            None => 0.8,
+            Some(BarWidth::Number(w)) => *w,
+            Some(BarWidth::Unit(..)) => return Err(unit_on_numeric_axis()),
+        };
+        Ok(align_span(*self, w, align, |w| w / 2.0)?)
+    }
+    fn from_length(v: f64) -> PyResult<Self> {
+        Ok(v)
+    }
+}
+
+impl BarValue for f32 {
+    fn bar_span(&self, width: &Option<BarWidth>, align: Align) -> PyResult<(Self, Self)> {
+        let (a, b) = (*self as f64).bar_span(width, align)?;
+        Ok((a as f32, b as f32))
+    }
+    fn from_length(v: f64) -> PyResult<Self> {
+        Ok(v as f32)
+    }
+}
+
+macro_rules! impl_integer {
+    ($($t:ty => $dtype:expr),*) => {$(
+        /// An integer axis can't place a bar between two integers.
+        impl BarValue for $t {
+            fn bar_span(&self, _: &Option<BarWidth>, _: Align) -> PyResult<(Self, Self)> {
+                Err(Error::DtypeMismatch {
+                    expected: "a float or chrono axis for bar positions".to_string(),
+                    actual: format!("a {:?} axis", $dtype),
+                }
+                .into())
+            }
+            fn from_length(v: f64) -> PyResult<Self> {
+                Ok(v.round() as $t)
+            }
+        }
+    )*};
+}
+
+impl_integer!(i64 => Dtype::I64, i32 => Dtype::I32);
+
+impl BarValue for NaiveDateTime {
+    fn bar_span(&self, width: &Option<BarWidth>, align: Align) -> PyResult<(Self, Self)> {
+        Ok(match width {
+            None => align_span(*self, days(0.8)?, align, |w| w / 2)?,
+            Some(BarWidth::Number(w)) => align_span(*self, days(*w)?, align, |w| w / 2)?,
+            Some(BarWidth::Unit(unit)) => {
+                let start = unit.floor(*self)?;
+                (start, unit.next(start)?)
+            }
+        })
+    }
+    fn from_length(_: f64) -> PyResult<Self> {
+        Err(length_not_numeric(Dtype::NaiveDateTime))
+    }
+}
+
+/// A date axis has no time of day, so bars span whole days.
+impl BarValue for NaiveDate {
+    fn bar_span(&self, width: &Option<BarWidth>, align: Align) -> PyResult<(Self, Self)> {
+        let n = match width {
+            None => 1,
+            Some(BarWidth::Number(w)) => (w.round() as i64).max(1),
+            Some(BarWidth::Unit(unit)) if unit.is_date_unit() => {
+                let start = unit.floor_date(*self)?;
+                return Ok((start, unit.next_date(start)?));
+            }
+            Some(BarWidth::Unit(unit)) => return Err(unit_mismatch(*unit, "date")),
+        };
+        Ok(align_span(*self, whole_days(n)?, align, |w| {
+            Duration::days(w.num_days() / 2)
+        })?)
+    }
+    fn from_length(_: f64) -> PyResult<Self> {
+        Err(length_not_numeric(Dtype::NaiveDate))
+    }
+}
+
+impl BarValue for NaiveTime {
+    fn bar_span(&self, width: &Option<BarWidth>, align: Align) -> PyResult<(Self, Self)> {
+        let w = match width {
+            None => Duration::minutes(48),
+            Some(BarWidth::Number(w)) => days(*w)?,
+            Some(BarWidth::Unit(unit)) if !unit.is_date_unit() => {
+                // the last unit of the day ends at the last nanosecond
+                let unit_ns = unit.fixed_duration().unwrap().num_nanoseconds().unwrap();
+                let ns = hist::time_to_ns(*self);
+                let start = ns - ns % unit_ns;
+                return Ok((hist::ns_to_time(start), hist::ns_to_time(start + unit_ns)));
+            }
+            Some(BarWidth::Unit(unit)) => return Err(unit_mismatch(*unit, "time")),
+        };
+        Ok(align_span(*self, w, align, |w| w / 2)?)
+    }
+    fn from_length(_: f64) -> PyResult<Self> {
+        Err(length_not_numeric(Dtype::NaiveTime))
+    }
+}
+
+impl BarValue for Duration {
+    fn bar_span(&self, width: &Option<BarWidth>, align: Align) -> PyResult<(Self, Self)> {
+        let w = match width {
+            None => Duration::minutes(48),
+            Some(BarWidth::Number(w)) => days(*w)?,
+            Some(BarWidth::Unit(unit)) => {
+                let unit_ns = match unit.fixed_duration() {
+                    Some(d) => d.num_nanoseconds().unwrap_or(i64::MAX),
+                    None => return Err(unit_mismatch(*unit, "duration")),
+                };
+                let ns = self.num_nanoseconds().unwrap_or(0);
+                let start = Duration::nanoseconds(ns - ns.rem_euclid(unit_ns));
+                return Ok((start, start + Duration::nanoseconds(unit_ns)));
+            }
+        };
+        Ok(align_span(*self, w, align, |w| w / 2)?)
+    }
+    fn from_length(_: f64) -> PyResult<Self> {
+        Err(length_not_numeric(Dtype::Duration))
+    }
+}
+
+fn spans<T: BarValue + Copy>(
+    lo: T,
+    hi: T,
+    width: &Option<BarWidth>,
+    align: Align,
+) -> PyResult<(T, T)> {
+    Ok((lo.bar_span(width, align)?.0, hi.bar_span(width, align)?.1))
+}
+
+/// The range of the position axis covered by the bars at `positions`.
+pub fn position_extent(
+    py: Python,
+    positions: &Series,
+    dtype: Dtype,
+    width: &Option<BarWidth>,
+    align: Align,
+) -> PyResult<Option<RangeEnum>> {
+    let extent = match range::series_extent(py, positions, dtype)? {
+        Some(extent) => extent,
+        None => return Ok(None),
+    };
+    Ok(Some(match extent {
+        RangeEnum::F64(a, b) => {
+            let (a, b) = spans(a, b, width, align)?;
+            RangeEnum::F64(a, b)
+        }
+        RangeEnum::F32(a, b) => {
+            let (a, b) = spans(a, b, width, align)?;
+            RangeEnum::F32(a, b)
+        }
+        RangeEnum::I64(a, b) => {
+            let (a, b) = spans(a, b, width, align)?;
+            RangeEnum::I64(a, b)
+        }
+        RangeEnum::I32(a, b) => {
+            let (a, b) = spans(a, b, width, align)?;
+            RangeEnum::I32(a, b)
+        }
+        RangeEnum::DateTime(a, b) => {
+            let (a, b) = spans(a, b, width, align)?;
+            RangeEnum::DateTime(a, b)
+        }
+        RangeEnum::Date(a, b) => {
+            let (a, b) = spans(a, b, width, align)?;
+            RangeEnum::Date(a, b)
+        }
+        RangeEnum::Time(a, b) => {
+            let (a, b) = spans(a, b, width, align)?;
+            RangeEnum::Time(a, b)
+        }
+        RangeEnum::Duration(a, b) => {
+            let (a, b) = spans(a, b, width, align)?;
+            RangeEnum::Duration(a, b)
+        }
+        // bars fit in the segments of their categories
+        RangeEnum::Category(names) => RangeEnum::Category(names),
+    }))
+}
+
+/// The range of the length axis covered by the bars, including their baselines.
+pub fn length_extent(
+    py: Python,
+    lengths: &Series,
+    baseline: &Baseline,
+    dtype: Dtype,
+) -> PyResult<Option<RangeEnum>> {
+    let ends = baseline
+        .values(py)?
+        .zip(lengths.iter_f64(py)?)
+        .flat_map(|(b, h)| vec![b, b + h])
+        .filter(|v| !v.is_nan());
+    match range::min_max(ends) {
+        Some((lo, hi)) => Ok(Some(numeric_range(lo, hi, dtype)?)),
+        None => Ok(None),
+    }
+}
+
+/// The range (lo, hi) on a numeric length axis.
+pub fn numeric_range(lo: f64, hi: f64, dtype: Dtype) -> PyResult<RangeEnum> {
+    Ok(match dtype {
+        Dtype::F64 => RangeEnum::F64(lo, hi),
+        Dtype::F32 => RangeEnum::F32(lo as f32, hi as f32),
+        Dtype::I64 => RangeEnum::I64(lo.floor() as i64, hi.ceil() as i64),
+        Dtype::I32 => RangeEnum::I32(lo.floor() as i32, hi.ceil() as i32),
+        _ => return Err(length_not_numeric(dtype)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn day_widths() {
+        assert_eq!(days(0.5).unwrap(), Duration::hours(12));
+        assert!(days(1e6).is_err());
+        assert!(days(f64::NAN).is_err());
+        assert_eq!(whole_days(-3).unwrap(), Duration::days(-3));
+        assert!(whole_days(i64::MIN).is_err());
+    }
+
+    #[test]
+    fn spans_out_of_range() {
+        let day = Duration::days(1);
+        let last = chrono::naive::MAX_DATE.and_hms(0, 0, 0);
+        assert!(align_span(last, day, Align::Edge, |d| d / 2).is_err());
+        let first = chrono::naive::MIN_DATE.and_hms(0, 0, 0);
+        assert!(align_span(first, day, Align::Center, |d| d / 2).is_err());
+        assert_eq!(
+            align_span(first, day, Align::Edge, |d| d / 2).unwrap(),
+            (first, first + day),
+        );
+    }
+
+    #[test]
+    fn times_stay_in_the_day() {
+        let t = NaiveTime::from_hms(22, 0, 0);
+        assert_eq!(
+            align_span(t, Duration::hours(1), Align::Edge, |d| d / 2).unwrap(),
+            (t, NaiveTime::from_hms(23, 0, 0)),
+        );
+        assert!(align_span(t, Duration::hours(3), Align::Edge, |d| d / 2).is_err());
+        let t = NaiveTime::from_hms(0, 30, 0);
+        assert!(align_span(t, Duration::hours(2), Align::Center, |d| d / 2).is_err());
+    }
+}
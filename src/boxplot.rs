@@ -0,0 +1,280 @@
+//! Box plots and violin plots of groups of values.
+//! `Chart.boxplot` and `Chart.violin` draw a glyph per group at its position on the x-axis.
+//!
+//! The statistics are computed once when the method is called, so that a chart keeps
+//! a few numbers per group instead of the data.
+//! plotters' `Boxplot` is limited to f32 values and 1.5 IQR fences, so the glyphs are drawn
+//! from rectangles, paths and polygons instead.
+
+use crate::range;
+use crate::Series;
+use pyo3::prelude::*;
+
+/// One group or a list of groups. Accepts a series, or a list of series.
+pub struct Groups(pub Vec<Series>);
+
+impl<'source> FromPyObject<'source> for Groups {
+    fn extract(x: &'source PyAny) -> PyResult<Self> {
+        if let Ok(s) = x.extract::<Series>() {
+            return Ok(Groups(vec![s]));
+        }
+        Ok(Groups(x.extract()?))
+    }
+}
+
+impl Groups {
+    /// The values of each group, sorted. NaN and infinities are left out.
+    pub fn sorted(&self, py: Python) -> PyResult<Vec<Vec<f64>>> {
+        self.0
+            .iter()
+            .map(|s| Ok(finite_sorted(s.iter_f64(py)?)))
+            .collect()
+    }
+}
+
+/// The finite values, sorted.
+fn finite_sorted(values: impl Iterator<Item = f64>) -> Vec<f64> {
+    let mut values: Vec<f64> = values.filter(|v| v.is_finite()).collect();
+    values.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap());
+    values
+}
+
+/// The `pct` percentile of sorted values with linear interpolation, as numpy.percentile.
+fn percentile(sorted: &[f64], pct: f64) -> f64 {
+    let rank = pct / 100.0 * (sorted.len() - 1) as f64;
+    let (i, d) = (rank.floor() as usize, rank.fract());
+    match sorted.get(i + 1) {
+        Some(next) => sorted[i] + (next - sorted[i]) * d,
+        None => sorted[i],
+    }
+}
+
+pub struct BoxStats {
+    pub q1: f64,
+    pub median: f64,
+    pub q3: f64,
+    /// The most extreme values within `whis` IQR from the box.
+    pub whisker_lo: f64,
+    pub whisker_hi: f64,
+    /// The values beyond the whiskers.
+    pub outliers: Vec<f64>,
+}
+
+impl BoxStats {
+    /// None if there is no value.
+    pub fn new(sorted: &[f64], whis: f64) -> Option<Self> {
+        if sorted.is_empty() {
+            return None;
+        }
+        let q1 = percentile(sorted, 25.0);
+        let q3 = percentile(sorted, 75.0);
+        let (lo, hi) = (q1 - whis * (q3 - q1), q3 + whis * (q3 - q1));
+        let inside = |v: &&f64| lo <= **v && **v <= hi;
+        Some(BoxStats {
+            q1,
+            median: percentile(sorted, 50.0),
+            q3,
+            // whiskers don't reach into the box, even if whis is negative
+            whisker_lo: sorted.iter().find(inside).map_or(q1, |v| v.min(q1)),
+            whisker_hi: sorted.iter().rev().find(inside).map_or(q3, |v| v.max(q3)),
+            outliers: sorted.iter().cloned().filter(|v| !inside(&v)).collect(),
+        })
+    }
+
+    /// (min, max) of the drawn values.
+    pub fn extent(&self, show_outliers: bool) -> (f64, f64) {
+        match (show_outliers, self.outliers.first(), self.outliers.last()) {
+            (true, Some(first), Some(last)) => {
+                (first.min(self.whisker_lo), last.max(self.whisker_hi))
+            }
+            _ => (self.whisker_lo, self.whisker_hi),
+        }
+    }
+}
+
+/// The kernel density estimate of a group, between its min and max.
+pub struct Violin {
+    pub values: Vec<f64>,
+    /// The density at each value, scaled to a maximum of 1.
+    pub densities: Vec<f64>,
+    pub median: f64,
+}
+
+/// The number of values a violin is evaluated at.
+const VIOLIN_POINTS: usize = 100;
+/// Larger groups are binned before the density is estimated.
+const KDE_BINS: usize = 1024;
+
+impl Violin {
+    /// A gaussian KDE. `bandwidth` is the kernel standard deviation, Scott's rule by default.
+    /// None if there is no value. A group of a single value is a flat line.
+    pub fn new(sorted: &[f64], bandwidth: Option<f64>) -> Option<Self> {
+        let n = sorted.len();
+        let (lo, hi) = (*sorted.first()?, *sorted.last()?);
+        let median = percentile(sorted, 50.0);
+        let bandwidth = bandwidth.unwrap_or_else(|| {
+            let mean = sorted.iter().sum::<f64>() / n as f64;
+            let var =
+                sorted.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (n.max(2) - 1) as f64;
+            var.sqrt() * (n as f64).powf(-0.2)
+        });
+        if lo == hi {
+            return Some(Violin {
+                values: vec![lo, hi],
+                densities: vec![1.0, 1.0],
+                median,
+            });
+        }
+
+        // (value, weight) of the kernels
+        let kernels: Vec<(f64, f64)> = if n <= KDE_BINS * 2 {
+            sorted.iter().map(|&v| (v, 1.0)).collect()
+        } else {
+            let step = (hi - lo) / KDE_BINS as f64;
+            let mut counts = vec![0.0; KDE_BINS];
+            for v in sorted {
+                counts[(((v - lo) / step) as usize).min(KDE_BINS - 1)] += 1.0;
+            }
+            counts
+                .into_iter()
+                .enumerate()
+                .filter(|(_, c)| *c > 0.0)
+                .map(|(i, c)| (lo + (i as f64 + 0.5) * step, c))
+                .collect()
+        };
+
+        let values: Vec<f64> = (0..VIOLIN_POINTS)
+            .map(|i| lo + (hi - lo) * i as f64 / (VIOLIN_POINTS - 1) as f64)
+            .collect();
+        let densities: Vec<f64> = values
+            .iter()
+            .map(|x| {
+                kernels
+                    .iter()
+                    .map(|(v, w)| w * (-0.5 * ((x - v) / bandwidth).powi(2)).exp())
+                    .sum()
+            })
+            .collect();
+        let max = densities.iter().cloned().fold(0.0, f64::max);
+        Some(Violin {
+            values,
+            densities: densities.iter().map(|d| d / max).collect(),
+            median,
+        })
+    }
+}
+
+pub enum Glyphs {
+    Boxes {
+        stats: Vec<Option<BoxStats>>,
+        show_outliers: bool,
+    },
+    Violins(Vec<Option<Violin>>),
+}
+
+/// Glyphs of groups. `positions` has a value for each group.
+pub struct BoxSeries {
+    pub positions: Series,
+    /// in axis units, or days on chrono axes.
+    pub width: f64,
+    pub glyphs: Glyphs,
+}
+
+impl BoxSeries {
+    /// (min, max) of the drawn values of all groups. None if every group is empty.
+    pub fn value_extent(&self) -> Option<(f64, f64)> {
+        let extents: Vec<(f64, f64)> = match &self.glyphs {
+            Glyphs::Boxes {
+                stats,
+                show_outliers,
+            } => stats
+                .iter()
+                .flatten()
+                .map(|s| s.extent(*show_outliers))
+                .collect(),
+            Glyphs::Violins(violins) => violins
+                .iter()
+                .flatten()
+                .map(|v| (v.values[0], v.values[v.values.len() - 1]))
+                .collect(),
+        };
+        range::min_max(extents.into_iter().flat_map(|(lo, hi)| vec![lo, hi]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_finite_values_are_sorted() {
+        let values = vec![3.0, f64::NAN, f64::INFINITY, -1.0, f64::NEG_INFINITY, 2.0];
+        assert_eq!(finite_sorted(values.into_iter()), vec![-1.0, 2.0, 3.0]);
+    }
+
+    #[test]
+    fn percentiles_match_numpy() {
+        let sorted = [1.0, 2.0, 3.0, 4.0];
+        assert_eq!(percentile(&sorted, 0.0), 1.0);
+        assert_eq!(percentile(&sorted, 25.0), 1.75);
+        assert_eq!(percentile(&sorted, 50.0), 2.5);
+        assert_eq!(percentile(&sorted, 75.0), 3.25);
+        assert_eq!(percentile(&sorted, 100.0), 4.0);
+        assert_eq!(percentile(&[7.0], 50.0), 7.0);
+    }
+
+    #[test]
+    fn box_stats() {
+        let sorted = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 100.0];
+        let stats = BoxStats::new(&sorted, 1.5).unwrap();
+        assert_eq!((stats.q1, stats.median, stats.q3), (3.25, 5.5, 7.75));
+        assert_eq!((stats.whisker_lo, stats.whisker_hi), (1.0, 9.0));
+        assert_eq!(stats.outliers, vec![100.0]);
+        assert_eq!(stats.extent(true), (1.0, 100.0));
+        assert_eq!(stats.extent(false), (1.0, 9.0));
+        assert!(BoxStats::new(&[], 1.5).is_none());
+    }
+
+    #[test]
+    fn whiskers_stay_outside_the_box() {
+        let sorted = [1.0, 2.0, 3.0, 4.0, 5.0];
+        let stats = BoxStats::new(&sorted, -1.0).unwrap();
+        assert_eq!((stats.whisker_lo, stats.whisker_hi), (stats.q1, stats.q3));
+        assert_eq!(stats.outliers, sorted.to_vec());
+    }
+
+    #[test]
+    fn violins() {
+        let sorted = [1.0, 2.0, 2.0, 3.0];
+        let violin = Violin::new(&sorted, Some(0.5)).unwrap();
+        assert_eq!(violin.values.len(), VIOLIN_POINTS);
+        assert_eq!(
+            (violin.values[0], violin.values[VIOLIN_POINTS - 1]),
+            (1.0, 3.0)
+        );
+        assert_eq!(violin.median, 2.0);
+        let max = violin.densities.iter().cloned().fold(0.0, f64::max);
+        assert_eq!(max, 1.0);
+        // symmetric values have a symmetric density, densest in the middle
+        let (first, last) = (violin.densities[0], violin.densities[VIOLIN_POINTS - 1]);
+        assert!((first - last).abs() < 1e-12);
+        assert!(first < 1.0);
+        assert!(Violin::new(&[], None).is_none());
+    }
+
+    #[test]
+    fn violin_of_a_single_value() {
+        let violin = Violin::new(&[4.0, 4.0], None).unwrap();
+        assert_eq!(violin.values, vec![4.0, 4.0]);
+        assert_eq!(violin.densities, vec![1.0, 1.0]);
+    }
+
+    #[test]
+    fn large_groups_are_binned() {
+        let sorted: Vec<f64> = (0..10 * KDE_BINS).map(|i| i as f64).collect();
+        let violin = Violin::new(&sorted, None).unwrap();
+        assert_eq!(violin.values.len(), VIOLIN_POINTS);
+        assert!(violin.densities.iter().all(|d| d.is_finite() && *d > 0.0));
+        assert_eq!(violin.median, 5.0 * KDE_BINS as f64 - 0.5);
+    }
+}
@@ -1,91 +1,381 @@
 //! A type-erased wrapper around plotters::DrawingArea.
+use numpy::PyArray3;
 use plotters::prelude::*;
 use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PySlice, PyTuple};
 // use plotters::coord::types::RangedCoordf64;
+use crate::backend::EzelBackend;
+use crate::color::ColorArg;
+use crate::error::{self, Error};
+use crate::layout::{self, Size, Split};
 use crate::Backend;
 use plotters::coord::Shift;
+use std::ops::Range;
+use std::os::raw::c_long;
 use std::sync::Arc;
 
 /// Canvas defines a drawing area for charts.
 #[pyclass(unsendable)]
-#[text_signature = "(width=1000, height=800)"]
+#[text_signature = "(width=1000, height=800, color=None, backend=\"bitmap\")"]
 pub struct Canvas {
     is_root: bool,
-    backend: Arc<Backend>, // this is shared by all canvas from the same root canvas
-    pub area: DrawingArea<BitMapBackend<'static>, Shift>, // self-reference backend
+    pub area: DrawingArea<EzelBackend, Shift>, // self-reference backend. dropped before backend.
+    pub backend: Arc<Backend>, // this is shared by all canvas from the same root canvas
+}
+
+impl Canvas {
+    fn encode(&self, format: image::ImageFormat) -> PyResult<Vec<u8>> {
+        self.backend
+            .encode(format)
+            .map_err(|source| Error::Image { source }.into())
+    }
+
+    /// A sub-canvas on the same backend.
+    fn sub_canvas(&self, area: DrawingArea<EzelBackend, Shift>) -> Self {
+        Self {
+            is_root: false,
+            backend: self.backend.clone(),
+            area,
+        }
+    }
+
+    /// The pixel ranges of the rows and columns of a grid, relative to the canvas.
+    fn grid_tracks(
+        &self,
+        nrows: usize,
+        ncols: usize,
+        hspace: Option<Size>,
+        wspace: Option<Size>,
+        width_ratios: Option<Vec<f64>>,
+        height_ratios: Option<Vec<f64>>,
+    ) -> PyResult<(Vec<Range<i32>>, Vec<Range<i32>>)> {
+        let (width, height) = self.area.dim_in_pixel();
+        // a size is never negative
+        let space = |size: Option<Size>, length| size.map_or(Ok(0), |v| v.in_pixels(length));
+        let columns = layout::tracks(
+            width,
+            ncols,
+            space(wspace, width)? as u32,
+            width_ratios.as_deref(),
+            "column",
+        )?;
+        let rows = layout::tracks(
+            height,
+            nrows,
+            space(hspace, height)? as u32,
+            height_ratios.as_deref(),
+            "row",
+        )?;
+        Ok((rows, columns))
+    }
+
+    /// The sub-canvas of the pixels `ys` x `xs`.
+    fn cell(&self, ys: Range<i32>, xs: Range<i32>) -> Self {
+        // the canvas is split 3x3 at the edges of the cell, and the center is the cell.
+        let area = self
+            .area
+            .split_by_breakpoints([xs.start, xs.end], [ys.start, ys.end])
+            .swap_remove(4);
+        self.sub_canvas(area)
+    }
+
+    fn sub_canvases<'py>(
+        &self,
+        py: Python<'py>,
+        areas: Vec<DrawingArea<EzelBackend, Shift>>,
+    ) -> &'py PyTuple {
+        PyTuple::new(
+            py,
+            areas
+                .into_iter()
+                .map(|area| self.sub_canvas(area).into_py(py)),
+        )
+    }
 }
 
 #[pymethods]
 impl Canvas {
     /// Creates a new root canvas.
+    ///
+    /// color: the background color (default: #eeeeee).
+    ///     a color name, hex string, rgb()/hsl() string, (r, g, b[, a]) tuple, or ez.Color.
+    /// backend: "bitmap" (default) or "svg".
+    ///     A bitmap canvas can be saved as png, jpg, bmp, ..
+    ///     A svg canvas is rendered into an in-memory string and saved as svg.
     #[new]
-    pub fn new(width: Option<usize>, height: Option<usize>, _color: Option<&str>) -> Self {
+    pub fn new(
+        width: Option<usize>,
+        height: Option<usize>,
+        color: Option<ColorArg>,
+        backend: Option<&str>,
+    ) -> PyResult<Self> {
         let width = width.unwrap_or(1000);
         let height = height.unwrap_or(800);
-        let backend = Arc::new(Backend::new(width, height));
+        let backend = match backend.unwrap_or("bitmap") {
+            "bitmap" => Backend::bitmap(width, height),
+            "svg" => Backend::svg(width, height),
+            other => {
+                return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                    "unknown backend {:?}, expected \"bitmap\" or \"svg\"",
+                    other
+                )))
+            }
+        };
+        let backend = Arc::new(backend);
         let area: DrawingArea<_, _> = (&backend.inner).into();
-        area.fill(&RGBColor(238, 238, 238)).unwrap();
-        Self {
+        match color {
+            Some(c) => area.fill(&c.0),
+            None => area.fill(&RGBColor(238, 238, 238)),
+        }
+        .map_err(error::render)?;
+        Ok(Self {
             is_root: true,
             backend,
             area,
-        }
+        })
     }
 
-    /// split the canvas into two sub-canvas.
+    /// split the canvas into a tuple of sub-canvas, left to right.
     /// the original canvas still can be used.
-    /// If `pixel` is None, the canvas is evenly split.
-    #[text_signature = "(pixel=None)"]
-    pub fn split_horizontally(&self, pixel: Option<u32>) -> (Self, Self) {
-        let pixel = pixel.unwrap_or_else(|| self.area.dim_in_pixel().0 / 2);
-        let (a1, a2) = self.area.split_horizontally(pixel);
-        (
-            Self {
-                is_root: false,
-                backend: self.backend.clone(),
-                area: a1,
-            },
-            Self {
-                is_root: false,
-                backend: self.backend.clone(),
-                area: a2,
-            },
-        )
+    /// at: the width of the left part, in px (e.g. 300), a fraction (0.3) or a percentage ("30%").
+    ///     Or a list of width ratios of N parts, e.g. [1, 2, 1].
+    ///     If `at` is None, the canvas is evenly split in two.
+    #[text_signature = "(at=None)"]
+    pub fn split_horizontally<'py>(
+        &self,
+        py: Python<'py>,
+        at: Option<Split>,
+    ) -> PyResult<&'py PyTuple> {
+        let xs = layout::breakpoints(self.area.dim_in_pixel().0, at)?;
+        let areas = self.area.split_by_breakpoints(xs, &[] as &[i32]);
+        Ok(self.sub_canvases(py, areas))
     }
 
-    /// split the canvas into two sub-canvas.
+    /// split the canvas into a tuple of sub-canvas, top to bottom.
     /// the original canvas still can be used.
-    /// If `pixel` is None, the canvas is evenly split.
-    #[text_signature = "(pixel=None)"]
-    pub fn split_vertically(&self, pixel: Option<u32>) -> (Self, Self) {
-        let pixel = pixel.unwrap_or_else(|| self.area.dim_in_pixel().1 / 2);
-        let (a1, a2) = self.area.split_vertically(pixel);
-        (
-            Self {
-                is_root: false,
-                backend: self.backend.clone(),
-                area: a1,
-            },
-            Self {
-                is_root: false,
-                backend: self.backend.clone(),
-                area: a2,
-            },
-        )
+    /// at: the height of the top part, in px (e.g. 300), a fraction (0.3) or a percentage ("30%").
+    ///     Or a list of height ratios of N parts, e.g. [1, 2, 1].
+    ///     If `at` is None, the canvas is evenly split in two.
+    #[text_signature = "(at=None)"]
+    pub fn split_vertically<'py>(
+        &self,
+        py: Python<'py>,
+        at: Option<Split>,
+    ) -> PyResult<&'py PyTuple> {
+        let ys = layout::breakpoints(self.area.dim_in_pixel().1, at)?;
+        let areas = self.area.split_by_breakpoints(&[] as &[i32], ys);
+        Ok(self.sub_canvases(py, areas))
+    }
+
+    /// Split the canvas into a grid of nrows x ncols cells, and return them as a 2D list.
+    /// `grid[i][j]` is the sub-canvas of row i and column j.
+    /// The original canvas still can be used. `grid_span` makes a sub-canvas spanning cells.
+    ///
+    /// hspace: the space between rows, in px, a fraction or a percentage of the height (default 0).
+    /// wspace: the space between columns, in px, a fraction or a percentage of the width (default 0).
+    /// width_ratios: the relative width of each column, e.g. [2, 1, 1]. Defaults to even widths.
+    /// height_ratios: the relative height of each row. Defaults to even heights.
+    #[text_signature = "(nrows, ncols, hspace=0, wspace=0, width_ratios=None, height_ratios=None)"]
+    pub fn grid(
+        &self,
+        nrows: usize,
+        ncols: usize,
+        hspace: Option<Size>,
+        wspace: Option<Size>,
+        width_ratios: Option<Vec<f64>>,
+        height_ratios: Option<Vec<f64>>,
+    ) -> PyResult<Vec<Vec<Canvas>>> {
+        let (rows, columns) =
+            self.grid_tracks(nrows, ncols, hspace, wspace, width_ratios, height_ratios)?;
+        Ok(rows
+            .iter()
+            .map(|ys| {
+                columns
+                    .iter()
+                    .map(|xs| self.cell(ys.clone(), xs.clone()))
+                    .collect()
+            })
+            .collect())
+    }
+
+    /// The sub-canvas spanning `rows` and `columns` of the grid made by `grid` with the same arguments.
+    /// rows, columns: an index or a slice of step 1, e.g. `canvas.grid_span(3, 4, slice(0, 2), 1)`
+    ///     spans the rows 0 and 1 of column 1.
+    #[text_signature = "(nrows, ncols, rows, columns, hspace=0, wspace=0, width_ratios=None, height_ratios=None)"]
+    pub fn grid_span(
+        &self,
+        nrows: usize,
+        ncols: usize,
+        rows: &PyAny,
+        columns: &PyAny,
+        hspace: Option<Size>,
+        wspace: Option<Size>,
+        width_ratios: Option<Vec<f64>>,
+        height_ratios: Option<Vec<f64>>,
+    ) -> PyResult<Canvas> {
+        let (row_tracks, column_tracks) =
+            self.grid_tracks(nrows, ncols, hspace, wspace, width_ratios, height_ratios)?;
+        let rows = select(rows, nrows, "row")?;
+        let columns = select(columns, ncols, "column")?;
+        Ok(self.cell(
+            row_tracks[rows.start].start..row_tracks[rows.end - 1].end,
+            column_tracks[columns.start].start..column_tracks[columns.end - 1].end,
+        ))
+    }
+
+    /// Encode the canvas into an image file in memory and return the bytes.
+    /// Currently you can encode only the root canvas.
+    /// format: "png" (default), "jpeg", "bmp", "gif", .. for a bitmap canvas.
+    ///     A svg canvas is always encoded as a utf-8 svg document.
+    #[text_signature = "(format=\"png\")"]
+    pub fn to_bytes<'py>(&self, py: Python<'py>, format: Option<&str>) -> PyResult<&'py PyBytes> {
+        if !self.is_root {
+            return Err(pyo3::exceptions::PyValueError::new_err(
+                "only root canvas can be encoded",
+            ));
+        }
+        self.backend.render_deferred(py)?;
+        let format = format.unwrap_or("png");
+        if self.backend.is_svg() {
+            if format != "svg" {
+                return Err(pyo3::exceptions::PyValueError::new_err(
+                    "a canvas created with backend=\"svg\" can be encoded only as svg",
+                ));
+            }
+            return Ok(PyBytes::new(py, self.backend.svg_document().as_bytes()));
+        }
+        let image_format = image::ImageFormat::from_extension(format).ok_or_else(|| {
+            pyo3::exceptions::PyValueError::new_err(format!("unknown image format {:?}", format))
+        })?;
+        Ok(PyBytes::new(py, &self.encode(image_format)?))
+    }
+
+    /// Encode the canvas as png and return the bytes.
+    #[text_signature = "()"]
+    pub fn to_png_bytes<'py>(&self, py: Python<'py>) -> PyResult<&'py PyBytes> {
+        self.to_bytes(py, Some("png"))
+    }
+
+    /// Copy the pixels of the canvas into a numpy.ndarray of shape (height, width, 3) and dtype uint8.
+    /// Currently you can copy only the root canvas of a bitmap backend.
+    #[text_signature = "()"]
+    pub fn to_numpy<'py>(&self, py: Python<'py>) -> PyResult<&'py PyArray3<u8>> {
+        if !self.is_root {
+            return Err(pyo3::exceptions::PyValueError::new_err(
+                "only root canvas can be copied",
+            ));
+        }
+        if self.backend.is_svg() {
+            return Err(pyo3::exceptions::PyValueError::new_err(
+                "a canvas created with backend=\"svg\" has no pixels",
+            ));
+        }
+        self.backend.render_deferred(py)?;
+        let (w, h) = self.backend.inner.borrow().get_size();
+        numpy::PyArray1::from_slice(py, &self.backend.buffer).reshape([h as usize, w as usize, 3])
     }
 
     /// Save the canvas to an image file.
     /// Currently you can save only the root canvas.
+    /// The file format is selected by the extension of `path`.
+    /// A svg canvas can be saved only as svg, and a bitmap canvas only as raster images.
     #[text_signature = "(path)"]
-    pub fn save(&self, path: &str) -> PyResult<()> {
+    pub fn save(&self, py: Python, path: &str) -> PyResult<()> {
         if !self.is_root {
             return Err(pyo3::exceptions::PyValueError::new_err(
                 "only root canvas can save",
             ));
         }
+        let is_svg_path = path.to_lowercase().ends_with(".svg");
+        if self.backend.is_svg() != is_svg_path {
+            return Err(pyo3::exceptions::PyValueError::new_err(if is_svg_path {
+                "only a canvas created with backend=\"svg\" can be saved as svg"
+            } else {
+                "a canvas created with backend=\"svg\" can be saved only as svg"
+            }));
+        }
+        self.backend.render_deferred(py)?;
+        if is_svg_path {
+            std::fs::write(path, self.backend.svg_document()).map_err(|source| Error::Io {
+                path: path.to_string(),
+                source,
+            })?;
+            return Ok(());
+        }
         let (w, h) = self.backend.inner.borrow().get_size();
-        image::save_buffer(path, &self.backend.buffer, w, h, image::ColorType::Rgb8).unwrap();
-        // self.inner.save
+        image::save_buffer(path, &self.backend.buffer, w, h, image::ColorType::Rgb8).map_err(
+            |e| match e {
+                image::ImageError::IoError(source) => Error::Io {
+                    path: path.to_string(),
+                    source,
+                },
+                source => Error::Image { source },
+            },
+        )?;
         Ok(())
     }
+
+    /// Jupyter rich display of a bitmap canvas.
+    /// A sub-canvas displays its whole root canvas.
+    pub fn _repr_png_<'py>(&self, py: Python<'py>) -> PyResult<Option<&'py PyBytes>> {
+        if self.backend.is_svg() {
+            return Ok(None);
+        }
+        self.backend.render_deferred(py)?;
+        Ok(Some(PyBytes::new(
+            py,
+            &self.encode(image::ImageFormat::Png)?,
+        )))
+    }
+
+    /// Jupyter rich display of a svg canvas.
+    /// A sub-canvas displays its whole root canvas.
+    pub fn _repr_svg_(&self, py: Python) -> PyResult<Option<String>> {
+        if !self.backend.is_svg() {
+            return Ok(None);
+        }
+        self.backend.render_deferred(py)?;
+        Ok(Some(self.backend.svg_document()))
+    }
+
+    /// Jupyter rich display as html. Bitmaps are embedded as base64 png.
+    /// A sub-canvas displays its whole root canvas.
+    pub fn _repr_html_(&self, py: Python) -> PyResult<String> {
+        self.backend.render_deferred(py)?;
+        if self.backend.is_svg() {
+            return Ok(self.backend.svg_document());
+        }
+        Ok(format!(
+            "<img src=\"data:image/png;base64,{}\"/>",
+            base64::encode(self.encode(image::ImageFormat::Png)?)
+        ))
+    }
+}
+
+/// The rows or columns selected by an int or a slice of step 1.
+fn select(key: &PyAny, len: usize, what: &str) -> PyResult<Range<usize>> {
+    if let Ok(slice) = key.downcast::<PySlice>() {
+        let indices = slice.indices(len as c_long)?;
+        if indices.step != 1 {
+            return Err(pyo3::exceptions::PyValueError::new_err(
+                "a grid slice should have step 1",
+            ));
+        }
+        if indices.slicelength <= 0 {
+            return Err(pyo3::exceptions::PyIndexError::new_err(format!(
+                "the {} slice is empty",
+                what
+            )));
+        }
+        return Ok(indices.start as usize..indices.stop as usize);
+    }
+    let i: isize = key.extract()?;
+    let j = if i < 0 { i + len as isize } else { i };
+    if j < 0 || j >= len as isize {
+        return Err(pyo3::exceptions::PyIndexError::new_err(format!(
+            "{} index {} out of range for {} {}s",
+            what, i, len, what
+        )));
+    }
+    Ok(j as usize..j as usize + 1)
 }
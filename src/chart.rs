@@ -1,52 +1,1790 @@
+use crate::annotation::{self, Annotation, Arrow, TextLabel, Value};
+use crate::backend::{Deferred, EzelBackend};
+use crate::bar::{self, Align, BarSeries, BarValue, BarWidth, Baseline};
+use crate::boxplot::{BoxSeries, BoxStats, Glyphs, Groups, Violin};
+use crate::color::{CmapArg, ColorArg, Colormap};
+use crate::coord::{CategoryCoord, F64Coord, RangedTime, SeriesCoord};
+use crate::error::{self, Error};
+use crate::errorbar::{self, ErrArg, ErrorBarSeries, ErrorValue};
+use crate::fill::{FillSeries, Mask};
 use crate::hack::static_reference;
-use crate::range::{Range, RangeEnum};
+use crate::heatmap::{self, CellSeries, CellValue, Grid, Image};
+use crate::hist::{self, Bars, BinValue, Bins};
+use crate::layout::Size;
+use crate::marker::{Marker, MarkerElement, PointColorArg, ScatterSeries, SizeArg};
+use crate::ohlc::{self, Glyph, OhlcBar, OhlcSeries};
+use crate::range::{self, Range, RangeEnum, Scale};
+use crate::step::{StemSeries, Step, StepPath};
+use crate::tick::{TickFormat, TickValue};
 use crate::Canvas;
 use crate::Dtype;
 use crate::Series;
-use plotters::coord::types::{RangedCoordf64, RangedDateTime};
+use chrono::{NaiveDate, NaiveDateTime};
+use plotters::chart::SeriesAnno;
+use plotters::coord::types::{RangedCoordf32, RangedCoordi32, RangedCoordi64, RangedDateTime};
 use plotters::prelude::*;
+use plotters::style::text_anchor::{HPos, Pos, VPos};
+use plotters::style::RGBAColor;
 use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+use std::cell::RefCell;
+use std::rc::Rc;
 
+type Ctx<X, Y> = ChartContext<'static, EzelBackend, Cartesian2d<X, Y>>;
+
+/// Charts on the x-axis `X`, for each type of the y-axis.
+enum Charts<X: Ranged> {
+    F64(Ctx<X, F64Coord>),
+    F32(Ctx<X, RangedCoordf32>),
+    I64(Ctx<X, RangedCoordi64>),
+    I32(Ctx<X, RangedCoordi32>),
+    DateTime(Ctx<X, RangedDateTime<NaiveDateTime>>),
+    Date(Ctx<X, RangedDate<NaiveDate>>),
+    Time(Ctx<X, RangedTime>),
+    Duration(Ctx<X, RangedDuration>),
+    Category(Ctx<X, CategoryCoord>),
+}
+
+/// Charts for each type of the x-axis.
 enum TypedChart {
-    F64F64(
-        ChartContext<'static, BitMapBackend<'static>, Cartesian2d<RangedCoordf64, RangedCoordf64>>,
-    ),
-    DateTimeF64(
-        ChartContext<
-            'static,
-            BitMapBackend<'static>,
-            Cartesian2d<RangedDateTime<chrono::NaiveDateTime>, RangedCoordf64>,
-        >,
-    ),
-    DateF64(
-        ChartContext<
-            'static,
-            BitMapBackend<'static>,
-            Cartesian2d<RangedDate<chrono::NaiveDate>, RangedCoordf64>,
-        >,
-    ),
-    DurationF64(
-        ChartContext<'static, BitMapBackend<'static>, Cartesian2d<RangedDuration, RangedCoordf64>>,
-    ),
+    F64(Charts<F64Coord>),
+    F32(Charts<RangedCoordf32>),
+    I64(Charts<RangedCoordi64>),
+    I32(Charts<RangedCoordi32>),
+    DateTime(Charts<RangedDateTime<NaiveDateTime>>),
+    Date(Charts<RangedDate<NaiveDate>>),
+    Time(Charts<RangedTime>),
+    Duration(Charts<RangedDuration>),
+    Category(Charts<CategoryCoord>),
+}
+
+/// Evaluates `$body` with `$c` bound to the ChartContext of a TypedChart.
+/// `$body` is compiled for each pair of axis types.
+macro_rules! with_chart {
+    ($chart:expr, $c:ident => $body:expr) => {
+        match $chart {
+            TypedChart::F64(charts) => with_chart!(@y charts, $c => $body),
+            TypedChart::F32(charts) => with_chart!(@y charts, $c => $body),
+            TypedChart::I64(charts) => with_chart!(@y charts, $c => $body),
+            TypedChart::I32(charts) => with_chart!(@y charts, $c => $body),
+            TypedChart::DateTime(charts) => with_chart!(@y charts, $c => $body),
+            TypedChart::Date(charts) => with_chart!(@y charts, $c => $body),
+            TypedChart::Time(charts) => with_chart!(@y charts, $c => $body),
+            TypedChart::Duration(charts) => with_chart!(@y charts, $c => $body),
+            TypedChart::Category(charts) => with_chart!(@y charts, $c => $body),
+        }
+    };
+    (@y $charts:expr, $c:ident => $body:expr) => {
+        match $charts {
+            Charts::F64($c) => $body,
+            Charts::F32($c) => $body,
+            Charts::I64($c) => $body,
+            Charts::I32($c) => $body,
+            Charts::DateTime($c) => $body,
+            Charts::Date($c) => $body,
+            Charts::Time($c) => $body,
+            Charts::Duration($c) => $body,
+            Charts::Category($c) => $body,
+        }
+    };
+}
+
+impl TypedChart {
+    fn x_dtype(&self) -> Dtype {
+        match self {
+            TypedChart::F64(..) => Dtype::F64,
+            TypedChart::F32(..) => Dtype::F32,
+            TypedChart::I64(..) => Dtype::I64,
+            TypedChart::I32(..) => Dtype::I32,
+            TypedChart::DateTime(..) => Dtype::NaiveDateTime,
+            TypedChart::Date(..) => Dtype::NaiveDate,
+            TypedChart::Time(..) => Dtype::NaiveTime,
+            TypedChart::Duration(..) => Dtype::Duration,
+            TypedChart::Category(..) => Dtype::String,
+        }
+    }
+}
+
+/// The x and y values of the series, typed by the coordinates of the chart.
+fn xy<'a, X: SeriesCoord, Y: SeriesCoord>(
+    c: &Ctx<X, Y>,
+    py: Python<'a>,
+    x: &'a Series,
+    y: &'a Series,
+) -> PyResult<Box<dyn Iterator<Item = (X::ValueType, Y::ValueType)> + 'a>>
+where
+    X::ValueType: 'a,
+    Y::ValueType: 'a,
+{
+    let coord = c.as_coord_spec();
+    Ok(Box::new(
+        coord
+            .x_spec()
+            .values(py, x)?
+            .zip(coord.y_spec().values(py, y)?),
+    ))
+}
+
+/// Checks that y has a value for every x.
+fn same_len(py: Python, x: &Series, y: &Series) -> PyResult<()> {
+    if x.len(py) != y.len(py) {
+        return Err(Error::LengthMismatch {
+            what: "y".to_string(),
+            expected: x.len(py),
+            actual: y.len(py),
+        }
+        .into());
+    }
+    Ok(())
+}
+
+/// Draws (left, right, height) bars from y = 0. The x dtype of the bars should match the chart.
+fn draw_bars<X: Ranged, Y: Ranged>(
+    c: &mut Ctx<X, Y>,
+    bars: &Bars,
+    style: &ShapeStyle,
+    label: &Option<String>,
+) -> PyResult<()>
+where
+    X::ValueType: BinValue + Clone + 'static,
+    Y::ValueType: BarValue + Clone + 'static,
+{
+    let bars = X::ValueType::bars(bars).unwrap_or(&[]);
+    let zero = Y::ValueType::from_length(0.0)?;
+    let rects = bars
+        .iter()
+        .map(|(l, r, h)| {
+            Ok(Rectangle::new(
+                [
+                    (l.clone(), zero.clone()),
+                    (r.clone(), Y::ValueType::from_length(*h)?),
+                ],
+                style.clone(),
+            ))
+        })
+        .collect::<PyResult<Vec<_>>>()?;
+    let anno = c.draw_series(rects).map_err(error::render)?;
+    annotate(anno, label, Swatch::Bar(style.clone()));
+    Ok(())
+}
+
+/// Draws a step line through the points. Points of NaN y are left out.
+fn draw_step<X: SeriesCoord, Y: SeriesCoord>(
+    c: &mut Ctx<X, Y>,
+    py: Python,
+    x: &Series,
+    y: &Series,
+    step: Step,
+    style: &ShapeStyle,
+    label: &Option<String>,
+) -> PyResult<()>
+where
+    X::ValueType: Clone + 'static,
+    Y::ValueType: ErrorValue + Clone + 'static,
+{
+    let points: Vec<_> = xy(c, py, x, y)?.filter(|(_, y)| !y.is_nan()).collect();
+    let anno = c
+        .draw_series(std::iter::once(StepPath::new(points, step, style.clone())))
+        .map_err(error::render)?;
+    annotate(anno, label, Swatch::Line(style.clone()));
+    Ok(())
+}
+
+/// Draws a reference line or span across the chart, or a text with an optional arrow.
+fn draw_annotation<X: SeriesCoord, Y: SeriesCoord>(
+    c: &mut Ctx<X, Y>,
+    py: Python,
+    annotation: &Annotation,
+    style: &ShapeStyle,
+    label: &Option<String>,
+) -> PyResult<()>
+where
+    X::ValueType: Clone + 'static,
+    Y::ValueType: Clone + 'static,
+{
+    let coord = c.as_coord_spec();
+    let (x, y) = (coord.x_spec(), coord.y_spec());
+    let (x_range, y_range) = (x.range(), y.range());
+    let (anno, swatch) = match annotation {
+        Annotation::HLine(v) => {
+            let lines: Vec<_> = y
+                .values(py, &v.0)?
+                .map(|y| vec![(x_range.start.clone(), y.clone()), (x_range.end.clone(), y)])
+                .collect();
+            let anno = c
+                .draw_series(
+                    lines
+                        .into_iter()
+                        .map(|l| PathElement::new(l, style.clone())),
+                )
+                .map_err(error::render)?;
+            (anno, Swatch::Line(style.clone()))
+        }
+        Annotation::VLine(v) => {
+            let lines: Vec<_> = x
+                .values(py, &v.0)?
+                .map(|x| vec![(x.clone(), y_range.start.clone()), (x, y_range.end.clone())])
+                .collect();
+            let anno = c
+                .draw_series(
+                    lines
+                        .into_iter()
+                        .map(|l| PathElement::new(l, style.clone())),
+                )
+                .map_err(error::render)?;
+            (anno, Swatch::Line(style.clone()))
+        }
+        Annotation::HSpan(lo, hi) => {
+            let spans: Vec<_> = y
+                .values(py, &lo.0)?
+                .zip(y.values(py, &hi.0)?)
+                .map(|(lo, hi)| [(x_range.start.clone(), lo), (x_range.end.clone(), hi)])
+                .collect();
+            let anno = c
+                .draw_series(spans.into_iter().map(|r| Rectangle::new(r, style.clone())))
+                .map_err(error::render)?;
+            (anno, Swatch::Bar(style.clone()))
+        }
+        Annotation::VSpan(lo, hi) => {
+            let spans: Vec<_> = x
+                .values(py, &lo.0)?
+                .zip(x.values(py, &hi.0)?)
+                .map(|(lo, hi)| [(lo, y_range.start.clone()), (hi, y_range.end.clone())])
+                .collect();
+            let anno = c
+                .draw_series(spans.into_iter().map(|r| Rectangle::new(r, style.clone())))
+                .map_err(error::render)?;
+            (anno, Swatch::Bar(style.clone()))
+        }
+        Annotation::Text {
+            label: text,
+            xy: target,
+            xytext,
+            arrow,
+        } => {
+            let targets: Vec<_> = xy(c, py, &target.0 .0, &target.1 .0)?.collect();
+            let positions: Vec<_> = match xytext {
+                Some((x, y)) => xy(c, py, &x.0, &y.0)?.collect(),
+                None => targets.clone(),
+            };
+            for (target, at) in targets.into_iter().zip(positions) {
+                let (target_px, at_px) = (c.backend_coord(&target), c.backend_coord(&at));
+                let pos = match (text.anchor, xytext) {
+                    (Some(pos), _) => pos,
+                    (None, Some(..)) => annotation::anchor_away(at_px, target_px),
+                    (None, None) => Pos::new(HPos::Left, VPos::Bottom),
+                };
+                let area = c.plotting_area();
+                if *arrow && xytext.is_some() {
+                    area.draw(&Arrow::new(at.clone(), target, style.clone()))
+                        .map_err(error::render)?;
+                }
+                let font = (text.font.as_str(), text.size)
+                    .into_font()
+                    .color(&style.color)
+                    .pos(pos);
+                area.draw(&Text::new(text.text.as_str(), at, font))
+                    .map_err(error::render)?;
+            }
+            return Ok(());
+        }
+    };
+    annotate(anno, label, swatch);
+    Ok(())
+}
+
+/// Draws a line from the baseline to each point, and a circle at the point.
+fn draw_stem<X: SeriesCoord, Y: SeriesCoord>(
+    c: &mut Ctx<X, Y>,
+    py: Python,
+    stem: &StemSeries,
+    style: &ShapeStyle,
+    label: &Option<String>,
+) -> PyResult<()>
+where
+    X::ValueType: Clone + 'static,
+    Y::ValueType: BarValue + ErrorValue + Clone + 'static,
+{
+    let coord = c.as_coord_spec();
+    let x: Vec<_> = coord.x_spec().values(py, &stem.x)?.collect();
+    let y: Vec<_> = coord.y_spec().values(py, &stem.y)?.collect();
+    let base: Vec<_> = match &stem.baseline {
+        Baseline::Scalar(v) => vec![Y::ValueType::from_length(*v)?; x.len()],
+        Baseline::Series(s) => coord.y_spec().values(py, s)?.collect(),
+    };
+    let stems = || {
+        x.iter()
+            .zip(&y)
+            .zip(&base)
+            .filter(|((_, y), _)| !y.is_nan())
+    };
+    c.draw_series(stems().map(|((x, y), b)| {
+        PathElement::new(
+            vec![(x.clone(), b.clone()), (x.clone(), y.clone())],
+            style.clone(),
+        )
+    }))
+    .map_err(error::render)?;
+    let marker = style.filled();
+    let anno = c
+        .draw_series(
+            stems().map(|((x, y), _)| Circle::new((x.clone(), y.clone()), 3, marker.clone())),
+        )
+        .map_err(error::render)?;
+    annotate(anno, label, Swatch::Marker(Marker::Circle, 3, marker));
+    Ok(())
+}
+
+/// Draws bars of a bar chart in a single series.
+fn draw_bar<X: SeriesCoord, Y: SeriesCoord>(
+    c: &mut Ctx<X, Y>,
+    py: Python,
+    bar: &BarSeries,
+    style: &ShapeStyle,
+    label: &Option<String>,
+) -> PyResult<()>
+where
+    X::ValueType: BarValue + Clone + 'static,
+    Y::ValueType: BarValue + Clone + 'static,
+{
+    let ends = bar.baseline.values(py)?.zip(bar.lengths.iter_f64(py)?);
+    let rects = if bar.horizontal {
+        c.as_coord_spec()
+            .y_spec()
+            .values(py, &bar.positions)?
+            .zip(ends)
+            .map(|(p, (b, h))| {
+                let (y0, y1) = p.bar_span(&bar.width, bar.align)?;
+                let (x0, x1) = (
+                    X::ValueType::from_length(b)?,
+                    X::ValueType::from_length(b + h)?,
+                );
+                Ok(Rectangle::new([(x0, y0), (x1, y1)], style.clone()))
+            })
+            .collect::<PyResult<Vec<_>>>()?
+    } else {
+        c.as_coord_spec()
+            .x_spec()
+            .values(py, &bar.positions)?
+            .zip(ends)
+            .map(|(p, (b, h))| {
+                let (x0, x1) = p.bar_span(&bar.width, bar.align)?;
+                let (y0, y1) = (
+                    Y::ValueType::from_length(b)?,
+                    Y::ValueType::from_length(b + h)?,
+                );
+                Ok(Rectangle::new([(x0, y0), (x1, y1)], style.clone()))
+            })
+            .collect::<PyResult<Vec<_>>>()?
+    };
+    let anno = c.draw_series(rects).map_err(error::render)?;
+    annotate(anno, label, Swatch::Bar(style.clone()));
+    Ok(())
+}
+
+/// Draws a filled region as a polygon per run of selected points.
+fn draw_fill<X: SeriesCoord, Y: SeriesCoord>(
+    c: &mut Ctx<X, Y>,
+    py: Python,
+    fill: &FillSeries,
+    style: &ShapeStyle,
+    line_style: &ShapeStyle,
+    label: &Option<String>,
+) -> PyResult<()>
+where
+    X::ValueType: Clone + 'static,
+    Y::ValueType: BarValue + Clone + 'static,
+{
+    let coord = c.as_coord_spec();
+    let x: Vec<_> = coord.x_spec().values(py, &fill.x)?.collect();
+    let y1: Vec<_> = coord.y_spec().values(py, &fill.y1)?.collect();
+    let y2: Vec<_> = match &fill.y2 {
+        Baseline::Scalar(v) => vec![Y::ValueType::from_length(*v)?; x.len()],
+        Baseline::Series(s) => coord.y_spec().values(py, s)?.collect(),
+    };
+    let polygons = fill.runs(x.len()).into_iter().map(|run| {
+        let upper = run.clone().map(|i| (x[i].clone(), y1[i].clone()));
+        let lower = run.rev().map(|i| (x[i].clone(), y2[i].clone()));
+        Polygon::new(upper.chain(lower).collect::<Vec<_>>(), style.clone())
+    });
+    let anno = c.draw_series(polygons).map_err(error::render)?;
+    annotate(anno, label, Swatch::Bar(style.clone()));
+    if fill.outline {
+        let line = x.iter().cloned().zip(y1.iter().cloned());
+        c.draw_series(LineSeries::new(line, line_style.clone()))
+            .map_err(error::render)?;
+    }
+    Ok(())
+}
+
+/// Draws the vertical error bars, then the horizontal ones.
+fn draw_errorbar<X: SeriesCoord, Y: SeriesCoord>(
+    c: &mut Ctx<X, Y>,
+    py: Python,
+    bars: &ErrorBarSeries,
+    style: &ShapeStyle,
+    cap_size: u32,
+    label: &Option<String>,
+) -> PyResult<()>
+where
+    X::ValueType: ErrorValue + Clone + 'static,
+    Y::ValueType: ErrorValue + Clone + 'static,
+{
+    let coord = c.as_coord_spec();
+    let x: Vec<_> = coord.x_spec().values(py, &bars.x)?.collect();
+    let y: Vec<_> = coord.y_spec().values(py, &bars.y)?.collect();
+    let points = || x.iter().zip(y.iter());
+    let mut label = label.as_ref();
+    if let Some(yerr) = &bars.yerr {
+        let bars = points()
+            .zip(yerr)
+            .map(|((x, y), &(lo, hi))| {
+                Ok(ErrorBar::new_vertical(
+                    x.clone(),
+                    y.offset(-lo)?,
+                    y.clone(),
+                    y.offset(hi)?,
+                    style.clone(),
+                    cap_size,
+                ))
+            })
+            .collect::<error::Result<Vec<_>>>()?;
+        let anno = c.draw_series(bars).map_err(error::render)?;
+        annotate(
+            anno,
+            &label.take().cloned(),
+            Swatch::ErrorBar(cap_size, style.clone()),
+        );
+    }
+    if let Some(xerr) = &bars.xerr {
+        let bars = points()
+            .zip(xerr)
+            .map(|((x, y), &(lo, hi))| {
+                Ok(ErrorBar::new_horizontal(
+                    y.clone(),
+                    x.offset(-lo)?,
+                    x.clone(),
+                    x.offset(hi)?,
+                    style.clone(),
+                    cap_size,
+                ))
+            })
+            .collect::<error::Result<Vec<_>>>()?;
+        let anno = c.draw_series(bars).map_err(error::render)?;
+        annotate(
+            anno,
+            &label.take().cloned(),
+            Swatch::ErrorBar(cap_size, style.clone()),
+        );
+    }
+    Ok(())
+}
+
+/// Draws a filled rectangle per cell.
+fn draw_cells<X: SeriesCoord, Y: SeriesCoord>(
+    c: &mut Ctx<X, Y>,
+    py: Python,
+    cells: &CellSeries,
+) -> PyResult<()>
+where
+    X::ValueType: CellValue + 'static,
+    Y::ValueType: CellValue + 'static,
+{
+    let coord = c.as_coord_spec();
+    let x = heatmap::spans(coord.x_spec().values(py, &cells.x)?.collect(), cells.cols)?;
+    let y = heatmap::spans(coord.y_spec().values(py, &cells.y)?.collect(), cells.rows)?;
+    let rects = cells.colors.iter().enumerate().filter_map(|(k, color)| {
+        let ((x0, x1), (y0, y1)) = (&x[k % cells.cols], &y[k / cells.cols]);
+        color.as_ref().map(|color| {
+            Rectangle::new(
+                [(x0.clone(), y0.clone()), (x1.clone(), y1.clone())],
+                color.filled(),
+            )
+        })
+    });
+    c.draw_series(rects).map_err(error::render)?;
+    Ok(())
+}
+
+/// Draws a candlestick or an OHLC bar at each time. Times with a NaN price are left out.
+fn draw_ohlc<X: SeriesCoord, Y: SeriesCoord>(
+    c: &mut Ctx<X, Y>,
+    py: Python,
+    prices: &OhlcSeries,
+    up_style: &ShapeStyle,
+    down_style: &ShapeStyle,
+    label: &Option<String>,
+) -> PyResult<()>
+where
+    X::ValueType: Clone + 'static,
+    Y::ValueType: ErrorValue + PartialOrd + Clone + 'static,
+{
+    let coord = c.as_coord_spec();
+    let t: Vec<_> = coord.x_spec().values(py, &prices.t)?.collect();
+    let y = coord.y_spec();
+    let rows: Vec<_> = t
+        .into_iter()
+        .zip(y.values(py, &prices.open)?)
+        .zip(y.values(py, &prices.high)?)
+        .zip(y.values(py, &prices.low)?)
+        .zip(y.values(py, &prices.close)?)
+        .map(|((((t, open), high), low), close)| (t, open, high, low, close))
+        .filter(|(_, open, high, low, close)| {
+            !(open.is_nan() || high.is_nan() || low.is_nan() || close.is_nan())
+        })
+        .collect();
+    let width = prices.width.unwrap_or_else(|| {
+        ohlc::default_width(
+            rows.iter()
+                .map(|(t, open, ..)| c.backend_coord(&(t.clone(), open.clone())).0)
+                .collect(),
+        )
+    });
+    let rows = rows.into_iter();
+    let anno = match prices.glyph {
+        Glyph::Candlestick => c.draw_series(rows.map(|(t, open, high, low, close)| {
+            CandleStick::new(
+                t,
+                open,
+                high,
+                low,
+                close,
+                up_style.clone(),
+                down_style.clone(),
+                width,
+            )
+        })),
+        Glyph::Ohlc => c.draw_series(rows.map(|(t, open, high, low, close)| {
+            OhlcBar::new(t, open, high, low, close, up_style, down_style, width)
+        })),
+    }
+    .map_err(error::render)?;
+    annotate(anno, label, Swatch::Bar(up_style.clone()));
+    Ok(())
+}
+
+/// Draws a box or a violin per group.
+fn draw_boxes<X: SeriesCoord, Y: SeriesCoord>(
+    c: &mut Ctx<X, Y>,
+    py: Python,
+    boxes: &BoxSeries,
+    line_style: &ShapeStyle,
+    fill_style: &ShapeStyle,
+    label: &Option<String>,
+) -> PyResult<()>
+where
+    X::ValueType: ErrorValue + Clone + 'static,
+    Y::ValueType: BarValue + Clone + 'static,
+{
+    let positions: Vec<_> = c
+        .as_coord_spec()
+        .x_spec()
+        .values(py, &boxes.positions)?
+        .collect();
+    let v = |value: f64| Y::ValueType::from_length(value);
+    let half = boxes.width / 2.0;
+    // shapes are filled, then outlined
+    let mut shapes = vec![];
+    let mut paths = vec![];
+    let mut outliers = vec![];
+    match &boxes.glyphs {
+        Glyphs::Boxes {
+            stats,
+            show_outliers,
+        } => {
+            for (p, s) in positions.iter().zip(stats) {
+                let s = match s {
+                    Some(s) => s,
+                    None => continue,
+                };
+                let (left, right) = (p.offset(-half)?, p.offset(half)?);
+                let (cap_left, cap_right) = (p.offset(-half / 2.0)?, p.offset(half / 2.0)?);
+                shapes.push(vec![
+                    (left.clone(), v(s.q1)?),
+                    (right.clone(), v(s.q1)?),
+                    (right.clone(), v(s.q3)?),
+                    (left.clone(), v(s.q3)?),
+                ]);
+                paths.push(vec![(left, v(s.median)?), (right, v(s.median)?)]);
+                paths.push(vec![(p.clone(), v(s.q1)?), (p.clone(), v(s.whisker_lo)?)]);
+                paths.push(vec![(p.clone(), v(s.q3)?), (p.clone(), v(s.whisker_hi)?)]);
+                for w in [s.whisker_lo, s.whisker_hi].iter() {
+                    paths.push(vec![
+                        (cap_left.clone(), v(*w)?),
+                        (cap_right.clone(), v(*w)?),
+                    ]);
+                }
+                if *show_outliers {
+                    for o in &s.outliers {
+                        outliers.push((p.clone(), v(*o)?));
+                    }
+                }
+            }
+        }
+        Glyphs::Violins(violins) => {
+            for (p, violin) in positions.iter().zip(violins) {
+                let violin = match violin {
+                    Some(violin) => violin,
+                    None => continue,
+                };
+                let side = |sign: f64, i: usize| -> PyResult<_> {
+                    let d = violin.densities[i];
+                    Ok((p.offset(sign * half * d)?, v(violin.values[i])?))
+                };
+                let n = violin.values.len();
+                shapes.push(
+                    (0..n)
+                        .map(|i| side(1.0, i))
+                        .chain((0..n).rev().map(|i| side(-1.0, i)))
+                        .collect::<PyResult<Vec<_>>>()?,
+                );
+                let i = violin
+                    .values
+                    .iter()
+                    .position(|&value| value >= violin.median)
+                    .unwrap_or(n - 1);
+                let d = violin.densities[i];
+                let median = v(violin.median)?;
+                paths.push(vec![
+                    (p.offset(-half * d)?, median.clone()),
+                    (p.offset(half * d)?, median),
+                ]);
+            }
+        }
+    }
+    let anno = c
+        .draw_series(
+            shapes
+                .iter()
+                .map(|shape| Polygon::new(shape.clone(), fill_style.clone())),
+        )
+        .map_err(error::render)?;
+    annotate(anno, label, Swatch::Bar(fill_style.clone()));
+    let outlines = shapes.into_iter().map(|mut shape| {
+        shape.push(shape[0].clone());
+        shape
+    });
+    c.draw_series(
+        outlines
+            .chain(paths)
+            .map(|path| PathElement::new(path, line_style.clone())),
+    )
+    .map_err(error::render)?;
+    c.draw_series(
+        outliers
+            .into_iter()
+            .map(|p| Circle::new(p, 3, line_style.clone())),
+    )
+    .map_err(error::render)?;
+    Ok(())
+}
+
+/// The legend element of a series.
+enum Swatch {
+    Line(ShapeStyle),
+    Marker(Marker, u32, ShapeStyle),
+    Bar(ShapeStyle),
+    ErrorBar(u32, ShapeStyle),
+}
+
+/// Labels the series for the legend. An unlabeled series is left out of the legend.
+fn annotate(anno: &mut SeriesAnno<'static, EzelBackend>, label: &Option<String>, swatch: Swatch) {
+    let label = match label {
+        Some(label) => label,
+        None => return,
+    };
+    // the legend element is drawn in the 20px left of the label, centered at y.
+    let anno = anno.label(label.as_str());
+    match swatch {
+        Swatch::Line(style) => {
+            anno.legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], style.clone()))
+        }
+        Swatch::Marker(marker, size, style) => {
+            anno.legend(move |(x, y)| MarkerElement::new((x + 10, y), marker, size, style.clone()))
+        }
+        Swatch::Bar(style) => {
+            anno.legend(move |(x, y)| Rectangle::new([(x, y - 5), (x + 20, y + 5)], style.clone()))
+        }
+        Swatch::ErrorBar(cap_size, style) => anno.legend(move |(x, y)| {
+            ErrorBar::new_vertical(x + 10, y - 6, y, y + 6, style.clone(), cap_size)
+        }),
+    };
+}
+
+/// A text of `Chart.text` or `Chart.annotate`.
+fn text_label(
+    text: String,
+    font: Option<&str>,
+    size: Option<u32>,
+    anchor: Option<&str>,
+) -> PyResult<TextLabel> {
+    Ok(TextLabel {
+        text,
+        font: font.unwrap_or("sans-serif").to_string(),
+        size: size.unwrap_or(12),
+        anchor: anchor.map(annotation::text_anchor).transpose()?,
+    })
+}
+
+/// The style of a text and its arrow, black by default.
+fn text_style(color: Option<ColorArg>) -> ShapeStyle {
+    ShapeStyle {
+        color: color.map_or(BLACK.to_rgba(), |c| c.0),
+        filled: true,
+        stroke_width: 1,
+    }
+}
+
+/// A legend position name in the style of matplotlib.
+fn legend_position(name: &str) -> PyResult<SeriesLabelPosition> {
+    Ok(match name {
+        "upper left" => SeriesLabelPosition::UpperLeft,
+        "upper center" => SeriesLabelPosition::UpperMiddle,
+        "upper right" => SeriesLabelPosition::UpperRight,
+        "center left" => SeriesLabelPosition::MiddleLeft,
+        "center" => SeriesLabelPosition::MiddleMiddle,
+        "center right" => SeriesLabelPosition::MiddleRight,
+        "lower left" => SeriesLabelPosition::LowerLeft,
+        "lower center" => SeriesLabelPosition::LowerMiddle,
+        "lower right" => SeriesLabelPosition::LowerRight,
+        _ => {
+            return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                "unknown legend position {:?}, expected \"upper left\", \"upper center\", \"upper right\", \"center left\", \"center\", \"center right\", \"lower left\", \"lower center\" or \"lower right\"",
+                name
+            )))
+        }
+    })
+}
+
+/// Chart::new arguments, kept until the chart is built.
+struct ChartConfig {
+    /// The area of the canvas. Not the canvas itself, since the canvas backend keeps deferred
+    /// charts and that would be a reference cycle.
+    area: DrawingArea<EzelBackend, plotters::coord::Shift>,
+    margin: Option<Size>,
+    margin_left: Option<Size>,
+    margin_right: Option<Size>,
+    margin_top: Option<Size>,
+    margin_bottom: Option<Size>,
+    label_area: Option<Size>,
+    label_area_left: Option<Size>,
+    label_area_right: Option<Size>,
+    label_area_top: Option<Size>,
+    label_area_bottom: Option<Size>,
+    caption: Option<String>,
+    caption_font: Option<String>,
+    caption_size: Option<u32>,
+    background: RGBAColor,
+    mesh_x: bool,
+    mesh_y: bool,
+    axis_x: bool,
+    axis_y: bool,
+    axis_x_label_max: usize,
+    axis_y_label_max: usize,
+    x_label: Option<String>,
+    y_label: Option<String>,
+    axis_label_font: String,
+    axis_label_size: u32,
+    axis_label_color: RGBAColor,
+    y_label_rotation: i32,
+    x_tick_format: Option<TickFormat>,
+    y_tick_format: Option<TickFormat>,
+    x_range: Option<RangeEnum>,
+    y_range: Option<RangeEnum>,
+    x_scale: Scale,
+    y_scale: Scale,
+    padding: f64,
+}
+
+impl ChartConfig {
+    /// The (width, height) of an axis title in px.
+    fn title_size(&self, text: &str) -> (i32, i32) {
+        let size = self.axis_label_size;
+        let (w, h) = (self.axis_label_font.as_str(), size)
+            .into_font()
+            .box_size(text)
+            .unwrap_or((text.chars().count() as u32 * size / 2, size));
+        (w as i32, h as i32)
+    }
+
+    /// The (left, bottom) label area sizes in a canvas of (width, height) px.
+    /// Unless the user sets them, they fit the tick labels and the axis titles.
+    fn label_area_sizes(&self, (width, height): (u32, u32)) -> PyResult<(i32, i32)> {
+        let user_specified = self.label_area.is_some()
+            || self.label_area_left.is_some()
+            || self.label_area_right.is_some()
+            || self.label_area_top.is_some()
+            || self.label_area_bottom.is_some();
+        let default = if user_specified { 0 } else { 20 };
+        let left = self.label_area_left.or(self.label_area);
+        let left = left.map(|v| v.in_pixels(width)).unwrap_or_else(|| {
+            Ok(default
+                + match &self.y_label {
+                    None => 0,
+                    Some(v) if self.y_label_rotation == 0 => self.title_size(v).0 + 10,
+                    Some(v) => self.title_size(v).1 + 5,
+                })
+        })?;
+        let bottom = self.label_area_bottom.or(self.label_area);
+        let bottom = bottom.map(|v| v.in_pixels(height)).unwrap_or_else(|| {
+            Ok(default
+                + match &self.x_label {
+                    None => 0,
+                    Some(v) => self.title_size(v).1 + 5,
+                })
+        })?;
+        Ok((left, bottom))
+    }
+
+    fn title_style(&self) -> TextStyle {
+        (self.axis_label_font.as_str(), self.axis_label_size)
+            .into_font()
+            .color(&self.axis_label_color)
+    }
+
+    /// Builds the chart and draws the background, mesh and axes.
+    fn build(&self, py: Python, x_range: RangeEnum, y_range: RangeEnum) -> PyResult<TypedChart> {
+        // the chart context only borrows the area while it's built
+        let area = unsafe { static_reference(&self.area) };
+        let (width, height) = area.dim_in_pixel();
+        let mut b = ChartBuilder::on(area);
+        let user_margin = self.margin.is_some()
+            || self.margin_left.is_some()
+            || self.margin_right.is_some()
+            || self.margin_top.is_some()
+            || self.margin_bottom.is_some();
+        if user_margin {
+            let margin = |side: Option<Size>, length| {
+                side.or(self.margin).map_or(Ok(0), |v| v.in_pixels(length))
+            };
+            b.margin_left(margin(self.margin_left, width)?);
+            b.margin_right(margin(self.margin_right, width)?);
+            b.margin_top(margin(self.margin_top, height)?);
+            b.margin_bottom(margin(self.margin_bottom, height)?);
+        } else {
+            // 20px on the default canvas
+            b.margin(Size::Fraction(0.025).in_pixels(width.min(height))?);
+        }
+
+        let (left, bottom) = self.label_area_sizes((width, height))?;
+        b.y_label_area_size(left);
+        b.x_label_area_size(bottom);
+        if let Some(v) = self.label_area_right.or(self.label_area) {
+            b.right_y_label_area_size(v.in_pixels(width)?);
+        }
+        if let Some(v) = self.label_area_top.or(self.label_area) {
+            b.top_x_label_area_size(v.in_pixels(height)?);
+        }
+        if let Some(v) = &self.caption {
+            b.caption(
+                v,
+                (
+                    self.caption_font.as_deref().unwrap_or("sans-serif"),
+                    self.caption_size.unwrap_or(20),
+                ),
+            );
+        }
+        Ok(match x_range {
+            RangeEnum::F64(x0, x1) => TypedChart::F64(self.build_y(
+                py,
+                b,
+                F64Coord::new(x0, x1, self.x_scale),
+                y_range,
+            )?),
+            RangeEnum::F32(x0, x1) => {
+                TypedChart::F32(self.build_y(py, b, (x0..x1).into(), y_range)?)
+            }
+            RangeEnum::I64(x0, x1) => {
+                TypedChart::I64(self.build_y(py, b, (x0..x1).into(), y_range)?)
+            }
+            RangeEnum::I32(x0, x1) => {
+                TypedChart::I32(self.build_y(py, b, (x0..x1).into(), y_range)?)
+            }
+            RangeEnum::DateTime(x0, x1) => {
+                TypedChart::DateTime(self.build_y(py, b, (x0..x1).into(), y_range)?)
+            }
+            RangeEnum::Date(x0, x1) => {
+                TypedChart::Date(self.build_y(py, b, (x0..x1).into(), y_range)?)
+            }
+            RangeEnum::Time(x0, x1) => {
+                TypedChart::Time(self.build_y(py, b, (x0..x1).into(), y_range)?)
+            }
+            RangeEnum::Duration(x0, x1) => {
+                TypedChart::Duration(self.build_y(py, b, (x0..x1).into(), y_range)?)
+            }
+            RangeEnum::Category(names) => {
+                TypedChart::Category(self.build_y(py, b, CategoryCoord::new(names), y_range)?)
+            }
+        })
+    }
+
+    fn build_y<X>(
+        &self,
+        py: Python,
+        b: ChartBuilder<'static, '_, EzelBackend>,
+        x: X,
+        y_range: RangeEnum,
+    ) -> PyResult<Charts<X>>
+    where
+        X: SeriesCoord,
+        X::ValueType: TickValue,
+    {
+        Ok(match y_range {
+            RangeEnum::F64(y0, y1) => {
+                Charts::F64(self.build_xy(py, b, x, F64Coord::new(y0, y1, self.y_scale))?)
+            }
+            RangeEnum::F32(y0, y1) => Charts::F32(self.build_xy(py, b, x, (y0..y1).into())?),
+            RangeEnum::I64(y0, y1) => Charts::I64(self.build_xy(py, b, x, (y0..y1).into())?),
+            RangeEnum::I32(y0, y1) => Charts::I32(self.build_xy(py, b, x, (y0..y1).into())?),
+            RangeEnum::DateTime(y0, y1) => {
+                Charts::DateTime(self.build_xy(py, b, x, (y0..y1).into())?)
+            }
+            RangeEnum::Date(y0, y1) => Charts::Date(self.build_xy(py, b, x, (y0..y1).into())?),
+            RangeEnum::Time(y0, y1) => Charts::Time(self.build_xy(py, b, x, (y0..y1).into())?),
+            RangeEnum::Duration(y0, y1) => {
+                Charts::Duration(self.build_xy(py, b, x, (y0..y1).into())?)
+            }
+            RangeEnum::Category(names) => {
+                Charts::Category(self.build_xy(py, b, x, CategoryCoord::new(names))?)
+            }
+        })
+    }
+
+    fn build_xy<X, Y>(
+        &self,
+        py: Python,
+        mut b: ChartBuilder<'static, '_, EzelBackend>,
+        x: X,
+        y: Y,
+    ) -> PyResult<Ctx<X, Y>>
+    where
+        X: SeriesCoord,
+        Y: SeriesCoord,
+        X::ValueType: TickValue,
+        Y::ValueType: TickValue,
+    {
+        let (default_x, default_y) = (x.tick_formatter(), y.tick_formatter());
+        let mut chart = b.build_cartesian_2d(x, y).map_err(error::render)?;
+        chart
+            .plotting_area()
+            .fill(&self.background)
+            .map_err(error::render)?;
+        let tick_error = RefCell::new(None);
+        let format_x = |v: &X::ValueType| match &self.x_tick_format {
+            Some(f) => f.label(py, v, &tick_error),
+            None => default_x(v),
+        };
+        let format_y = |v: &Y::ValueType| match &self.y_tick_format {
+            Some(f) => f.label(py, v, &tick_error),
+            None => default_y(v),
+        };
+        {
+            let mut mesh = chart.configure_mesh();
+            if !self.mesh_x {
+                mesh.disable_x_mesh();
+            }
+            if !self.mesh_y {
+                mesh.disable_y_mesh();
+            }
+            if !self.axis_x {
+                mesh.disable_x_axis();
+            }
+            if !self.axis_y {
+                mesh.disable_y_axis();
+            }
+            mesh.x_labels(self.axis_x_label_max);
+            mesh.y_labels(self.axis_y_label_max);
+            mesh.x_label_formatter(&format_x);
+            mesh.y_label_formatter(&format_y);
+            mesh.axis_desc_style(self.title_style());
+            if let Some(v) = &self.x_label {
+                mesh.x_desc(v.as_str());
+            }
+            if let (Some(v), 270) = (&self.y_label, self.y_label_rotation) {
+                mesh.y_desc(v.as_str());
+            }
+            mesh.draw().map_err(error::render)?;
+        }
+        if let Some(e) = tick_error.borrow_mut().take() {
+            return Err(e);
+        }
+        // plotters always draws y_desc rotated by 270 degrees, so other rotations are drawn here.
+        if let (Some(v), 0) | (Some(v), 90) = (&self.y_label, self.y_label_rotation) {
+            let area = chart.plotting_area().strip_coord_spec();
+            let x = -self.label_area_sizes(self.area.dim_in_pixel())?.0;
+            let y = area.dim_in_pixel().1 as i32 / 2;
+            let style = if self.y_label_rotation == 0 {
+                self.title_style().pos(Pos::new(HPos::Left, VPos::Center))
+            } else {
+                self.title_style()
+                    .transform(FontTransform::Rotate90)
+                    .pos(Pos::new(HPos::Center, VPos::Bottom))
+            };
+            area.draw_text(v, &style, (x, y)).map_err(error::render)?;
+        }
+        Ok(chart)
+    }
+}
+
+/// A drawing call on a chart.
+/// It's recorded until the chart is built when the chart range is inferred.
+enum Op {
+    Line {
+        x: Series,
+        y: Series,
+        style: ShapeStyle,
+        label: Option<String>,
+    },
+    Step {
+        x: Series,
+        y: Series,
+        step: Step,
+        style: ShapeStyle,
+        label: Option<String>,
+    },
+    Stem {
+        stem: StemSeries,
+        style: ShapeStyle,
+        label: Option<String>,
+    },
+    Scatter {
+        points: ScatterSeries,
+        style: ShapeStyle,
+        label: Option<String>,
+    },
+    Bars {
+        bars: Bars,
+        style: ShapeStyle,
+        label: Option<String>,
+    },
+    Bar {
+        bar: BarSeries,
+        style: ShapeStyle,
+        label: Option<String>,
+    },
+    Boxes {
+        boxes: BoxSeries,
+        line_style: ShapeStyle,
+        fill_style: ShapeStyle,
+        label: Option<String>,
+    },
+    Cells {
+        cells: CellSeries,
+    },
+    ErrorBar {
+        bars: ErrorBarSeries,
+        style: ShapeStyle,
+        cap_size: u32,
+        label: Option<String>,
+    },
+    Fill {
+        fill: FillSeries,
+        style: ShapeStyle,
+        line_style: ShapeStyle,
+        label: Option<String>,
+    },
+    Ohlc {
+        prices: OhlcSeries,
+        up_style: ShapeStyle,
+        down_style: ShapeStyle,
+        label: Option<String>,
+    },
+    Annotation {
+        annotation: Annotation,
+        style: ShapeStyle,
+        label: Option<String>,
+    },
+    /// Draws the labels of the series drawn so far.
+    Legend {
+        position: String,
+        frame: bool,
+        font: String,
+        font_size: u32,
+    },
+}
+
+impl Op {
+    /// Copies the numpy arrays of the op, which is drawn after the method returns.
+    fn detach(&mut self, py: Python) -> PyResult<()> {
+        let baseline = |b: &mut Baseline| match b {
+            Baseline::Series(s) => s.detach(py),
+            Baseline::Scalar(..) => Ok(()),
+        };
+        match self {
+            Op::Line { x, y, .. }
+            | Op::Step { x, y, .. }
+            | Op::Scatter {
+                points: ScatterSeries { x, y, .. },
+                ..
+            }
+            | Op::Cells {
+                cells: CellSeries { x, y, .. },
+            }
+            | Op::ErrorBar {
+                bars: ErrorBarSeries { x, y, .. },
+                ..
+            } => {
+                x.detach(py)?;
+                y.detach(py)
+            }
+            Op::Stem { stem, .. } => {
+                stem.x.detach(py)?;
+                stem.y.detach(py)?;
+                baseline(&mut stem.baseline)
+            }
+            Op::Bar { bar, .. } => {
+                bar.positions.detach(py)?;
+                bar.lengths.detach(py)?;
+                baseline(&mut bar.baseline)
+            }
+            Op::Boxes { boxes, .. } => boxes.positions.detach(py),
+            Op::Fill { fill, .. } => {
+                fill.x.detach(py)?;
+                fill.y1.detach(py)?;
+                baseline(&mut fill.y2)
+            }
+            Op::Ohlc { prices, .. } => {
+                for s in [
+                    &mut prices.t,
+                    &mut prices.open,
+                    &mut prices.high,
+                    &mut prices.low,
+                    &mut prices.close,
+                ]
+                .iter_mut()
+                {
+                    s.detach(py)?;
+                }
+                Ok(())
+            }
+            // annotation values are lists, which are copied already
+            Op::Bars { .. } | Op::Annotation { .. } | Op::Legend { .. } => Ok(()),
+        }
+    }
+
+    /// The axis dtypes this op needs, if it decides them.
+    fn dtypes(&self) -> (Option<Dtype>, Option<Dtype>) {
+        match self {
+            Op::Line { x, y, .. }
+            | Op::Step { x, y, .. }
+            | Op::Stem {
+                stem: StemSeries { x, y, .. },
+                ..
+            }
+            | Op::Scatter {
+                points: ScatterSeries { x, y, .. },
+                ..
+            } => (axis_dtype(x), axis_dtype(y)),
+            Op::Bars { bars, .. } => (Some(bars.x_dtype()), Some(Dtype::F64)),
+            Op::Bar { bar, .. } => {
+                let position = axis_dtype(&bar.positions).map(float_if_numeric);
+                if bar.horizontal {
+                    (Some(Dtype::F64), position)
+                } else {
+                    (position, Some(Dtype::F64))
+                }
+            }
+            Op::Boxes { boxes, .. } => (
+                axis_dtype(&boxes.positions).map(float_if_numeric),
+                Some(Dtype::F64),
+            ),
+            Op::Cells { cells } => (
+                axis_dtype(&cells.x).map(float_if_numeric),
+                axis_dtype(&cells.y).map(float_if_numeric),
+            ),
+            Op::ErrorBar { bars, .. } => {
+                // errors move integer values between integers
+                let dtype = |v: &Series, err: &Option<_>| match err {
+                    Some(..) => axis_dtype(v).map(float_if_numeric),
+                    None => axis_dtype(v),
+                };
+                (dtype(&bars.x, &bars.xerr), dtype(&bars.y, &bars.yerr))
+            }
+            Op::Fill { fill, .. } => (axis_dtype(&fill.x), axis_dtype(&fill.y1)),
+            Op::Ohlc { prices, .. } => (axis_dtype(&prices.t), axis_dtype(&prices.open)),
+            Op::Annotation { annotation, .. } => {
+                let dtype = |v: &Value| axis_dtype(&v.0).map(float_if_numeric);
+                match annotation {
+                    Annotation::HLine(y) | Annotation::HSpan(y, _) => (None, dtype(y)),
+                    Annotation::VLine(x) | Annotation::VSpan(x, _) => (dtype(x), None),
+                    Annotation::Text { xy: (x, y), .. } => (dtype(x), dtype(y)),
+                }
+            }
+            Op::Legend { .. } => (None, None),
+        }
+    }
+
+    /// The x and y range covered by this op.
+    fn extent(
+        &self,
+        py: Python,
+        x_dtype: Dtype,
+        y_dtype: Dtype,
+    ) -> PyResult<(Option<RangeEnum>, Option<RangeEnum>)> {
+        Ok(match self {
+            Op::Line { x, y, .. }
+            | Op::Step { x, y, .. }
+            | Op::Scatter {
+                points: ScatterSeries { x, y, .. },
+                ..
+            } => (
+                range::series_extent(py, x, x_dtype)?,
+                range::series_extent(py, y, y_dtype)?,
+            ),
+            Op::Bars { bars, .. } => match bars.extent() {
+                Some((x, (lo, hi))) => (Some(x), Some(bar::numeric_range(lo, hi, y_dtype)?)),
+                None => (None, None),
+            },
+            Op::Bar { bar, .. } if bar.horizontal => (
+                bar::length_extent(py, &bar.lengths, &bar.baseline, x_dtype)?,
+                bar::position_extent(py, &bar.positions, y_dtype, &bar.width, bar.align)?,
+            ),
+            Op::Bar { bar, .. } => (
+                bar::position_extent(py, &bar.positions, x_dtype, &bar.width, bar.align)?,
+                bar::length_extent(py, &bar.lengths, &bar.baseline, y_dtype)?,
+            ),
+            Op::Boxes { boxes, .. } => {
+                let n = boxes.positions.len(py);
+                let half = boxes.width / 2.0;
+                let x = match x_dtype {
+                    Dtype::String => range::series_extent(py, &boxes.positions, x_dtype)?,
+                    _ => errorbar::extent(
+                        py,
+                        &boxes.positions,
+                        &Some(vec![(half, half); n]),
+                        x_dtype,
+                    )?,
+                };
+                let y = match boxes.value_extent() {
+                    Some((lo, hi)) => Some(bar::numeric_range(lo, hi, y_dtype)?),
+                    None => None,
+                };
+                (x, y)
+            }
+            Op::Cells { cells } => (
+                heatmap::extent(py, &cells.x, cells.cols, x_dtype)?,
+                heatmap::extent(py, &cells.y, cells.rows, y_dtype)?,
+            ),
+            Op::ErrorBar { bars, .. } => (
+                errorbar::extent(py, &bars.x, &bars.xerr, x_dtype)?,
+                errorbar::extent(py, &bars.y, &bars.yerr, y_dtype)?,
+            ),
+            Op::Fill { fill, .. } => (
+                range::series_extent(py, &fill.x, x_dtype)?,
+                fill.y_extent(py, y_dtype)?,
+            ),
+            Op::Stem { stem, .. } => (
+                range::series_extent(py, &stem.x, x_dtype)?,
+                stem.y_extent(py, y_dtype)?,
+            ),
+            Op::Ohlc { prices, .. } => (
+                range::series_extent(py, &prices.t, x_dtype)?,
+                prices.y_extent(py, y_dtype)?,
+            ),
+            Op::Annotation { annotation, .. } => annotation.extent(py, x_dtype, y_dtype)?,
+            Op::Legend { .. } => (None, None),
+        })
+    }
+
+    fn draw(&self, py: Python, inner: &mut TypedChart) -> PyResult<()> {
+        match self {
+            Op::Line { x, y, style, label } => {
+                with_chart!(inner, c => {
+                    let data = xy(c, py, x, y)?;
+                    let anno = c
+                        .draw_series(LineSeries::new(data, style.clone()))
+                        .map_err(error::render)?;
+                    annotate(anno, label, Swatch::Line(style.clone()));
+                });
+            }
+            Op::Step {
+                x,
+                y,
+                step,
+                style,
+                label,
+            } => {
+                with_chart!(inner, c => draw_step(c, py, x, y, *step, style, label)?);
+            }
+            Op::Stem { stem, style, label } => {
+                with_chart!(inner, c => draw_stem(c, py, stem, style, label)?);
+            }
+            Op::Scatter {
+                points,
+                style,
+                label,
+            } => {
+                with_chart!(inner, c => {
+                    let data = xy(c, py, &points.x, &points.y)?;
+                    let anno = c
+                        .draw_series(points.markers(data, style))
+                        .map_err(error::render)?;
+                    annotate(anno, label, Swatch::Marker(points.marker, points.size, style.clone()));
+                });
+            }
+            Op::Bar { bar, style, label } => {
+                with_chart!(inner, c => draw_bar(c, py, bar, style, label)?);
+            }
+            Op::Boxes {
+                boxes,
+                line_style,
+                fill_style,
+                label,
+            } => {
+                with_chart!(inner, c => draw_boxes(c, py, boxes, line_style, fill_style, label)?);
+            }
+            Op::Cells { cells } => {
+                with_chart!(inner, c => draw_cells(c, py, cells)?);
+            }
+            Op::ErrorBar {
+                bars,
+                style,
+                cap_size,
+                label,
+            } => {
+                with_chart!(inner, c => draw_errorbar(c, py, bars, style, *cap_size, label)?);
+            }
+            Op::Fill {
+                fill,
+                style,
+                line_style,
+                label,
+            } => {
+                with_chart!(inner, c => draw_fill(c, py, fill, style, line_style, label)?);
+            }
+            Op::Ohlc {
+                prices,
+                up_style,
+                down_style,
+                label,
+            } => {
+                with_chart!(inner, c => draw_ohlc(c, py, prices, up_style, down_style, label)?);
+            }
+            Op::Annotation {
+                annotation,
+                style,
+                label,
+            } => {
+                with_chart!(inner, c => draw_annotation(c, py, annotation, style, label)?);
+            }
+            Op::Legend {
+                position,
+                frame,
+                font,
+                font_size,
+            } => {
+                with_chart!(inner, c => {
+                    let mut labels = c.configure_series_labels();
+                    labels
+                        .position(legend_position(position)?)
+                        .label_font((font.as_str(), *font_size));
+                    if *frame {
+                        labels
+                            .border_style(&BLACK)
+                            .background_style(&WHITE.mix(0.8));
+                    }
+                    labels.draw().map_err(error::render)?;
+                });
+            }
+            Op::Bars { bars, style, label } => {
+                if bars.x_dtype() != inner.x_dtype() {
+                    return Err(Error::DtypeMismatch {
+                        expected: format!("a {:?} x-axis", bars.x_dtype()),
+                        actual: format!("a {:?} x-axis", inner.x_dtype()),
+                    }
+                    .into());
+                }
+                with_chart!(inner, c => draw_bars(c, bars, style, label)?);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Numeric data is drawn on a f64 axis when the series can't be drawn on an integer axis.
+fn float_if_numeric(dtype: Dtype) -> Dtype {
+    if dtype.is_numeric() {
+        Dtype::F64
+    } else {
+        dtype
+    }
+}
+
+/// The axis dtype used to draw the series when the range is inferred.
+/// A list of str is drawn on a categorical axis.
+fn axis_dtype(series: &Series) -> Option<Dtype> {
+    match series {
+        Series::EmptyPyList => None,
+        _ => Some(series.dtype()),
+    }
 }
 
-#[pyclass(unsendable)]
-#[text_signature = "(canvas, x_range, y_range, margin=None, margin_left=None ,margin_right=None, margin_top=None, margin_bottom=None, label_area=None, label_area_left=None, label_area_right=None, label_area_top=None, label_area_bottom=None, caption=None, caption_font=None, caption_size=None, mesh_x=None, mesh_y=None, axis_x=None, axis_y=None, axis_x_label_max=None, axis_y_label_max=None)"]
-pub struct Chart {
-    _canvas: Py<Canvas>, // Why Py<Canvas>? Since canvas is exposed to user, Python object around Canvas shouldn't be destroyed.
-    inner: TypedChart,
-    color_index: usize,
-    x_dtype: Dtype,
-    y_dtype: Dtype,
-}
+enum ChartState {
+    Ready(TypedChart),
+    /// x_range or y_range is omitted; ops are recorded until the canvas is rendered.
+    Deferred {
+        config: ChartConfig,
+        ops: Vec<Op>,
+    },
+}
+
+impl Deferred for RefCell<ChartState> {
+    /// Infers the missing ranges from the recorded ops, builds the chart, and replays the ops.
+    fn render(&self, py: Python) -> PyResult<()> {
+        let mut state = self.borrow_mut();
+        let (config, ops) = match &*state {
+            ChartState::Ready(..) => return Ok(()),
+            ChartState::Deferred { config, ops } => (config, ops),
+        };
+
+        let x_dtype = config
+            .x_range
+            .as_ref()
+            .map(|r| r.dtype())
+            .or_else(|| ops.iter().find_map(|op| op.dtypes().0))
+            .unwrap_or(Dtype::F64);
+        let y_dtype = config
+            .y_range
+            .as_ref()
+            .map(|r| r.dtype())
+            .or_else(|| ops.iter().find_map(|op| op.dtypes().1))
+            .unwrap_or(Dtype::F64);
+
+        let mut x_extent: Option<RangeEnum> = None;
+        let mut y_extent: Option<RangeEnum> = None;
+        for op in ops {
+            let (x, y) = op.extent(py, x_dtype, y_dtype)?;
+            x_extent = match (x_extent, x) {
+                (Some(a), Some(b)) => Some(a.union(b)),
+                (a, b) => a.or(b),
+            };
+            y_extent = match (y_extent, y) {
+                (Some(a), Some(b)) => Some(a.union(b)),
+                (a, b) => a.or(b),
+            };
+        }
+        let x_range = match &config.x_range {
+            Some(r) => r.clone(),
+            None => default_extent(x_extent, x_dtype).pad(config.padding),
+        };
+        let y_range = match &config.y_range {
+            Some(r) => r.clone(),
+            None => default_extent(y_extent, y_dtype).pad(config.padding),
+        };
+
+        let mut inner = config.build(py, x_range, y_range)?;
+        for op in ops {
+            op.draw(py, &mut inner)?;
+        }
+        *state = ChartState::Ready(inner);
+        Ok(())
+    }
+}
+
+/// The inferred extent, or a default range if there is no data.
+fn default_extent(extent: Option<RangeEnum>, dtype: Dtype) -> RangeEnum {
+    if let Some(r) = extent {
+        return r;
+    }
+    let epoch = chrono::NaiveDate::from_ymd(1970, 1, 1).and_hms(0, 0, 0);
+    match dtype {
+        Dtype::F64 => RangeEnum::F64(0.0, 1.0),
+        Dtype::F32 => RangeEnum::F32(0.0, 1.0),
+        Dtype::I64 => RangeEnum::I64(0, 1),
+        Dtype::I32 => RangeEnum::I32(0, 1),
+        Dtype::NaiveDateTime => RangeEnum::DateTime(epoch, epoch),
+        Dtype::NaiveDate => RangeEnum::Date(epoch.date(), epoch.date()),
+        Dtype::NaiveTime => RangeEnum::Time(epoch.time(), epoch.time()),
+        Dtype::Duration => RangeEnum::Duration(chrono::Duration::zero(), chrono::Duration::zero()),
+        Dtype::String => RangeEnum::Category(Rc::new(vec![])),
+    }
+}
+
+#[pyclass(unsendable)]
+#[text_signature = "(canvas, x_range=None, y_range=None, padding=0.05, margin=None, margin_left=None, margin_right=None, margin_top=None, margin_bottom=None, label_area=None, label_area_left=None, label_area_right=None, label_area_top=None, label_area_bottom=None, caption=None, caption_font=None, caption_size=None, color=None, mesh_x=None, mesh_y=None, axis_x=None, axis_y=None, axis_x_label_max=None, axis_y_label_max=None, x_label=None, y_label=None, axis_label_font=None, axis_label_size=None, axis_label_color=None, y_label_rotation=270, x_tick_format=None, y_tick_format=None)"]
+pub struct Chart {
+    // fields are dropped in order. state draws on the canvas backend, so it's dropped first.
+    state: Rc<RefCell<ChartState>>, // shared with the canvas backend to render a deferred chart
+    _canvas: Py<Canvas>, // Why Py<Canvas>? Since canvas is exposed to user, Python object around Canvas shouldn't be destroyed.
+    color_index: usize,
+}
+
+impl Chart {
+    fn next_color(&mut self) -> PaletteColor<Palette9999> {
+        self.color_index += 1;
+        if PaletteColor::<Palette9999>::pick(self.color_index).rgb() == (255, 255, 255) {
+            self.color_index += 1;
+        }
+        PaletteColor::<Palette9999>::pick(self.color_index)
+    }
+
+    /// the user color, or the next color in the palette.
+    fn series_color(&mut self, color: Option<ColorArg>) -> RGBAColor {
+        match color {
+            Some(c) => c.0,
+            None => self.next_color().to_rgba(),
+        }
+    }
+
+    /// Draws the op, or records it if the chart is deferred.
+    fn apply(&mut self, py: Python, mut op: Op) -> PyResult<()> {
+        match &mut *self.state.borrow_mut() {
+            ChartState::Ready(inner) => op.draw(py, inner),
+            ChartState::Deferred { ops, .. } => {
+                op.detach(py)?;
+                ops.push(op);
+                Ok(())
+            }
+        }
+    }
+
+    fn bar_series(
+        &self,
+        py: Python,
+        positions: Series,
+        lengths: Series,
+        width: Option<BarWidth>,
+        baseline: Option<Baseline>,
+        align: Option<&str>,
+        horizontal: bool,
+    ) -> PyResult<BarSeries> {
+        let (what, base) = if horizontal {
+            ("width", "left")
+        } else {
+            ("height", "bottom")
+        };
+        let check = |name: &str, series: &Series| {
+            if series.len(py) != positions.len(py) {
+                return Err(Error::LengthMismatch {
+                    what: name.to_string(),
+                    expected: positions.len(py),
+                    actual: series.len(py),
+                }
+                .into());
+            }
+            Ok(())
+        };
+        check(what, &lengths)?;
+        if let Some(Baseline::Series(s)) = &baseline {
+            check(base, s)?;
+        }
+        Ok(BarSeries {
+            positions,
+            lengths,
+            baseline: baseline.unwrap_or(Baseline::Scalar(0.0)),
+            width,
+            align: Align::parse(align.unwrap_or("center"))?,
+            horizontal,
+        })
+    }
+
+    fn bar_style(
+        &mut self,
+        color: Option<ColorArg>,
+        filled: Option<bool>,
+        stroke_width: Option<u32>,
+    ) -> ShapeStyle {
+        ShapeStyle {
+            color: self.series_color(color),
+            filled: filled.unwrap_or(true),
+            stroke_width: stroke_width.unwrap_or(1),
+        }
+    }
+
+    /// Checks the lengths of a filled region.
+    fn fill_series(
+        &self,
+        py: Python,
+        x: Series,
+        y1: Series,
+        y2: Baseline,
+        mask: Option<Mask>,
+        outline: bool,
+    ) -> PyResult<FillSeries> {
+        same_len(py, &x, &y1)?;
+        let check = |name: &str, len: usize| {
+            if len != x.len(py) {
+                return Err(Error::LengthMismatch {
+                    what: name.to_string(),
+                    expected: x.len(py),
+                    actual: len,
+                });
+            }
+            Ok(())
+        };
+        if let Baseline::Series(s) = &y2 {
+            check("y2", s.len(py))?;
+        }
+        if let Some(mask) = &mask {
+            check("where", mask.0.len())?;
+        }
+        Ok(FillSeries {
+            x,
+            y1,
+            y2,
+            mask,
+            outline,
+        })
+    }
+
+    /// The (fill, line) styles of a filled region.
+    fn fill_style(
+        &mut self,
+        color: Option<ColorArg>,
+        alpha: Option<f64>,
+        stroke_width: Option<u32>,
+    ) -> PyResult<(ShapeStyle, ShapeStyle)> {
+        let alpha = alpha.unwrap_or(0.3);
+        if !(0.0..=1.0).contains(&alpha) {
+            return Err(pyo3::exceptions::PyValueError::new_err(
+                "alpha should be between 0 and 1",
+            ));
+        }
+        let color = self.series_color(color);
+        let line = ShapeStyle {
+            color,
+            filled: false,
+            stroke_width: stroke_width.unwrap_or(2),
+        };
+        Ok((color.mix(alpha).filled(), line))
+    }
+
+    /// Checks that every group has a position.
+    fn box_series(
+        &self,
+        py: Python,
+        groups: &Groups,
+        positions: Option<Series>,
+        width: Option<f64>,
+        glyphs: Glyphs,
+    ) -> PyResult<BoxSeries> {
+        let positions = positions.unwrap_or_else(|| Series::arange(py, groups.0.len()));
+        if positions.len(py) != groups.0.len() {
+            return Err(Error::LengthMismatch {
+                what: "positions".to_string(),
+                expected: groups.0.len(),
+                actual: positions.len(py),
+            }
+            .into());
+        }
+        let width = width.unwrap_or(0.5);
+        if width.is_nan() || width <= 0.0 {
+            return Err(pyo3::exceptions::PyValueError::new_err(
+                "width should be positive",
+            ));
+        }
+        Ok(BoxSeries {
+            positions,
+            width,
+            glyphs,
+        })
+    }
+
+    /// The style of a reference line.
+    fn reference_style(
+        &mut self,
+        color: Option<ColorArg>,
+        stroke_width: Option<u32>,
+    ) -> ShapeStyle {
+        ShapeStyle {
+            color: self.series_color(color),
+            filled: true,
+            stroke_width: stroke_width.unwrap_or(1),
+        }
+    }
+
+    fn ohlc_glyphs(
+        &mut self,
+        py: Python,
+        glyph: Glyph,
+        t: Series,
+        open: Series,
+        high: Series,
+        low: Series,
+        close: Series,
+        up_color: Option<ColorArg>,
+        down_color: Option<ColorArg>,
+        width: Option<u32>,
+        stroke_width: Option<u32>,
+        label: Option<String>,
+    ) -> PyResult<()> {
+        for (name, prices) in [
+            ("open", &open),
+            ("high", &high),
+            ("low", &low),
+            ("close", &close),
+        ]
+        .iter()
+        {
+            if prices.len(py) != t.len(py) {
+                return Err(Error::LengthMismatch {
+                    what: name.to_string(),
+                    expected: t.len(py),
+                    actual: prices.len(py),
+                }
+                .into());
+            }
+        }
+        let style = |color: Option<ColorArg>, default: RGBColor| ShapeStyle {
+            color: color.map_or(default.to_rgba(), |c| c.0),
+            filled: false,
+            stroke_width: stroke_width.unwrap_or(1),
+        };
+        let up_style = style(up_color, RGBColor(0x26, 0xa6, 0x9a));
+        let down_style = style(down_color, RGBColor(0xef, 0x53, 0x50));
+        let prices = OhlcSeries {
+            t,
+            open,
+            high,
+            low,
+            close,
+            glyph,
+            width,
+        };
+        self.apply(
+            py,
+            Op::Ohlc {
+                prices,
+                up_style,
+                down_style,
+                label,
+            },
+        )
+    }
+
+    /// The cell edges or centers of an axis, 0, 1, .. by default.
+    fn cell_edges(
+        &self,
+        py: Python,
+        edges: Option<Series>,
+        cells: usize,
+        what: &str,
+    ) -> PyResult<Series> {
+        let edges = edges.unwrap_or_else(|| Series::arange(py, cells));
+        let len = edges.len(py);
+        if len != cells && len != cells + 1 {
+            return Err(Error::LengthMismatch {
+                what: what.to_string(),
+                expected: cells + 1,
+                actual: len,
+            }
+            .into());
+        }
+        Ok(edges)
+    }
 
-impl Chart {
-    fn next_color(&mut self) -> PaletteColor<Palette9999> {
-        self.color_index += 1;
-        if PaletteColor::<Palette9999>::pick(self.color_index).rgb() == (255, 255, 255) {
-            self.color_index += 1;
+    /// The x-axis dtype for a histogram of x.
+    /// For a deferred chart, it's decided by x_range or the first series, or f64 for numeric x.
+    fn x_dtype(&self, x: &Series) -> Option<Dtype> {
+        match &*self.state.borrow() {
+            ChartState::Ready(inner) => Some(inner.x_dtype()),
+            ChartState::Deferred { config, ops } => config
+                .x_range
+                .as_ref()
+                .map(|r| r.dtype())
+                .or_else(|| ops.iter().find_map(|op| op.dtypes().0))
+                .or_else(|| axis_dtype(x).map(float_if_numeric)),
         }
-        PaletteColor::<Palette9999>::pick(self.color_index)
     }
 }
 
@@ -54,47 +1792,67 @@ impl Chart {
 impl Chart {
     /// Creates new Chart on a Canvas.
     ///
+    /// ## Chart Range
+    /// x_range, y_range: ez.Range of the x-axis and y-axis.
+    ///     If omitted, the range is inferred from the data when the canvas is saved or displayed.
+    ///     A f64 range can be drawn on a log or symlog scale, e.g. ez.Range.f64(1, 1e6, scale="log").
+    ///     An inferred range is always linear, so pass the range to use a log or symlog scale.
+    /// padding: the ratio of the data span added to both ends of an inferred range (default 0.05).
+    ///
     /// ## Chart Styles
     /// caption: the title of the chart (TODO: caption font)
     ///     caption_size: caption font size in px
     ///     caption_font: "sans-serif", "serif", "monospace", or font name
-    /// margin: the space between the canvas and the chart
+    /// margin: the space between the canvas and the chart, in px (e.g. 20), a fraction (0.05)
+    ///     or a percentage ("5%") of the canvas. Defaults to 2.5% of the shorter side.
+    ///     margin_left, margin_right: relative to the canvas width. precedes margin.
+    ///     margin_top, margin_bottom: relative to the canvas height. precedes margin.
+    /// color: the background color of the plotting area (default: white)
     ///
     /// ## Axis Styles
-    /// x_label (TODO: font)
-    /// y_label (TODO: font)
-    /// label_area:            space(px) for the top/bottom/left/right label areas
-    ///     label_area_x:      space(px) for top, bottom
-    ///     label_area_y:      space(px) for left, right
-    ///     label_area_left:   space(px) for the left label area. precedes label_area.
-    ///     label_area_right:  space(px) for right left label area. precedes label_area.
-    ///     label_area_top:    space(px) for the top label area. precedes label_area.
-    ///     label_area_bottom: space(px) for the bottom label area. precedes label_area.
+    /// x_label: the title of the x-axis, drawn below the tick labels
+    /// y_label: the title of the y-axis, drawn left of the tick labels
+    ///     axis_label_font:  "sans-serif" (default), "serif", "monospace", or font name
+    ///     axis_label_size:  font size in px (default 16)
+    ///     axis_label_color: a color name, hex string, tuple, or ez.Color (default: black)
+    ///     y_label_rotation: 270 (default, reads bottom to top), 90 (top to bottom), or 0 (horizontal)
+    /// x_tick_format, y_tick_format: the format of the tick labels.
+    ///     On a datetime, date, time or duration axis, a strftime string, e.g. "%m-%d %H:%M".
+    ///     On a numeric axis, a Python format spec or template, e.g. ".2f", ",.0f" or "{:.1%}".
+    ///     Or a callable that takes the tick value and returns its label.
+    /// label_area:            space for the top/bottom/left/right label areas
+    ///     label_area_left:   space for the left label area. precedes label_area.
+    ///     label_area_right:  space for the right label area. precedes label_area.
+    ///     label_area_top:    space for the top label area. precedes label_area.
+    ///     label_area_bottom: space for the bottom label area. precedes label_area.
+    ///     A space is in px (e.g. 40), a fraction (0.1) or a percentage ("10%") of the canvas,
+    ///     of its width for left and right, and of its height for top and bottom.
     ///
     #[new]
     pub fn new(
         py: Python,
         canvas: Py<Canvas>,
-        x_range: Py<Range>,
-        y_range: Py<Range>,
+        x_range: Option<Py<Range>>,
+        y_range: Option<Py<Range>>,
+        padding: Option<f64>,
         //
-        margin: Option<i32>,
-        margin_left: Option<i32>,
-        margin_right: Option<i32>,
-        margin_top: Option<i32>,
-        margin_bottom: Option<i32>,
+        margin: Option<Size>,
+        margin_left: Option<Size>,
+        margin_right: Option<Size>,
+        margin_top: Option<Size>,
+        margin_bottom: Option<Size>,
         //
-        label_area: Option<i32>,
-        label_area_left: Option<i32>,
-        label_area_right: Option<i32>,
-        label_area_top: Option<i32>,
-        label_area_bottom: Option<i32>,
+        label_area: Option<Size>,
+        label_area_left: Option<Size>,
+        label_area_right: Option<Size>,
+        label_area_top: Option<Size>,
+        label_area_bottom: Option<Size>,
         //
         caption: Option<&str>,
         caption_font: Option<&str>,
         caption_size: Option<u32>,
         //
-        _color: Option<&str>,
+        color: Option<ColorArg>,
         // mesh (grid) and axis
         mesh_x: Option<bool>,
         mesh_y: Option<bool>,
@@ -102,314 +1860,942 @@ impl Chart {
         axis_y: Option<bool>,
         axis_x_label_max: Option<usize>,
         axis_y_label_max: Option<usize>,
+        // axis titles
+        x_label: Option<&str>,
+        y_label: Option<&str>,
+        axis_label_font: Option<&str>,
+        axis_label_size: Option<u32>,
+        axis_label_color: Option<ColorArg>,
+        y_label_rotation: Option<i32>,
+        // tick labels
+        x_tick_format: Option<TickFormat>,
+        y_tick_format: Option<TickFormat>,
     ) -> PyResult<Self> {
-        let x_range = x_range.borrow(py);
-        let y_range = y_range.borrow(py);
-        let canvas_ref = canvas.borrow_mut(py);
-        let mesh_x = mesh_x.unwrap_or(true);
-        let mesh_y = mesh_y.unwrap_or(true);
-        let axis_x = axis_x.unwrap_or(true);
-        let axis_y = axis_y.unwrap_or(true);
-        let axis_x_label_max = axis_x_label_max.unwrap_or(10);
-        let axis_y_label_max = axis_y_label_max.unwrap_or(10);
-
-        let mut b = ChartBuilder::on(unsafe { static_reference(&canvas_ref.area) });
-        if let Some(v) = margin {
-            b.margin(v);
-        }
-        if let Some(v) = margin_left {
-            b.margin_left(v);
-        }
-        if let Some(v) = margin_right {
-            b.margin_right(v);
-        }
-        if let Some(v) = margin_top {
-            b.margin_top(v);
-        }
-        if let Some(v) = margin_bottom {
-            b.margin_bottom(v);
-        }
-
-        // user specified no margin at all
-        if margin.is_none()
-            && margin_left.is_none()
-            && margin_right.is_none()
-            && margin_top.is_none()
-            && margin_bottom.is_none()
-        {
-            b.margin_left(20); // TODO: change to percentage
-            b.margin_right(20);
-            b.margin_top(20);
-            b.margin_bottom(20);
-        }
-
-        if label_area.is_none()
-            && label_area_left.is_none()
-            && label_area_right.is_none()
-            && label_area_top.is_none()
-            && label_area_bottom.is_none()
-        {
-            b.set_left_and_bottom_label_area_size(20);
-        }
-
-        if let Some(v) = label_area {
-            b.set_all_label_area_size(v);
-        }
-        if let Some(v) = label_area_left {
-            b.y_label_area_size(v);
-        }
-        if let Some(v) = label_area_right {
-            b.right_y_label_area_size(v);
-        }
-        if let Some(v) = label_area_top {
-            b.top_x_label_area_size(v);
-        }
-        if let Some(v) = label_area_bottom {
-            b.x_label_area_size(v);
-        }
-        if let Some(v) = caption {
-            b.caption(
-                v,
-                (
-                    caption_font.unwrap_or("sans-serif"),
-                    caption_size.unwrap_or(20),
-                ),
-            );
+        let y_label_rotation = y_label_rotation.unwrap_or(270);
+        if ![0, 90, 270].contains(&y_label_rotation) {
+            return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                "y_label_rotation should be 0, 90 or 270, got {}",
+                y_label_rotation
+            )));
         }
-        drop(canvas_ref);
-
-        let x_dtype = x_range.dtype();
-        let y_dtype = y_range.dtype();
+        let config = ChartConfig {
+            area: canvas.borrow(py).area.clone(),
+            margin,
+            margin_left,
+            margin_right,
+            margin_top,
+            margin_bottom,
+            label_area,
+            label_area_left,
+            label_area_right,
+            label_area_top,
+            label_area_bottom,
+            caption: caption.map(|s| s.to_string()),
+            caption_font: caption_font.map(|s| s.to_string()),
+            caption_size,
+            background: color.map(|c| c.0).unwrap_or_else(|| WHITE.to_rgba()),
+            mesh_x: mesh_x.unwrap_or(true),
+            mesh_y: mesh_y.unwrap_or(true),
+            axis_x: axis_x.unwrap_or(true),
+            axis_y: axis_y.unwrap_or(true),
+            axis_x_label_max: axis_x_label_max.unwrap_or(10),
+            axis_y_label_max: axis_y_label_max.unwrap_or(10),
+            x_label: x_label.map(|s| s.to_string()),
+            y_label: y_label.map(|s| s.to_string()),
+            axis_label_font: axis_label_font.unwrap_or("sans-serif").to_string(),
+            axis_label_size: axis_label_size.unwrap_or(16),
+            axis_label_color: axis_label_color
+                .map(|c| c.0)
+                .unwrap_or_else(|| BLACK.to_rgba()),
+            y_label_rotation,
+            x_tick_format,
+            y_tick_format,
+            x_range: x_range.as_ref().map(|r| r.borrow(py).range.clone()),
+            y_range: y_range.as_ref().map(|r| r.borrow(py).range.clone()),
+            x_scale: x_range.map_or(Scale::Linear, |r| r.borrow(py).scale),
+            y_scale: y_range.map_or(Scale::Linear, |r| r.borrow(py).scale),
+            padding: padding.unwrap_or(0.05),
+        };
 
-        let inner = match (x_dtype, y_dtype) {
-            (Dtype::F64, Dtype::F64) => {
-                let x_range =
-                    flowutils::unwrap_pattern!(x_range.range, RangeEnum::F64(a,b) => (a..b));
-                let y_range =
-                    flowutils::unwrap_pattern!(y_range.range, RangeEnum::F64(a,b) => (a..b));
-                let mut chart = b.build_cartesian_2d(x_range, y_range).unwrap();
-                chart.plotting_area().fill(&WHITE).unwrap();
-                {
-                    let mut mesh = chart.configure_mesh();
-                    if !mesh_x {
-                        mesh.disable_x_mesh();
-                    }
-                    if !mesh_y {
-                        mesh.disable_y_mesh();
-                    }
-                    if !axis_x {
-                        mesh.disable_x_axis();
-                    }
-                    if !axis_y {
-                        mesh.disable_y_axis();
-                    }
-                    mesh.x_labels(axis_x_label_max);
-                    mesh.y_labels(axis_y_label_max);
-                    mesh.draw().unwrap();
-                }
-                TypedChart::F64F64(chart)
-            }
-            (Dtype::NaiveDateTime, Dtype::F64) => {
-                let x_range: RangedDateTime<chrono::NaiveDateTime> =
-                    flowutils::unwrap_pattern!(x_range.range, RangeEnum::DateTime(a,b) => (a..b))
-                        .into();
-                let y_range =
-                    flowutils::unwrap_pattern!(y_range.range, RangeEnum::F64(a,b) => (a..b));
-                let mut chart = b.build_cartesian_2d(x_range, y_range).unwrap();
-                chart.plotting_area().fill(&WHITE).unwrap();
-                {
-                    let mut mesh = chart.configure_mesh();
-                    if !mesh_x {
-                        mesh.disable_x_mesh();
-                    }
-                    if !mesh_y {
-                        mesh.disable_y_mesh();
-                    }
-                    if !axis_x {
-                        mesh.disable_x_axis();
-                    }
-                    if !axis_y {
-                        mesh.disable_y_axis();
-                    }
-                    mesh.x_labels(axis_x_label_max);
-                    mesh.y_labels(axis_y_label_max);
-                    mesh.draw().unwrap();
-                }
-                TypedChart::DateTimeF64(chart)
-            }
-            (Dtype::NaiveDate, Dtype::F64) => {
-                let x_range: RangedDate<chrono::NaiveDate> =
-                    flowutils::unwrap_pattern!(x_range.range, RangeEnum::Date(a,b) => (a..b))
-                        .into();
-                let y_range =
-                    flowutils::unwrap_pattern!(y_range.range, RangeEnum::F64(a,b) => (a..b));
-                let mut chart = b.build_cartesian_2d(x_range, y_range).unwrap();
-                chart.plotting_area().fill(&WHITE).unwrap();
-                {
-                    let mut mesh = chart.configure_mesh();
-                    if !mesh_x {
-                        mesh.disable_x_mesh();
-                    }
-                    if !mesh_y {
-                        mesh.disable_y_mesh();
-                    }
-                    if !axis_x {
-                        mesh.disable_x_axis();
-                    }
-                    if !axis_y {
-                        mesh.disable_y_axis();
-                    }
-                    mesh.x_labels(axis_x_label_max);
-                    mesh.y_labels(axis_y_label_max);
-                    mesh.draw().unwrap();
-                }
-                TypedChart::DateF64(chart)
-            }
-            (Dtype::Duration, Dtype::F64) => {
-                let x_range: RangedDuration =
-                    flowutils::unwrap_pattern!(x_range.range, RangeEnum::Duration(a,b) => (a..b))
-                        .into();
-                let y_range =
-                    flowutils::unwrap_pattern!(y_range.range, RangeEnum::F64(a,b) => (a..b));
-                let mut chart = b.build_cartesian_2d(x_range, y_range).unwrap();
-                chart.plotting_area().fill(&WHITE).unwrap();
-                {
-                    let mut mesh = chart.configure_mesh();
-                    if !mesh_x {
-                        mesh.disable_x_mesh();
-                    }
-                    if !mesh_y {
-                        mesh.disable_y_mesh();
-                    }
-                    if !axis_x {
-                        mesh.disable_x_axis();
-                    }
-                    if !axis_y {
-                        mesh.disable_y_axis();
-                    }
-                    mesh.x_labels(axis_x_label_max);
-                    mesh.y_labels(axis_y_label_max);
-                    mesh.draw().unwrap();
-                }
-                TypedChart::DurationF64(chart)
-            }
-            _ => {
-                println!("x {:?} y {:?}", x_dtype, y_dtype);
-                unreachable!()
+        let state = match (&config.x_range, &config.y_range) {
+            (Some(x_range), Some(y_range)) => {
+                ChartState::Ready(config.build(py, x_range.clone(), y_range.clone())?)
             }
+            _ => ChartState::Deferred {
+                config,
+                ops: Vec::new(),
+            },
         };
+        let state = Rc::new(RefCell::new(state));
+        if let ChartState::Deferred { .. } = &*state.borrow() {
+            canvas
+                .borrow(py)
+                .backend
+                .deferred
+                .borrow_mut()
+                .push(state.clone());
+        }
         Ok(Self {
+            state,
             _canvas: canvas,
-            inner,
             color_index: 0,
-            x_dtype,
-            y_dtype,
         })
     }
 
     /// x and y accepts a native Python list or a 1D numpy.ndarray.
-    /// _color parameter is not implemented yet.
-    #[text_signature = "($self, x, y, _color=None, filled=None, stroke_width=None)"]
+    /// A list of str is drawn on a categorical axis, see `ez.Range.categories`.
+    /// color: a color name, hex string, tuple, or ez.Color. Defaults to the next color in the palette.
+    /// label: the name of the series in the legend.
+    #[text_signature = "($self, x, y, color=None, filled=None, stroke_width=None, label=None)"]
     pub fn line(
         &mut self,
         py: Python,
         x: Series,
         y: Series,
-        _color: Option<&str>,
+        color: Option<ColorArg>,
         filled: Option<bool>,
         stroke_width: Option<u32>,
+        label: Option<String>,
     ) -> PyResult<()> {
-        assert!(x.len(py) == y.len(py));
-        let color = ShapeStyle {
-            color: self.next_color().to_rgba(),
+        same_len(py, &x, &y)?;
+        let style = ShapeStyle {
+            color: self.series_color(color),
             filled: filled.unwrap_or(true),
             stroke_width: stroke_width.unwrap_or(3),
         };
+        self.apply(py, Op::Line { x, y, style, label })
+    }
 
-        match &mut self.inner {
-            TypedChart::F64F64(ref mut c) => {
-                c.draw_series(LineSeries::new(x.iter_f64(py).zip(y.iter_f64(py)), color))
-                    .unwrap();
-            }
-            TypedChart::DateTimeF64(ref mut c) => {
-                c.draw_series(LineSeries::new(
-                    x.iter_datetime(py).zip(y.iter_f64(py)),
-                    color,
-                ))
-                .unwrap();
-            }
-            TypedChart::DateF64(ref mut c) => {
-                c.draw_series(LineSeries::new(x.iter_date(py).zip(y.iter_f64(py)), color))
-                    .unwrap();
-            }
-            TypedChart::DurationF64(ref mut c) => {
-                c.draw_series(LineSeries::new(
-                    x.iter_duration(py).zip(y.iter_f64(py)),
-                    color,
-                ))
-                .unwrap();
+    /// Draws a step line, e.g. a counter or a quantized signal.
+    /// x and y accepts a native Python list, a 1D numpy.ndarray, or ezel chrono types.
+    ///
+    /// where: "pre" (default) draws y[i] on (x[i - 1], x[i]], "post" on [x[i], x[i + 1]),
+    ///     and "mid" changes y halfway between the points.
+    /// color: a color name, hex string, tuple, or ez.Color. Defaults to the next color in the palette.
+    /// label: the name of the series in the legend.
+    #[text_signature = "($self, x, y, where=\"pre\", color=None, stroke_width=None, label=None)"]
+    pub fn step(
+        &mut self,
+        py: Python,
+        x: Series,
+        y: Series,
+        r#where: Option<Step>,
+        color: Option<ColorArg>,
+        stroke_width: Option<u32>,
+        label: Option<String>,
+    ) -> PyResult<()> {
+        same_len(py, &x, &y)?;
+        let style = ShapeStyle {
+            color: self.series_color(color),
+            filled: true,
+            stroke_width: stroke_width.unwrap_or(3),
+        };
+        let step = r#where.unwrap_or(Step::Pre);
+        self.apply(
+            py,
+            Op::Step {
+                x,
+                y,
+                step,
+                style,
+                label,
+            },
+        )
+    }
+
+    /// Draws a stem from the baseline to each point, e.g. sparse impulses.
+    /// x and y accepts a native Python list, a 1D numpy.ndarray, or ezel chrono types.
+    ///
+    /// baseline: a series, or a number on a numeric y-axis (default 0).
+    /// color: a color name, hex string, tuple, or ez.Color. Defaults to the next color in the palette.
+    /// label: the name of the series in the legend.
+    #[text_signature = "($self, x, y, baseline=0, color=None, stroke_width=None, label=None)"]
+    pub fn stem(
+        &mut self,
+        py: Python,
+        x: Series,
+        y: Series,
+        baseline: Option<Baseline>,
+        color: Option<ColorArg>,
+        stroke_width: Option<u32>,
+        label: Option<String>,
+    ) -> PyResult<()> {
+        same_len(py, &x, &y)?;
+        let baseline = baseline.unwrap_or(Baseline::Scalar(0.0));
+        if let Baseline::Series(s) = &baseline {
+            if s.len(py) != x.len(py) {
+                return Err(Error::LengthMismatch {
+                    what: "baseline".to_string(),
+                    expected: x.len(py),
+                    actual: s.len(py),
+                }
+                .into());
             }
         }
-        Ok(())
+        let style = ShapeStyle {
+            color: self.series_color(color),
+            filled: true,
+            stroke_width: stroke_width.unwrap_or(1),
+        };
+        let stem = StemSeries { x, y, baseline };
+        self.apply(py, Op::Stem { stem, style, label })
     }
-    
 
     /// x and y accepts a native Python list or a 1D numpy.ndarray.
-    /// _color parameter is not implemented yet.
-    #[text_signature = "($self, x, y, size, _color=None, filled=None, stroke_width=None)"]
+    /// A list of str is drawn on a categorical axis, see `ez.Range.categories`.
+    /// size: the marker radius in pixels (default 5), or a series of a radius for each point.
+    /// color: a color name, hex string, tuple, or ez.Color. Defaults to the next color in the palette.
+    ///     Or a list or 1D numpy.ndarray of a value for each point, mapped through cmap.
+    ///     Points of NaN are not drawn.
+    /// label: the name of the series in the legend.
+    /// marker: "circle" (default), "square", "triangle", "cross", "x" or "diamond".
+    /// cmap: a colormap name, ez.Colormap or ez.Palette for color values (default: "viridis").
+    /// vmin, vmax: the color values at both ends of cmap. Default to the min and max of the values.
+    #[text_signature = "($self, x, y, size=None, color=None, filled=None, stroke_width=None, label=None, marker=\"circle\", cmap=\"viridis\", vmin=None, vmax=None)"]
     pub fn scatter(
         &mut self,
         py: Python,
         x: Series,
         y: Series,
-        size: Option<u32>,
-        _color: Option<&str>,
+        size: Option<SizeArg>,
+        color: Option<PointColorArg>,
         filled: Option<bool>,
         stroke_width: Option<u32>,
+        label: Option<String>,
+        marker: Option<Marker>,
+        cmap: Option<CmapArg>,
+        vmin: Option<f64>,
+        vmax: Option<f64>,
     ) -> PyResult<()> {
-        assert!(x.len(py) == y.len(py));
-        let size = size.unwrap_or(5);
-        let color = ShapeStyle {
-            color: self.next_color().to_rgba(),
+        same_len(py, &x, &y)?;
+        let check = |name: &str, s: &Series| {
+            if s.len(py) != x.len(py) {
+                return Err(Error::LengthMismatch {
+                    what: name.to_string(),
+                    expected: x.len(py),
+                    actual: s.len(py),
+                });
+            }
+            Ok(())
+        };
+        let (size, sizes) = match size {
+            None => (5, None),
+            Some(SizeArg::Scalar(size)) => (size, None),
+            Some(SizeArg::Series(s)) => {
+                check("size", &s)?;
+                // NaN and negative sizes are 0
+                let sizes = s.iter_f64(py)?.map(|v| v.max(0.0).round() as u32);
+                (5, Some(sizes.collect()))
+            }
+        };
+        let (color, colors) = match color {
+            Some(PointColorArg::Values(values)) => {
+                check("color", &values)?;
+                let cmap = cmap.unwrap_or_else(|| Colormap::viridis().into());
+                let values: Vec<f64> = values.iter_f64(py)?.collect();
+                (cmap.eval(0.5), Some(cmap.map(&values, vmin, vmax)))
+            }
+            Some(PointColorArg::Color(color)) => (self.series_color(Some(color)), None),
+            None => (self.series_color(None), None),
+        };
+        let style = ShapeStyle {
+            color,
             filled: filled.unwrap_or(true),
             stroke_width: stroke_width.unwrap_or(3),
         };
+        let points = ScatterSeries {
+            x,
+            y,
+            marker: marker.unwrap_or(Marker::Circle),
+            size,
+            sizes,
+            colors,
+        };
+        self.apply(
+            py,
+            Op::Scatter {
+                points,
+                style,
+                label,
+            },
+        )
+    }
 
-        match &mut self.inner {
-            TypedChart::F64F64(ref mut c) => {
-                c.draw_series(
-                    x.iter_f64(py)
-                        .zip(y.iter_f64(py))
-                        .map(|(x, y)| Circle::new((x, y), size, color.clone())),
-                )
-                .unwrap();
-            }
-            TypedChart::DateTimeF64(ref mut c) => {
-                c.draw_series(
-                    x.iter_datetime(py)
-                        .zip(y.iter_f64(py))
-                        .map(|(x, y)| Circle::new((x, y), size, color.clone())),
-                )
-                .unwrap();
-            }
-            TypedChart::DateF64(ref mut c) => {
-                c.draw_series(
-                    x.iter_date(py)
-                        .zip(y.iter_f64(py))
-                        .map(|(x, y)| Circle::new((x, y), size, color.clone())),
-                )
-                .unwrap();
-            }
-            TypedChart::DurationF64(ref mut c) => {
-                c.draw_series(
-                    x.iter_duration(py)
-                        .zip(y.iter_f64(py))
-                        .map(|(x, y)| Circle::new((x, y), size, color.clone())),
-                )
-                .unwrap();
+    /// Fills the area between y and the baseline, and draws y as a line.
+    /// x and y accepts a native Python list, a 1D numpy.ndarray, or ezel chrono types.
+    ///
+    /// baseline: a number or a series on the y-axis (default 0).
+    /// color: a color name, hex string, tuple, or ez.Color. Defaults to the next color in the palette.
+    /// alpha: the opacity of the fill, from 0 to 1 (default 0.3). The line is opaque.
+    /// stroke_width: the width of the line (default 2).
+    /// label: the name of the series in the legend.
+    #[text_signature = "($self, x, y, baseline=0, color=None, alpha=0.3, stroke_width=None, label=None)"]
+    pub fn area(
+        &mut self,
+        py: Python,
+        x: Series,
+        y: Series,
+        baseline: Option<Baseline>,
+        color: Option<ColorArg>,
+        alpha: Option<f64>,
+        stroke_width: Option<u32>,
+        label: Option<String>,
+    ) -> PyResult<()> {
+        let baseline = baseline.unwrap_or(Baseline::Scalar(0.0));
+        let fill = self.fill_series(py, x, y, baseline, None, true)?;
+        let (style, line_style) = self.fill_style(color, alpha, stroke_width)?;
+        self.apply(
+            py,
+            Op::Fill {
+                fill,
+                style,
+                line_style,
+                label,
+            },
+        )
+    }
+
+    /// Fills the area between y1 and y2, e.g. a confidence band.
+    /// x, y1 and y2 accepts a native Python list, a 1D numpy.ndarray, or ezel chrono types.
+    ///
+    /// y2: a series, or a number on a numeric y-axis (default 0).
+    /// where: a list or array of bools. Only the runs of consecutive True points are filled.
+    /// color: a color name, hex string, tuple, or ez.Color. Defaults to the next color in the palette.
+    /// alpha: the opacity of the fill, from 0 to 1 (default 0.3).
+    /// label: the name of the series in the legend.
+    #[text_signature = "($self, x, y1, y2=0, where=None, color=None, alpha=0.3, label=None)"]
+    pub fn fill_between(
+        &mut self,
+        py: Python,
+        x: Series,
+        y1: Series,
+        y2: Option<Baseline>,
+        r#where: Option<Mask>,
+        color: Option<ColorArg>,
+        alpha: Option<f64>,
+        label: Option<String>,
+    ) -> PyResult<()> {
+        let y2 = y2.unwrap_or(Baseline::Scalar(0.0));
+        let fill = self.fill_series(py, x, y1, y2, r#where, false)?;
+        let (style, line_style) = self.fill_style(color, alpha, None)?;
+        self.apply(
+            py,
+            Op::Fill {
+                fill,
+                style,
+                line_style,
+                label,
+            },
+        )
+    }
+
+    /// Draws a heatmap of a 2D array. Row i of z is drawn at y_edges[i]..y_edges[i + 1],
+    /// and column j at x_edges[j]..x_edges[j + 1].
+    ///
+    /// z: a 2D numpy.ndarray of float or int, or a list of lists of numbers. NaN cells are not drawn.
+    /// x_edges, y_edges: the cell edges, numbers or ezel chrono types, one more than the cells.
+    ///     Or the cell centers with cells 1 unit wide, e.g. a list of str on a categorical axis.
+    ///     The cell of a date is the whole day.
+    ///     Defaults to the centers 0, 1, .., so that row 0 is at the bottom.
+    /// cmap: a colormap name, e.g. "viridis" (default), "magma", "rdbu", or an ez.Colormap or ez.Palette.
+    /// vmin, vmax: the values at both ends of the colormap. Default to the min and max of z.
+    #[text_signature = "($self, z, x_edges=None, y_edges=None, cmap=\"viridis\", vmin=None, vmax=None)"]
+    pub fn heatmap(
+        &mut self,
+        py: Python,
+        z: Grid,
+        x_edges: Option<Series>,
+        y_edges: Option<Series>,
+        cmap: Option<CmapArg>,
+        vmin: Option<f64>,
+        vmax: Option<f64>,
+    ) -> PyResult<()> {
+        let x = self.cell_edges(py, x_edges, z.cols, "x_edges")?;
+        let y = self.cell_edges(py, y_edges, z.rows, "y_edges")?;
+        let cmap = cmap.unwrap_or_else(|| Colormap::viridis().into());
+        let cells = CellSeries::heatmap(x, y, &z, cmap, vmin, vmax);
+        self.apply(py, Op::Cells { cells })
+    }
+
+    /// Draws an image with a cell per pixel. The top row of the image is drawn at the top.
+    ///
+    /// image: a numpy.ndarray of shape (height, width, 3) or (height, width, 4),
+    ///     either uint8 or float in [0, 1].
+    /// x_edges, y_edges: the pixel edges or centers, as in `heatmap`. Default to the centers 0, 1, ..
+    #[text_signature = "($self, image, x_edges=None, y_edges=None)"]
+    pub fn imshow(
+        &mut self,
+        py: Python,
+        image: Image,
+        x_edges: Option<Series>,
+        y_edges: Option<Series>,
+    ) -> PyResult<()> {
+        let x = self.cell_edges(py, x_edges, image.cols, "x_edges")?;
+        let y = self.cell_edges(py, y_edges, image.rows, "y_edges")?;
+        let cells = CellSeries::image(x, y, image);
+        self.apply(py, Op::Cells { cells })
+    }
+
+    /// Draws a box plot of each group.
+    /// The box spans the quartiles with a line at the median, and the whiskers reach
+    /// the most extreme values within `whis` times the interquartile range from the box.
+    ///
+    /// groups: a series, or a list of series. The statistics are computed in Rust.
+    /// positions: the positions of the groups on the x-axis, numbers, ezel chrono types,
+    ///     or a list of str on a categorical axis. Defaults to 0, 1, .., len(groups) - 1.
+    /// width: the box width in x units, or days on chrono axes (default 0.5).
+    /// whis: the whisker reach in interquartile ranges (default 1.5).
+    /// show_outliers: draws the values beyond the whiskers as circles (default True).
+    /// color: a color name, hex string, tuple, or ez.Color. Defaults to the next color in the palette.
+    /// alpha: the opacity of the box fill, from 0 to 1 (default 0.3). The lines are opaque.
+    /// label: the name of the series in the legend.
+    #[text_signature = "($self, groups, positions=None, width=0.5, whis=1.5, show_outliers=True, color=None, alpha=0.3, stroke_width=None, label=None)"]
+    pub fn boxplot(
+        &mut self,
+        py: Python,
+        groups: Groups,
+        positions: Option<Series>,
+        width: Option<f64>,
+        whis: Option<f64>,
+        show_outliers: Option<bool>,
+        color: Option<ColorArg>,
+        alpha: Option<f64>,
+        stroke_width: Option<u32>,
+        label: Option<String>,
+    ) -> PyResult<()> {
+        let whis = whis.unwrap_or(1.5);
+        let stats = groups
+            .sorted(py)?
+            .iter()
+            .map(|values| BoxStats::new(values, whis))
+            .collect();
+        let glyphs = Glyphs::Boxes {
+            stats,
+            show_outliers: show_outliers.unwrap_or(true),
+        };
+        let boxes = self.box_series(py, &groups, positions, width, glyphs)?;
+        let (fill_style, line_style) = self.fill_style(color, alpha, stroke_width.or(Some(1)))?;
+        self.apply(
+            py,
+            Op::Boxes {
+                boxes,
+                line_style,
+                fill_style,
+                label,
+            },
+        )
+    }
+
+    /// Draws a violin plot of each group, the kernel density estimate mirrored around its position.
+    /// The violin spans the min and max of the group, with a line at the median.
+    ///
+    /// groups: a series, or a list of series. The densities are computed in Rust.
+    /// positions: the positions of the groups on the x-axis, numbers, ezel chrono types,
+    ///     or a list of str on a categorical axis. Defaults to 0, 1, .., len(groups) - 1.
+    /// width: the maximum violin width in x units, or days on chrono axes (default 0.5).
+    /// bandwidth: the standard deviation of the gaussian kernel in y units.
+    ///     Defaults to Scott's rule. Groups over 2048 values are binned before the estimate.
+    /// color: a color name, hex string, tuple, or ez.Color. Defaults to the next color in the palette.
+    /// alpha: the opacity of the fill, from 0 to 1 (default 0.3). The lines are opaque.
+    /// label: the name of the series in the legend.
+    #[text_signature = "($self, groups, positions=None, width=0.5, bandwidth=None, color=None, alpha=0.3, stroke_width=None, label=None)"]
+    pub fn violin(
+        &mut self,
+        py: Python,
+        groups: Groups,
+        positions: Option<Series>,
+        width: Option<f64>,
+        bandwidth: Option<f64>,
+        color: Option<ColorArg>,
+        alpha: Option<f64>,
+        stroke_width: Option<u32>,
+        label: Option<String>,
+    ) -> PyResult<()> {
+        if let Some(b) = bandwidth {
+            if b.is_nan() || b <= 0.0 {
+                return Err(pyo3::exceptions::PyValueError::new_err(
+                    "bandwidth should be positive",
+                ));
             }
         }
+        let violins = groups
+            .sorted(py)?
+            .iter()
+            .map(|values| Violin::new(values, bandwidth))
+            .collect();
+        let boxes = self.box_series(py, &groups, positions, width, Glyphs::Violins(violins))?;
+        let (fill_style, line_style) = self.fill_style(color, alpha, stroke_width.or(Some(1)))?;
+        self.apply(
+            py,
+            Op::Boxes {
+                boxes,
+                line_style,
+                fill_style,
+                label,
+            },
+        )
+    }
 
-        Ok(())
+    /// Draws a candlestick at each time t. The body spans open and close, and the wick low and high.
+    /// t accepts ezel chrono types (e.g. ez.DateTime, ez.Date), a native Python list,
+    /// or a 1D numpy.ndarray. The prices accept a native Python list or a 1D numpy.ndarray.
+    ///
+    /// up_color: the color when close > open (default: #26a69a).
+    /// down_color: the color otherwise (default: #ef5350).
+    /// width: the body width in px. Defaults to 70% of the gap between the closest candles.
+    /// label: the name of the series in the legend.
+    #[text_signature = "($self, t, open, high, low, close, up_color=None, down_color=None, width=None, stroke_width=None, label=None)"]
+    pub fn candlestick(
+        &mut self,
+        py: Python,
+        t: Series,
+        open: Series,
+        high: Series,
+        low: Series,
+        close: Series,
+        up_color: Option<ColorArg>,
+        down_color: Option<ColorArg>,
+        width: Option<u32>,
+        stroke_width: Option<u32>,
+        label: Option<String>,
+    ) -> PyResult<()> {
+        self.ohlc_glyphs(
+            py,
+            Glyph::Candlestick,
+            t,
+            open,
+            high,
+            low,
+            close,
+            up_color,
+            down_color,
+            width,
+            stroke_width,
+            label,
+        )
+    }
+
+    /// Draws an OHLC bar at each time t: a line from low to high,
+    /// with a tick at open on the left and a tick at close on the right.
+    /// The arguments are the same as `candlestick`. width is the length of both ticks in px.
+    #[text_signature = "($self, t, open, high, low, close, up_color=None, down_color=None, width=None, stroke_width=None, label=None)"]
+    pub fn ohlc(
+        &mut self,
+        py: Python,
+        t: Series,
+        open: Series,
+        high: Series,
+        low: Series,
+        close: Series,
+        up_color: Option<ColorArg>,
+        down_color: Option<ColorArg>,
+        width: Option<u32>,
+        stroke_width: Option<u32>,
+        label: Option<String>,
+    ) -> PyResult<()> {
+        self.ohlc_glyphs(
+            py,
+            Glyph::Ohlc,
+            t,
+            open,
+            high,
+            low,
+            close,
+            up_color,
+            down_color,
+            width,
+            stroke_width,
+            label,
+        )
+    }
+
+    /// Draws error bars at the points (x, y).
+    /// x and y accepts a native Python list, a 1D numpy.ndarray, or ezel chrono types.
+    ///
+    /// yerr, xerr: the errors of y and x. A number for every point, a series of symmetric errors,
+    ///     or a pair of series (lower, upper), e.g. a 2xN numpy array.
+    ///     Errors are in axis units, or days on chrono axes. At least one of them is required.
+    /// cap_size: the length of the caps in px (default 6). The point is marked by a circle of half the size.
+    /// color: a color name, hex string, tuple, or ez.Color. Defaults to the next color in the palette.
+    /// label: the name of the series in the legend.
+    #[text_signature = "($self, x, y, yerr=None, xerr=None, cap_size=6, color=None, stroke_width=None, label=None)"]
+    pub fn errorbar(
+        &mut self,
+        py: Python,
+        x: Series,
+        y: Series,
+        yerr: Option<ErrArg>,
+        xerr: Option<ErrArg>,
+        cap_size: Option<u32>,
+        color: Option<ColorArg>,
+        stroke_width: Option<u32>,
+        label: Option<String>,
+    ) -> PyResult<()> {
+        same_len(py, &x, &y)?;
+        if yerr.is_none() && xerr.is_none() {
+            return Err(pyo3::exceptions::PyValueError::new_err(
+                "errorbar needs yerr or xerr",
+            ));
+        }
+        let n = x.len(py);
+        let bars = ErrorBarSeries {
+            yerr: yerr.map(|e| e.bounds(py, n, "yerr")).transpose()?,
+            xerr: xerr.map(|e| e.bounds(py, n, "xerr")).transpose()?,
+            x,
+            y,
+        };
+        let style = ShapeStyle {
+            color: self.series_color(color),
+            filled: true,
+            stroke_width: stroke_width.unwrap_or(1),
+        };
+        self.apply(
+            py,
+            Op::ErrorBar {
+                bars,
+                style,
+                cap_size: cap_size.unwrap_or(6),
+                label,
+            },
+        )
+    }
+
+    /// Jupyter rich display. Displays the root canvas of the chart.
+    pub fn _repr_png_<'py>(&self, py: Python<'py>) -> PyResult<Option<&'py PyBytes>> {
+        self._canvas.borrow(py)._repr_png_(py)
+    }
+
+    /// Jupyter rich display. Displays the root canvas of the chart.
+    pub fn _repr_svg_(&self, py: Python) -> PyResult<Option<String>> {
+        self._canvas.borrow(py)._repr_svg_(py)
+    }
+
+    /// Jupyter rich display. Displays the root canvas of the chart.
+    pub fn _repr_html_(&self, py: Python) -> PyResult<String> {
+        self._canvas.borrow(py)._repr_html_(py)
+    }
+
+    /// Draws a histogram of x. The bar heights are drawn on the y-axis.
+    /// x accepts a native Python list, a 1D numpy.ndarray, or ezel chrono types.
+    ///
+    /// bins: the number of equal-width bins (default 10),
+    ///     or a calendar unit for chrono axes: "year", "month", "week", "day", "hour", "minute", "second".
+    ///     Bins on an integer or date axis span a whole number of units,
+    ///     so there are fewer bins than asked when the data spans fewer values.
+    ///     A duration axis accepts only fixed-length units (week or shorter), a time axis only units shorter than a day.
+    /// range: ez.Range of the same dtype as the x-axis. Defaults to the min and max of x.
+    ///     Values outside of the range are ignored.
+    /// density: normalize the bars so that the total area is 1.
+    ///     The bin width is measured in seconds on chrono axes.
+    /// cumulative: each bar is the sum of all bars on its left.
+    /// color: a color name, hex string, tuple, or ez.Color. Defaults to the next color in the palette.
+    /// label: the name of the series in the legend.
+    #[text_signature = "($self, x, bins=10, range=None, density=False, cumulative=False, color=None, filled=None, stroke_width=None, label=None)"]
+    pub fn hist(
+        &mut self,
+        py: Python,
+        x: Series,
+        bins: Option<Bins>,
+        range: Option<Py<Range>>,
+        density: Option<bool>,
+        cumulative: Option<bool>,
+        color: Option<ColorArg>,
+        filled: Option<bool>,
+        stroke_width: Option<u32>,
+        label: Option<String>,
+    ) -> PyResult<()> {
+        let bins = bins.unwrap_or(Bins::Count(10));
+        let range = range.map(|r| r.borrow(py).range.clone());
+        let density = density.unwrap_or(false);
+        let cumulative = cumulative.unwrap_or(false);
+        let style = ShapeStyle {
+            color: self.series_color(color),
+            filled: filled.unwrap_or(true),
+            stroke_width: stroke_width.unwrap_or(1),
+        };
+        let x_dtype = self.x_dtype(&x).ok_or_else(|| {
+            pyo3::exceptions::PyValueError::new_err(
+                "cannot infer the x-axis dtype of the histogram",
+            )
+        })?;
+        let bars = hist::bin(py, x_dtype, &x, bins, range, density, cumulative)?;
+        self.apply(py, Op::Bars { bars, style, label })
+    }
+
+    /// Draws vertical bars at x. The bars span from `bottom` to `bottom + height`.
+    /// x accepts a native Python list, a 1D numpy.ndarray, or ezel chrono types.
+    /// A list of str places one bar per category on a categorical axis.
+    ///
+    /// width: the bar width in x units, or days on chrono axes. Defaults to 0.8
+    ///     (a day on a date axis, 0.8 day on a datetime axis, 48 minutes on a time or duration axis).
+    ///     Or a calendar unit: "year", "month", "week", "day", "hour", "minute", "second",
+    ///     in which case a bar spans the whole unit containing x.
+    /// bottom: a number or a series of the bar bottoms (default 0). A series stacks bars.
+    /// align: "center" (default) centers the bars at x, "edge" starts them at x.
+    /// color: a color name, hex string, tuple, or ez.Color. Defaults to the next color in the palette.
+    /// label: the name of the series in the legend.
+    #[text_signature = "($self, x, height, width=None, bottom=0, align=\"center\", color=None, filled=None, stroke_width=None, label=None)"]
+    pub fn bar(
+        &mut self,
+        py: Python,
+        x: Series,
+        height: Series,
+        width: Option<BarWidth>,
+        bottom: Option<Baseline>,
+        align: Option<&str>,
+        color: Option<ColorArg>,
+        filled: Option<bool>,
+        stroke_width: Option<u32>,
+        label: Option<String>,
+    ) -> PyResult<()> {
+        let bar = self.bar_series(py, x, height, width, bottom, align, false)?;
+        let style = self.bar_style(color, filled, stroke_width);
+        self.apply(py, Op::Bar { bar, style, label })
+    }
+
+    /// Draws horizontal bars at y. The bars span from `left` to `left + width`.
+    /// y accepts a native Python list, a 1D numpy.ndarray, or ezel chrono types.
+    /// A list of str places one bar per category on a categorical axis.
+    ///
+    /// height: the bar thickness in y units, or days on chrono axes. See `bar` for the defaults
+    ///     and calendar units.
+    /// left: a number or a series of the bar starts (default 0). A series stacks bars.
+    /// align: "center" (default) centers the bars at y, "edge" starts them at y.
+    /// color: a color name, hex string, tuple, or ez.Color. Defaults to the next color in the palette.
+    /// label: the name of the series in the legend.
+    #[text_signature = "($self, y, width, height=None, left=0, align=\"center\", color=None, filled=None, stroke_width=None, label=None)"]
+    pub fn barh(
+        &mut self,
+        py: Python,
+        y: Series,
+        width: Series,
+        height: Option<BarWidth>,
+        left: Option<Baseline>,
+        align: Option<&str>,
+        color: Option<ColorArg>,
+        filled: Option<bool>,
+        stroke_width: Option<u32>,
+        label: Option<String>,
+    ) -> PyResult<()> {
+        let bar = self.bar_series(py, y, width, height, left, align, true)?;
+        let style = self.bar_style(color, filled, stroke_width);
+        self.apply(py, Op::Bar { bar, style, label })
+    }
+
+    /// Draws a horizontal line across the chart at y, e.g. a threshold.
+    ///
+    /// y: a number, or a datetime.datetime, date, time or timedelta on a chrono y-axis.
+    /// color: a color name, hex string, tuple, or ez.Color. Defaults to the next color in the palette.
+    /// label: the name of the line in the legend.
+    #[text_signature = "($self, y, color=None, stroke_width=None, label=None)"]
+    pub fn axhline(
+        &mut self,
+        py: Python,
+        y: Value,
+        color: Option<ColorArg>,
+        stroke_width: Option<u32>,
+        label: Option<String>,
+    ) -> PyResult<()> {
+        let style = self.reference_style(color, stroke_width);
+        let annotation = Annotation::HLine(y);
+        self.apply(
+            py,
+            Op::Annotation {
+                annotation,
+                style,
+                label,
+            },
+        )
+    }
+
+    /// Draws a vertical line across the chart at x, e.g. a deploy time.
+    ///
+    /// x: a number, or a datetime.datetime, date, time or timedelta on a chrono x-axis.
+    /// color: a color name, hex string, tuple, or ez.Color. Defaults to the next color in the palette.
+    /// label: the name of the line in the legend.
+    #[text_signature = "($self, x, color=None, stroke_width=None, label=None)"]
+    pub fn axvline(
+        &mut self,
+        py: Python,
+        x: Value,
+        color: Option<ColorArg>,
+        stroke_width: Option<u32>,
+        label: Option<String>,
+    ) -> PyResult<()> {
+        let style = self.reference_style(color, stroke_width);
+        let annotation = Annotation::VLine(x);
+        self.apply(
+            py,
+            Op::Annotation {
+                annotation,
+                style,
+                label,
+            },
+        )
+    }
+
+    /// Fills a horizontal band across the chart from ymin to ymax.
+    ///
+    /// ymin, ymax: numbers, or datetime.datetime, date, time or timedelta on a chrono y-axis.
+    /// color: a color name, hex string, tuple, or ez.Color. Defaults to the next color in the palette.
+    /// alpha: the opacity of the band (default 0.3).
+    /// label: the name of the band in the legend.
+    #[text_signature = "($self, ymin, ymax, color=None, alpha=0.3, label=None)"]
+    pub fn axhspan(
+        &mut self,
+        py: Python,
+        ymin: Value,
+        ymax: Value,
+        color: Option<ColorArg>,
+        alpha: Option<f64>,
+        label: Option<String>,
+    ) -> PyResult<()> {
+        let (style, _) = self.fill_style(color, alpha, None)?;
+        let annotation = Annotation::HSpan(ymin, ymax);
+        self.apply(
+            py,
+            Op::Annotation {
+                annotation,
+                style,
+                label,
+            },
+        )
+    }
+
+    /// Fills a vertical band across the chart from xmin to xmax, e.g. an incident.
+    ///
+    /// xmin, xmax: numbers, or datetime.datetime, date, time or timedelta on a chrono x-axis.
+    /// color: a color name, hex string, tuple, or ez.Color. Defaults to the next color in the palette.
+    /// alpha: the opacity of the band (default 0.3).
+    /// label: the name of the band in the legend.
+    #[text_signature = "($self, xmin, xmax, color=None, alpha=0.3, label=None)"]
+    pub fn axvspan(
+        &mut self,
+        py: Python,
+        xmin: Value,
+        xmax: Value,
+        color: Option<ColorArg>,
+        alpha: Option<f64>,
+        label: Option<String>,
+    ) -> PyResult<()> {
+        let (style, _) = self.fill_style(color, alpha, None)?;
+        let annotation = Annotation::VSpan(xmin, xmax);
+        self.apply(
+            py,
+            Op::Annotation {
+                annotation,
+                style,
+                label,
+            },
+        )
+    }
+
+    /// Draws a text at (x, y) in data coordinates.
+    ///
+    /// x, y: a number, a datetime.datetime, date, time or timedelta, or a category name.
+    /// font: "sans-serif" (default), "serif", "monospace", or font name
+    /// size: font size in px (default 12)
+    /// color: a color name, hex string, tuple, or ez.Color. Defaults to black.
+    /// anchor: the point of the text placed at (x, y), "upper left", "upper center", "upper right",
+    ///     "center left", "center", "center right", "lower left", "lower center", or "lower right".
+    ///     Defaults to "lower left".
+    #[text_signature = "($self, x, y, s, font=\"sans-serif\", size=12, color=None, anchor=\"lower left\")"]
+    pub fn text(
+        &mut self,
+        py: Python,
+        x: Value,
+        y: Value,
+        s: String,
+        font: Option<&str>,
+        size: Option<u32>,
+        color: Option<ColorArg>,
+        anchor: Option<&str>,
+    ) -> PyResult<()> {
+        let label = text_label(s, font, size, anchor)?;
+        let annotation = Annotation::Text {
+            label,
+            xy: (x, y),
+            xytext: None,
+            arrow: false,
+        };
+        self.apply(
+            py,
+            Op::Annotation {
+                annotation,
+                style: text_style(color),
+                label: None,
+            },
+        )
+    }
+
+    /// Draws a text pointing at xy, e.g. `chart.annotate("deploy", (t, 0.5), (t2, 0.9))`.
+    ///
+    /// xy: the (x, y) pointed at, in data coordinates.
+    /// xytext: the (x, y) of the text. Defaults to xy.
+    /// arrow: draw an arrow from the text to xy (default True). Only drawn with xytext.
+    /// font: "sans-serif" (default), "serif", "monospace", or font name
+    /// size: font size in px (default 12)
+    /// color: the color of the text and the arrow, a color name, hex string, tuple, or ez.Color.
+    ///     Defaults to black.
+    /// anchor: the point of the text placed at xytext, see `text`.
+    ///     Defaults to the side away from xy.
+    #[text_signature = "($self, s, xy, xytext=None, arrow=True, font=\"sans-serif\", size=12, color=None, anchor=None)"]
+    pub fn annotate(
+        &mut self,
+        py: Python,
+        s: String,
+        xy: (Value, Value),
+        xytext: Option<(Value, Value)>,
+        arrow: Option<bool>,
+        font: Option<&str>,
+        size: Option<u32>,
+        color: Option<ColorArg>,
+        anchor: Option<&str>,
+    ) -> PyResult<()> {
+        let label = text_label(s, font, size, anchor)?;
+        let annotation = Annotation::Text {
+            label,
+            xy,
+            xytext,
+            arrow: arrow.unwrap_or(true),
+        };
+        self.apply(
+            py,
+            Op::Annotation {
+                annotation,
+                style: text_style(color),
+                label: None,
+            },
+        )
+    }
+
+    /// Draws the legend of the labeled series drawn so far.
+    /// Call it after drawing the series. A series without `label=` is not listed.
+    ///
+    /// position: "upper left", "upper center", "upper right", "center left", "center",
+    ///     "center right", "lower left", "lower center", or "lower right". Defaults to "upper right".
+    /// frame: draw a border and a translucent background behind the legend (default True).
+    /// font: "sans-serif" (default), "serif", "monospace", or font name
+    /// font_size: font size in px (default 12)
+    #[text_signature = "($self, position=\"upper right\", frame=True, font=\"sans-serif\", font_size=12)"]
+    pub fn legend(
+        &mut self,
+        py: Python,
+        position: Option<&str>,
+        frame: Option<bool>,
+        font: Option<&str>,
+        font_size: Option<u32>,
+    ) -> PyResult<()> {
+        let position = position.unwrap_or("upper right");
+        legend_position(position)?;
+        self.apply(
+            py,
+            Op::Legend {
+                position: position.to_string(),
+                frame: frame.unwrap_or(true),
+                font: font.unwrap_or("sans-serif").to_string(),
+                font_size: font_size.unwrap_or(12),
+            },
+        )
     }
 }
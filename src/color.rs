@@ -1,48 +1,803 @@
-// //! color1 = ezel.Color.turbo(0.3)
-// //! color2 = ezel.Color.viridis(0.5)
-// //! ..
-// use pyo3::prelude::*;
-
-// #[pyclass]
-// struct ContinuousColorScheme {
-//     inner: colorous::Gradient,
-// }
-
-// #[pymethods]
-// impl ContinuousColorScheme {
-//     pub fn any_scheme() -> Self {
-//         let mut rng = rand::thread_rng();
-//         match rng.gen::<usize>() % 10 {
-//         }
-
-//     }
-//     pub fn pick(t: f64) -> colorous::Color {
-//         Color{inner:self.inner.eval_continuous(t)}
-//     }
-// }
-
-// #[pyclass]
-// struct DiscreteColorScheme {
-//     inner: &'static [colorous::Color; 10],
-// }
-
-// #[pymethods]
-// impl DiscreteColorScheme {
-//     pub fn pick(t: usize) -> colorous::Color {
-//         Color{inner:self.inner[t]}
-//     }
-// }
-
-// #[pyclass]
-// struct Color {
-//     inner: colorous::Color
-// }
-
-// macro_rules!();
-
-// #[pymethods]
-// impl Color {
-//     fn turbo(t: f64) -> PyResult<> {
-//         ;
-//     }
-// }
+//! Conversion from Python color representations to plotters colors.
+//!
+//! Accepted forms:
+//! - CSS named colors: "red", "steelblue", "transparent", ..
+//! - hex strings: "#rgb", "#rgba", "#rrggbb", "#rrggbbaa"
+//! - functional strings: "rgb(255, 0, 0)", "rgba(255, 0, 0, 0.5)", "hsl(120, 100%, 50%)", "hsla(..)"
+//! - tuples: (r, g, b) or (r, g, b, a), either int 0..255 or float 0..1 for r, g, b.
+//!     a is always float 0..1. Values out of range are clamped.
+//! - ez.Color objects
+//!
+//! Colormaps and palettes are exposed as `ez.Colormap.viridis`, `ez.Palette.tableau10`, ..
+//! and accepted by `cmap=`.
+
+use plotters::prelude::*;
+use plotters::style::{Color as _, RGBAColor};
+use pyo3::prelude::*;
+use pyo3::PyObjectProtocol;
+
+/// An adapter between Python color representations and plotters' RGBAColor.
+/// This is used in the Python<->Rust API boundary, like Series.
+#[derive(Clone)]
+pub struct ColorArg(pub RGBAColor);
+
+impl<'source> FromPyObject<'source> for ColorArg {
+    fn extract(x: &'source PyAny) -> PyResult<Self> {
+        if let Ok(c) = x.extract::<PyRef<Color>>() {
+            return Ok(ColorArg(c.inner.clone()));
+        }
+        if let Ok(s) = x.extract::<&str>() {
+            return parse(s).map(ColorArg).ok_or_else(|| {
+                pyo3::exceptions::PyValueError::new_err(format!("invalid color {:?}", s))
+            });
+        }
+        if let Ok((r, g, b)) = x.extract::<(i64, i64, i64)>() {
+            return Ok(ColorArg(int_rgb(r, g, b).to_rgba()));
+        }
+        if let Ok((r, g, b, a)) = x.extract::<(i64, i64, i64, f64)>() {
+            return Ok(ColorArg(int_rgb(r, g, b).mix(clamp01(a))));
+        }
+        if let Ok((r, g, b)) = x.extract::<(f64, f64, f64)>() {
+            return Ok(ColorArg(unit_rgb(r, g, b).to_rgba()));
+        }
+        if let Ok((r, g, b, a)) = x.extract::<(f64, f64, f64, f64)>() {
+            return Ok(ColorArg(unit_rgb(r, g, b).mix(clamp01(a))));
+        }
+        Err(pyo3::exceptions::PyValueError::new_err(
+            "color should be a string, a (r, g, b) / (r, g, b, a) tuple, or an ez.Color",
+        ))
+    }
+}
+
+fn clamp01(v: f64) -> f64 {
+    v.max(0.0).min(1.0)
+}
+
+/// Int channels out of 0..255 are clamped, like float channels out of 0..1.
+fn int_rgb(r: i64, g: i64, b: i64) -> RGBColor {
+    let c = |v: i64| v.clamp(0, 255) as u8;
+    RGBColor(c(r), c(g), c(b))
+}
+
+fn unit_rgb(r: f64, g: f64, b: f64) -> RGBColor {
+    let c = |v: f64| (clamp01(v) * 255.0).round() as u8;
+    RGBColor(c(r), c(g), c(b))
+}
+
+/// Parses a CSS color string. Case and surrounding whitespace are ignored.
+pub fn parse(s: &str) -> Option<RGBAColor> {
+    let s = s.trim().to_ascii_lowercase();
+    if let Some(hex) = s.strip_prefix('#') {
+        return parse_hex(hex);
+    }
+    if let Some(args) = function_args(&s, "rgba").or_else(|| function_args(&s, "rgb")) {
+        return parse_rgb(&args);
+    }
+    if let Some(args) = function_args(&s, "hsla").or_else(|| function_args(&s, "hsl")) {
+        return parse_hsl(&args);
+    }
+    if s == "transparent" {
+        return Some(RGBColor(0, 0, 0).mix(0.0));
+    }
+    CSS_NAMED_COLORS
+        .binary_search_by_key(&s.as_str(), |&(name, _)| name)
+        .ok()
+        .map(|i| {
+            let (r, g, b) = CSS_NAMED_COLORS[i].1;
+            RGBColor(r, g, b).to_rgba()
+        })
+}
+
+fn parse_hex(hex: &str) -> Option<RGBAColor> {
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let digit = |i: usize| u8::from_str_radix(&hex[i..i + 1], 16).ok().map(|v| v * 17);
+    let byte = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    let (r, g, b, a) = match hex.len() {
+        3 => (digit(0)?, digit(1)?, digit(2)?, 255),
+        4 => (digit(0)?, digit(1)?, digit(2)?, digit(3)?),
+        6 => (byte(0)?, byte(2)?, byte(4)?, 255),
+        8 => (byte(0)?, byte(2)?, byte(4)?, byte(6)?),
+        _ => return None,
+    };
+    Some(RGBColor(r, g, b).mix(a as f64 / 255.0))
+}
+
+/// "rgb(1, 2, 3)" -> ["1", "2", "3"]. Both comma and space separators are accepted.
+fn function_args(s: &str, name: &str) -> Option<Vec<String>> {
+    let inner = s.strip_prefix(name)?.trim_start();
+    let inner = inner.strip_prefix('(')?.strip_suffix(')')?;
+    Some(
+        inner
+            .split(|c: char| c == ',' || c == '/' || c.is_whitespace())
+            .filter(|arg| !arg.is_empty())
+            .map(|arg| arg.to_string())
+            .collect(),
+    )
+}
+
+/// "50%" -> 0.5 of `scale`, "128" -> 128
+fn parse_number(arg: &str, scale: f64) -> Option<f64> {
+    match arg.strip_suffix('%') {
+        Some(percent) => percent.parse::<f64>().ok().map(|v| v / 100.0 * scale),
+        None => arg.parse::<f64>().ok(),
+    }
+}
+
+fn parse_alpha(args: &[String]) -> Option<f64> {
+    match args.get(3) {
+        Some(a) => parse_number(a, 1.0).map(clamp01),
+        None => Some(1.0),
+    }
+}
+
+fn parse_rgb(args: &[String]) -> Option<RGBAColor> {
+    if args.len() != 3 && args.len() != 4 {
+        return None;
+    }
+    let channel =
+        |i: usize| parse_number(&args[i], 255.0).map(|v| v.max(0.0).min(255.0).round() as u8);
+    Some(RGBColor(channel(0)?, channel(1)?, channel(2)?).mix(parse_alpha(args)?))
+}
+
+fn parse_hsl(args: &[String]) -> Option<RGBAColor> {
+    if args.len() != 3 && args.len() != 4 {
+        return None;
+    }
+    let h = args[0]
+        .strip_suffix("deg")
+        .unwrap_or(&args[0])
+        .parse::<f64>()
+        .ok()?;
+    let s = parse_number(&args[1], 1.0)?;
+    let l = parse_number(&args[2], 1.0)?;
+    Some(HSLColor(h.rem_euclid(360.0) / 360.0, s, l).mix(parse_alpha(args)?))
+}
+
+/// A colormap that maps [0, 1] to colors.
+/// Accepts a colormap name, e.g. "viridis", an ez.Colormap, or an ez.Palette,
+/// which maps equal bins of [0, 1] to its colors.
+#[derive(Clone)]
+pub enum CmapArg {
+    Gradient {
+        gradient: colorous::Gradient,
+        reversed: bool,
+    },
+    Palette(Vec<RGBAColor>),
+}
+
+impl<'source> FromPyObject<'source> for CmapArg {
+    fn extract(x: &'source PyAny) -> PyResult<Self> {
+        if let Ok(name) = x.extract::<&str>() {
+            return Ok(Colormap::new(name)?.into());
+        }
+        if let Ok(cmap) = x.extract::<PyRef<Colormap>>() {
+            return Ok((*cmap).into());
+        }
+        if let Ok(palette) = x.extract::<PyRef<Palette>>() {
+            return Ok(CmapArg::Palette(palette.colors.clone()));
+        }
+        Err(pyo3::exceptions::PyValueError::new_err(
+            "cmap should be a colormap name, an ez.Colormap, or an ez.Palette",
+        ))
+    }
+}
+
+impl From<Colormap> for CmapArg {
+    fn from(cmap: Colormap) -> Self {
+        CmapArg::Gradient {
+            gradient: cmap.gradient(),
+            reversed: cmap.reversed,
+        }
+    }
+}
+
+impl CmapArg {
+    /// The color at t, clamped to [0, 1].
+    pub fn eval(&self, t: f64) -> RGBAColor {
+        match self {
+            CmapArg::Gradient { gradient, reversed } => {
+                let t = if *reversed {
+                    1.0 - clamp01(t)
+                } else {
+                    clamp01(t)
+                };
+                let c = gradient.eval_continuous(t);
+                RGBColor(c.r, c.g, c.b).to_rgba()
+            }
+            CmapArg::Palette(colors) => {
+                let i = (clamp01(t) * colors.len() as f64) as usize;
+                colors[i.min(colors.len() - 1)].clone()
+            }
+        }
+    }
+
+    /// The colors of the values from vmin to vmax, None for NaN.
+    /// vmin and vmax default to the min and max of the values.
+    pub fn map(
+        &self,
+        values: &[f64],
+        vmin: Option<f64>,
+        vmax: Option<f64>,
+    ) -> Vec<Option<RGBAColor>> {
+        let finite = || values.iter().cloned().filter(|v| v.is_finite());
+        let lo = vmin.unwrap_or_else(|| finite().fold(f64::INFINITY, f64::min));
+        let hi = vmax.unwrap_or_else(|| finite().fold(f64::NEG_INFINITY, f64::max));
+        values
+            .iter()
+            .map(|&v| {
+                if v.is_nan() {
+                    None
+                } else if hi > lo {
+                    Some(self.eval((v - lo) / (hi - lo)))
+                } else {
+                    Some(self.eval(0.5))
+                }
+            })
+            .collect()
+    }
+}
+
+/// A color. Accepts the same forms as `color=`, and can be passed to any `color=`.
+///
+/// ```py
+/// ez.Color("steelblue")
+/// ez.Color((0.2, 0.4, 0.6, 0.5)).hex()  # '#33669980'
+/// ```
+#[pyclass]
+#[derive(Clone)]
+pub struct Color {
+    pub inner: RGBAColor,
+}
+
+#[pymethods]
+impl Color {
+    #[new]
+    #[text_signature = "(color)"]
+    pub fn new(color: ColorArg) -> Self {
+        Color { inner: color.0 }
+    }
+
+    /// (r, g, b, a), where r, g, b are int 0..255 and a is float 0..1.
+    #[getter]
+    pub fn rgba(&self) -> (u8, u8, u8, f64) {
+        let (r, g, b) = self.inner.rgb();
+        (r, g, b, self.inner.alpha())
+    }
+
+    /// "#rrggbb", or "#rrggbbaa" if the color is not opaque.
+    #[text_signature = "($self)"]
+    pub fn hex(&self) -> String {
+        let (r, g, b, a) = self.rgba();
+        if a < 1.0 {
+            let a = (a * 255.0).round() as u8;
+            format!("#{:02x}{:02x}{:02x}{:02x}", r, g, b, a)
+        } else {
+            format!("#{:02x}{:02x}{:02x}", r, g, b)
+        }
+    }
+}
+
+#[pyproto]
+impl PyObjectProtocol for Color {
+    fn __repr__(&self) -> String {
+        format!("Color({:?})", self.hex())
+    }
+}
+
+/// Defines a `#[classattr]` per name, e.g. `ez.Colormap.viridis`, with the other methods.
+macro_rules! named_pymethods {
+    ($ty:ident, [$($name:ident),*], { $($methods:tt)* }) => {
+        #[pymethods]
+        impl $ty {
+            $(
+                #[classattr]
+                pub fn $name() -> Self {
+                    $ty::named(stringify!($name)).unwrap()
+                }
+            )*
+            $($methods)*
+        }
+    };
+}
+
+/// A continuous colormap from colorous. Can be passed to any `cmap=`.
+///
+/// ```py
+/// ez.Colormap.viridis.eval(0.3)
+/// ez.Colormap.rdbu.reverse()
+/// ez.Colormap("magma").sample(5)
+/// ```
+#[pyclass]
+#[derive(Clone, Copy)]
+pub struct Colormap {
+    name: &'static str,
+    reversed: bool,
+}
+
+impl Colormap {
+    fn named(name: &str) -> Option<Self> {
+        GRADIENTS
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(name, _)| Colormap {
+                name,
+                reversed: false,
+            })
+    }
+
+    fn gradient(&self) -> colorous::Gradient {
+        GRADIENTS.iter().find(|(n, _)| *n == self.name).unwrap().1
+    }
+}
+
+named_pymethods!(
+    Colormap,
+    [
+        viridis, inferno, magma, plasma, cividis, turbo, warm, cool, cubehelix, blues, greens,
+        greys, oranges, purples, reds, bugn, bupu, gnbu, orrd, pubugn, pubu, purd, rdpu, ylgnbu,
+        ylgn, ylorbr, ylorrd, brbg, prgn, piyg, puor, rdbu, rdgy, rdylbu, rdylgn, spectral,
+        rainbow, sinebow
+    ],
+    {
+        /// name: a colormap name in matplotlib style. Case is ignored, e.g. "RdBu".
+        #[new]
+        #[text_signature = "(name)"]
+        pub fn new(name: &str) -> PyResult<Self> {
+            Colormap::named(name).ok_or_else(|| {
+                pyo3::exceptions::PyValueError::new_err(format!(
+                    "unknown colormap {:?}, expected one of {}",
+                    name,
+                    GRADIENTS
+                        .iter()
+                        .map(|(name, _)| *name)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ))
+            })
+        }
+
+        /// The color at t, clamped to [0, 1].
+        #[text_signature = "($self, t)"]
+        pub fn eval(&self, t: f64) -> Color {
+            Color {
+                inner: CmapArg::from(*self).eval(t),
+            }
+        }
+
+        /// The colormap from 1 to 0.
+        #[text_signature = "($self)"]
+        pub fn reverse(&self) -> Self {
+            Colormap {
+                name: self.name,
+                reversed: !self.reversed,
+            }
+        }
+
+        /// A palette of n colors evenly spaced from 0 to 1.
+        #[text_signature = "($self, n)"]
+        pub fn sample(&self, n: usize) -> PyResult<Palette> {
+            check_sample(n)?;
+            let cmap = CmapArg::from(*self);
+            let t = |i: usize| {
+                if n > 1 {
+                    i as f64 / (n - 1) as f64
+                } else {
+                    0.5
+                }
+            };
+            Ok(Palette {
+                colors: (0..n).map(|i| cmap.eval(t(i))).collect(),
+            })
+        }
+    }
+);
+
+#[pyproto]
+impl PyObjectProtocol for Colormap {
+    fn __repr__(&self) -> String {
+        match self.reversed {
+            false => format!("Colormap.{}", self.name),
+            true => format!("Colormap.{}.reverse()", self.name),
+        }
+    }
+}
+
+fn check_sample(n: usize) -> PyResult<()> {
+    if n == 0 {
+        return Err(pyo3::exceptions::PyValueError::new_err(
+            "n should be positive",
+        ));
+    }
+    Ok(())
+}
+
+/// A list of distinct colors. Can be passed to any `cmap=`, where it maps equal bins of [0, 1]
+/// to its colors.
+///
+/// ```py
+/// ez.Palette.tableau10.eval(3)
+/// ez.Palette(["red", "green", "blue"]).reverse()
+/// ```
+#[pyclass]
+#[derive(Clone)]
+pub struct Palette {
+    pub colors: Vec<RGBAColor>,
+}
+
+impl Palette {
+    fn named(name: &str) -> Option<Self> {
+        PALETTES
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, colors)| Palette {
+                colors: colors
+                    .iter()
+                    .map(|c| RGBColor(c.r, c.g, c.b).to_rgba())
+                    .collect(),
+            })
+    }
+}
+
+named_pymethods!(
+    Palette,
+    [category10, accent, dark2, paired, pastel1, pastel2, set1, set2, set3, tableau10],
+    {
+        /// colors: a list of colors, or a palette name, e.g. "tableau10".
+        #[new]
+        #[text_signature = "(colors)"]
+        pub fn new(colors: &PyAny) -> PyResult<Self> {
+            if let Ok(name) = colors.extract::<&str>() {
+                return Palette::named(name).ok_or_else(|| {
+                    pyo3::exceptions::PyValueError::new_err(format!(
+                        "unknown palette {:?}, expected one of {}",
+                        name,
+                        PALETTES
+                            .iter()
+                            .map(|(name, _)| *name)
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ))
+                });
+            }
+            let colors: Vec<ColorArg> = colors.extract()?;
+            if colors.is_empty() {
+                return Err(pyo3::exceptions::PyValueError::new_err(
+                    "a palette should have a color",
+                ));
+            }
+            Ok(Palette {
+                colors: colors.into_iter().map(|c| c.0).collect(),
+            })
+        }
+
+        /// The i-th color. i wraps around the palette.
+        #[text_signature = "($self, i)"]
+        pub fn eval(&self, i: usize) -> Color {
+            Color {
+                inner: self.colors[i % self.colors.len()].clone(),
+            }
+        }
+
+        /// The colors in reverse order.
+        #[text_signature = "($self)"]
+        pub fn reverse(&self) -> Self {
+            Palette {
+                colors: self.colors.iter().rev().cloned().collect(),
+            }
+        }
+
+        /// A palette of the first n colors, wrapping around the palette.
+        #[text_signature = "($self, n)"]
+        pub fn sample(&self, n: usize) -> PyResult<Self> {
+            check_sample(n)?;
+            Ok(Palette {
+                colors: (0..n).map(|i| self.eval(i).inner).collect(),
+            })
+        }
+
+        #[getter]
+        pub fn colors(&self) -> Vec<Color> {
+            self.colors
+                .iter()
+                .map(|c| Color { inner: c.clone() })
+                .collect()
+        }
+    }
+);
+
+#[pyproto]
+impl PyObjectProtocol for Palette {
+    fn __repr__(&self) -> String {
+        let colors: Vec<String> = self.colors().iter().map(|c| c.hex()).collect();
+        format!("Palette({:?})", colors)
+    }
+}
+
+const GRADIENTS: [(&str, colorous::Gradient); 38] = [
+    // sequential
+    ("viridis", colorous::VIRIDIS),
+    ("inferno", colorous::INFERNO),
+    ("magma", colorous::MAGMA),
+    ("plasma", colorous::PLASMA),
+    ("cividis", colorous::CIVIDIS),
+    ("turbo", colorous::TURBO),
+    ("warm", colorous::WARM),
+    ("cool", colorous::COOL),
+    ("cubehelix", colorous::CUBEHELIX),
+    ("blues", colorous::BLUES),
+    ("greens", colorous::GREENS),
+    ("greys", colorous::GREYS),
+    ("oranges", colorous::ORANGES),
+    ("purples", colorous::PURPLES),
+    ("reds", colorous::REDS),
+    ("bugn", colorous::BLUE_GREEN),
+    ("bupu", colorous::BLUE_PURPLE),
+    ("gnbu", colorous::GREEN_BLUE),
+    ("orrd", colorous::ORANGE_RED),
+    ("pubugn", colorous::PURPLE_BLUE_GREEN),
+    ("pubu", colorous::PURPLE_BLUE),
+    ("purd", colorous::PURPLE_RED),
+    ("rdpu", colorous::RED_PURPLE),
+    ("ylgnbu", colorous::YELLOW_GREEN_BLUE),
+    ("ylgn", colorous::YELLOW_GREEN),
+    ("ylorbr", colorous::YELLOW_ORANGE_BROWN),
+    ("ylorrd", colorous::YELLOW_ORANGE_RED),
+    // diverging
+    ("brbg", colorous::BROWN_GREEN),
+    ("prgn", colorous::PURPLE_GREEN),
+    ("piyg", colorous::PINK_GREEN),
+    ("puor", colorous::PURPLE_ORANGE),
+    ("rdbu", colorous::RED_BLUE),
+    ("rdgy", colorous::RED_GREY),
+    ("rdylbu", colorous::RED_YELLOW_BLUE),
+    ("rdylgn", colorous::RED_YELLOW_GREEN),
+    ("spectral", colorous::SPECTRAL),
+    // cyclical
+    ("rainbow", colorous::RAINBOW),
+    ("sinebow", colorous::SINEBOW),
+];
+
+const PALETTES: [(&str, &[colorous::Color]); 10] = [
+    ("category10", &colorous::CATEGORY10),
+    ("accent", &colorous::ACCENT),
+    ("dark2", &colorous::DARK2),
+    ("paired", &colorous::PAIRED),
+    ("pastel1", &colorous::PASTEL1),
+    ("pastel2", &colorous::PASTEL2),
+    ("set1", &colorous::SET1),
+    ("set2", &colorous::SET2),
+    ("set3", &colorous::SET3),
+    ("tableau10", &colorous::TABLEAU10),
+];
+
+/// CSS Color Module Level 4 named colors, sorted by name.
+const CSS_NAMED_COLORS: [(&str, (u8, u8, u8)); 148] = [
+    ("aliceblue", (240, 248, 255)),
+    ("antiquewhite", (250, 235, 215)),
+    ("aqua", (0, 255, 255)),
+    ("aquamarine", (127, 255, 212)),
+    ("azure", (240, 255, 255)),
+    ("beige", (245, 245, 220)),
+    ("bisque", (255, 228, 196)),
+    ("black", (0, 0, 0)),
+    ("blanchedalmond", (255, 235, 205)),
+    ("blue", (0, 0, 255)),
+    ("blueviolet", (138, 43, 226)),
+    ("brown", (165, 42, 42)),
+    ("burlywood", (222, 184, 135)),
+    ("cadetblue", (95, 158, 160)),
+    ("chartreuse", (127, 255, 0)),
+    ("chocolate", (210, 105, 30)),
+    ("coral", (255, 127, 80)),
+    ("cornflowerblue", (100, 149, 237)),
+    ("cornsilk", (255, 248, 220)),
+    ("crimson", (220, 20, 60)),
+    ("cyan", (0, 255, 255)),
+    ("darkblue", (0, 0, 139)),
+    ("darkcyan", (0, 139, 139)),
+    ("darkgoldenrod", (184, 134, 11)),
+    ("darkgray", (169, 169, 169)),
+    ("darkgreen", (0, 100, 0)),
+    ("darkgrey", (169, 169, 169)),
+    ("darkkhaki", (189, 183, 107)),
+    ("darkmagenta", (139, 0, 139)),
+    ("darkolivegreen", (85, 107, 47)),
+    ("darkorange", (255, 140, 0)),
+    ("darkorchid", (153, 50, 204)),
+    ("darkred", (139, 0, 0)),
+    ("darksalmon", (233, 150, 122)),
+    ("darkseagreen", (143, 188, 143)),
+    ("darkslateblue", (72, 61, 139)),
+    ("darkslategray", (47, 79, 79)),
+    ("darkslategrey", (47, 79, 79)),
+    ("darkturquoise", (0, 206, 209)),
+    ("darkviolet", (148, 0, 211)),
+    ("deeppink", (255, 20, 147)),
+    ("deepskyblue", (0, 191, 255)),
+    ("dimgray", (105, 105, 105)),
+    ("dimgrey", (105, 105, 105)),
+    ("dodgerblue", (30, 144, 255)),
+    ("firebrick", (178, 34, 34)),
+    ("floralwhite", (255, 250, 240)),
+    ("forestgreen", (34, 139, 34)),
+    ("fuchsia", (255, 0, 255)),
+    ("gainsboro", (220, 220, 220)),
+    ("ghostwhite", (248, 248, 255)),
+    ("gold", (255, 215, 0)),
+    ("goldenrod", (218, 165, 32)),
+    ("gray", (128, 128, 128)),
+    ("green", (0, 128, 0)),
+    ("greenyellow", (173, 255, 47)),
+    ("grey", (128, 128, 128)),
+    ("honeydew", (240, 255, 240)),
+    ("hotpink", (255, 105, 180)),
+    ("indianred", (205, 92, 92)),
+    ("indigo", (75, 0, 130)),
+    ("ivory", (255, 255, 240)),
+    ("khaki", (240, 230, 140)),
+    ("lavender", (230, 230, 250)),
+    ("lavenderblush", (255, 240, 245)),
+    ("lawngreen", (124, 252, 0)),
+    ("lemonchiffon", (255, 250, 205)),
+    ("lightblue", (173, 216, 230)),
+    ("lightcoral", (240, 128, 128)),
+    ("lightcyan", (224, 255, 255)),
+    ("lightgoldenrodyellow", (250, 250, 210)),
+    ("lightgray", (211, 211, 211)),
+    ("lightgreen", (144, 238, 144)),
+    ("lightgrey", (211, 211, 211)),
+    ("lightpink", (255, 182, 193)),
+    ("lightsalmon", (255, 160, 122)),
+    ("lightseagreen", (32, 178, 170)),
+    ("lightskyblue", (135, 206, 250)),
+    ("lightslategray", (119, 136, 153)),
+    ("lightslategrey", (119, 136, 153)),
+    ("lightsteelblue", (176, 196, 222)),
+    ("lightyellow", (255, 255, 224)),
+    ("lime", (0, 255, 0)),
+    ("limegreen", (50, 205, 50)),
+    ("linen", (250, 240, 230)),
+    ("magenta", (255, 0, 255)),
+    ("maroon", (128, 0, 0)),
+    ("mediumaquamarine", (102, 205, 170)),
+    ("mediumblue", (0, 0, 205)),
+    ("mediumorchid", (186, 85, 211)),
+    ("mediumpurple", (147, 112, 219)),
+    ("mediumseagreen", (60, 179, 113)),
+    ("mediumslateblue", (123, 104, 238)),
+    ("mediumspringgreen", (0, 250, 154)),
+    ("mediumturquoise", (72, 209, 204)),
+    ("mediumvioletred", (199, 21, 133)),
+    ("midnightblue", (25, 25, 112)),
+    ("mintcream", (245, 255, 250)),
+    ("mistyrose", (255, 228, 225)),
+    ("moccasin", (255, 228, 181)),
+    ("navajowhite", (255, 222, 173)),
+    ("navy", (0, 0, 128)),
+    ("oldlace", (253, 245, 230)),
+    ("olive", (128, 128, 0)),
+    ("olivedrab", (107, 142, 35)),
+    ("orange", (255, 165, 0)),
+    ("orangered", (255, 69, 0)),
+    ("orchid", (218, 112, 214)),
+    ("palegoldenrod", (238, 232, 170)),
+    ("palegreen", (152, 251, 152)),
+    ("paleturquoise", (175, 238, 238)),
+    ("palevioletred", (219, 112, 147)),
+    ("papayawhip", (255, 239, 213)),
+    ("peachpuff", (255, 218, 185)),
+    ("peru", (205, 133, 63)),
+    ("pink", (255, 192, 203)),
+    ("plum", (221, 160, 221)),
+    ("powderblue", (176, 224, 230)),
+    ("purple", (128, 0, 128)),
+    ("rebeccapurple", (102, 51, 153)),
+    ("red", (255, 0, 0)),
+    ("rosybrown", (188, 143, 143)),
+    ("royalblue", (65, 105, 225)),
+    ("saddlebrown", (139, 69, 19)),
+    ("salmon", (250, 128, 114)),
+    ("sandybrown", (244, 164, 96)),
+    ("seagreen", (46, 139, 87)),
+    ("seashell", (255, 245, 238)),
+    ("sienna", (160, 82, 45)),
+    ("silver", (192, 192, 192)),
+    ("skyblue", (135, 206, 235)),
+    ("slateblue", (106, 90, 205)),
+    ("slategray", (112, 128, 144)),
+    ("slategrey", (112, 128, 144)),
+    ("snow", (255, 250, 250)),
+    ("springgreen", (0, 255, 127)),
+    ("steelblue", (70, 130, 180)),
+    ("tan", (210, 180, 140)),
+    ("teal", (0, 128, 128)),
+    ("thistle", (216, 191, 216)),
+    ("tomato", (255, 99, 71)),
+    ("turquoise", (64, 224, 208)),
+    ("violet", (238, 130, 238)),
+    ("wheat", (245, 222, 179)),
+    ("white", (255, 255, 255)),
+    ("whitesmoke", (245, 245, 245)),
+    ("yellow", (255, 255, 0)),
+    ("yellowgreen", (154, 205, 50)),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use plotters::style::Color as _;
+
+    fn rgba(s: &str) -> Option<(u8, u8, u8, f64)> {
+        parse(s).map(|c| {
+            let (r, g, b) = c.rgb();
+            (r, g, b, c.alpha())
+        })
+    }
+
+    #[test]
+    fn hex() {
+        assert_eq!(rgba("#f00"), Some((255, 0, 0, 1.0)));
+        assert_eq!(rgba("#f008"), Some((255, 0, 0, 136.0 / 255.0)));
+        assert_eq!(rgba("#1f77b4"), Some((31, 119, 180, 1.0)));
+        assert_eq!(rgba(" #1F77B480 "), Some((31, 119, 180, 128.0 / 255.0)));
+        assert_eq!(rgba("#12345"), None);
+        assert_eq!(rgba("#ggg"), None);
+        assert_eq!(rgba("#"), None);
+    }
+
+    #[test]
+    fn rgb_functions() {
+        assert_eq!(rgba("rgb(255, 0, 0)"), Some((255, 0, 0, 1.0)));
+        assert_eq!(rgba("rgb(255 128 0)"), Some((255, 128, 0, 1.0)));
+        assert_eq!(rgba("rgba(0, 0, 255, 0.5)"), Some((0, 0, 255, 0.5)));
+        assert_eq!(rgba("rgb(0 0 255 / 50%)"), Some((0, 0, 255, 0.5)));
+        assert_eq!(rgba("rgb(100%, 50%, 0%)"), Some((255, 128, 0, 1.0)));
+        // out of range channels and alpha are clamped
+        assert_eq!(rgba("rgba(300, -5, 0, 2)"), Some((255, 0, 0, 1.0)));
+        assert_eq!(rgba("rgb(1, 2)"), None);
+        assert_eq!(rgba("rgb(1, 2, x)"), None);
+        assert_eq!(rgba("rgb(1, 2, 3"), None);
+    }
+
+    #[test]
+    fn hsl_functions() {
+        assert_eq!(rgba("hsl(0, 100%, 50%)"), Some((255, 0, 0, 1.0)));
+        assert_eq!(rgba("hsl(120deg 100% 25%)"), Some((0, 128, 0, 1.0)));
+        assert_eq!(rgba("hsla(240, 100%, 50%, 0.25)"), Some((0, 0, 255, 0.25)));
+        // hue wraps around
+        assert_eq!(rgba("hsl(-240, 100%, 50%)"), rgba("hsl(120, 100%, 50%)"));
+        assert_eq!(rgba("hsl(0, 0%, 100%)"), Some((255, 255, 255, 1.0)));
+        assert_eq!(rgba("hsl(red, 100%, 50%)"), None);
+    }
+
+    #[test]
+    fn named() {
+        assert_eq!(rgba("red"), Some((255, 0, 0, 1.0)));
+        assert_eq!(rgba(" SteelBlue "), Some((70, 130, 180, 1.0)));
+        assert_eq!(rgba("rebeccapurple"), Some((102, 51, 153, 1.0)));
+        assert_eq!(rgba("transparent"), Some((0, 0, 0, 0.0)));
+        assert_eq!(rgba("notacolor"), None);
+        assert_eq!(rgba(""), None);
+    }
+
+    #[test]
+    fn named_colors_are_sorted() {
+        // parse() binary searches the table
+        assert!(CSS_NAMED_COLORS.windows(2).all(|w| w[0].0 < w[1].0));
+        for &(name, (r, g, b)) in CSS_NAMED_COLORS.iter() {
+            assert_eq!(rgba(name), Some((r, g, b, 1.0)), "{}", name);
+        }
+    }
+
+    #[test]
+    fn tuple_channels() {
+        assert_eq!(int_rgb(255, 128, 0).rgb(), (255, 128, 0));
+        assert_eq!(int_rgb(300, -1, 0).rgb(), (255, 0, 0));
+        assert_eq!(unit_rgb(1.0, 0.5, 0.0).rgb(), (255, 128, 0));
+        assert_eq!(unit_rgb(2.0, -0.5, f64::NAN).rgb(), (255, 0, 0));
+        assert_eq!(clamp01(1.5), 1.0);
+        assert_eq!(clamp01(-0.5), 0.0);
+    }
+}
@@ -0,0 +1,376 @@
+//! Coordinates of chart axes.
+//! This is not exposed to Python user and only used internally.
+//!
+//! Charts use `F64Coord` for every f64 axis instead of
+//! `RangedCoordf64` and `LogCoord<f64>`, so that the scale doesn't multiply chart types.
+//! `RangedTime` fills the gap of plotters, which has no coordinate for the time of day.
+//! `CategoryCoord` places string categories in the segments of a plotters segmented coordinate.
+//! `SeriesCoord` reads a series as the values of a coordinate.
+
+use crate::range::Scale;
+use crate::Series;
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+use plotters::coord::ranged1d::{
+    DefaultFormatting, IntoSegmentedCoord, KeyPointHint, NoDefaultFormatting, Ranged, SegmentValue,
+    SegmentedCoord, ValueFormatter,
+};
+use plotters::coord::types::{
+    RangedCoordf32, RangedCoordf64, RangedCoordi32, RangedCoordi64, RangedDateTime,
+};
+use plotters::prelude::*;
+use pyo3::prelude::*;
+use std::collections::HashMap;
+use std::ops::Range;
+use std::rc::Rc;
+
+pub enum F64Coord {
+    Linear(RangedCoordf64),
+    Log {
+        coord: LogCoord<f64>,
+        base: f64,
+    },
+    /// `linear` is the coordinate of the transformed values.
+    SymLog {
+        linear: RangedCoordf64,
+        range: Range<f64>,
+        scale: Scale,
+    },
+}
+
+impl F64Coord {
+    pub fn new(a: f64, b: f64, scale: Scale) -> Self {
+        match scale {
+            Scale::Linear => F64Coord::Linear((a..b).into()),
+            Scale::Log { base } => F64Coord::Log {
+                coord: (a..b).log_scale().into(),
+                base,
+            },
+            Scale::SymLog { .. } => F64Coord::SymLog {
+                linear: (scale.transform(a)..scale.transform(b)).into(),
+                range: a..b,
+                scale,
+            },
+        }
+    }
+}
+
+/// Picks at most `max` values spread evenly over `values`.
+fn thin(values: Vec<f64>, max: usize) -> Vec<f64> {
+    if max == 0 {
+        return Vec::new();
+    }
+    let step = (values.len() + max - 1) / max;
+    if step <= 1 {
+        return values;
+    }
+    values.into_iter().step_by(step).collect()
+}
+
+impl Ranged for F64Coord {
+    type FormatOption = NoDefaultFormatting;
+    type ValueType = f64;
+
+    fn map(&self, value: &f64, limit: (i32, i32)) -> i32 {
+        match self {
+            F64Coord::Linear(c) => c.map(value, limit),
+            F64Coord::Log { coord, .. } => coord.map(value, limit),
+            F64Coord::SymLog { linear, scale, .. } => linear.map(&scale.transform(*value), limit),
+        }
+    }
+
+    fn key_points<Hint: KeyPointHint>(&self, hint: Hint) -> Vec<f64> {
+        match self {
+            F64Coord::Linear(c) => c.key_points(hint),
+            F64Coord::Log { coord, base } if *base == 10.0 => coord.key_points(hint),
+            F64Coord::Log { coord, base } => {
+                // powers of the base
+                let Range { start, end } = coord.range();
+                let (lo, hi) = (start.min(end), start.max(end));
+                let mut points = Vec::new();
+                let mut k = lo.log(*base).ceil() as i32;
+                while base.powi(k) <= hi {
+                    points.push(base.powi(k));
+                    k += 1;
+                }
+                thin(points, hint.max_num_points())
+            }
+            F64Coord::SymLog { linear, scale, .. } => {
+                // 0 and ±linthresh * base^k are at the whole positions,
+                // which may be off by a rounding error at the ends
+                let Range { start, end } = linear.range();
+                let (lo, hi) = (start.min(end) - 1e-9, start.max(end) + 1e-9);
+                let points = (lo.ceil() as i32..=hi.floor() as i32)
+                    .map(|k| scale.inverse(k as f64))
+                    .collect();
+                thin(points, hint.max_num_points())
+            }
+        }
+    }
+
+    fn range(&self) -> Range<f64> {
+        match self {
+            F64Coord::Linear(c) => c.range(),
+            F64Coord::Log { coord, .. } => coord.range(),
+            F64Coord::SymLog { range, .. } => range.clone(),
+        }
+    }
+}
+
+impl ValueFormatter<f64> for F64Coord {
+    fn format(value: &f64) -> String {
+        let abs = value.abs();
+        if abs != 0.0 && (abs < 1e-3 || abs >= 1e6) {
+            format!("{:e}", value)
+        } else {
+            RangedCoordf64::format(value)
+        }
+    }
+}
+
+/// The time of day.
+pub struct RangedTime {
+    linear: RangedCoordf64, // seconds from midnight
+    range: Range<NaiveTime>,
+}
+
+fn seconds_from_midnight(t: &NaiveTime) -> f64 {
+    t.num_seconds_from_midnight() as f64 + t.nanosecond() as f64 * 1e-9
+}
+
+impl From<Range<NaiveTime>> for RangedTime {
+    fn from(range: Range<NaiveTime>) -> Self {
+        Self {
+            linear: (seconds_from_midnight(&range.start)..seconds_from_midnight(&range.end)).into(),
+            range,
+        }
+    }
+}
+
+impl Ranged for RangedTime {
+    type FormatOption = DefaultFormatting;
+    type ValueType = NaiveTime;
+
+    fn map(&self, value: &NaiveTime, limit: (i32, i32)) -> i32 {
+        self.linear.map(&seconds_from_midnight(value), limit)
+    }
+
+    fn key_points<Hint: KeyPointHint>(&self, hint: Hint) -> Vec<NaiveTime> {
+        // the smallest clock-friendly step that fits
+        const STEPS: [u32; 17] = [
+            1, 2, 5, 10, 15, 30, 60, 120, 300, 600, 900, 1800, 3600, 7200, 10800, 21600, 43200,
+        ];
+        let max = hint.max_num_points();
+        if max == 0 {
+            return Vec::new();
+        }
+        let lo = seconds_from_midnight(&self.range.start.min(self.range.end));
+        let hi = seconds_from_midnight(&self.range.start.max(self.range.end));
+        let step = STEPS
+            .iter()
+            .map(|&s| s as f64)
+            .find(|s| ((hi - lo) / s) as usize + 1 <= max)
+            .unwrap_or(86400.0);
+        let mut points = Vec::new();
+        let mut v = (lo / step).ceil() * step;
+        while v <= hi && v < 86400.0 {
+            points.push(NaiveTime::from_num_seconds_from_midnight(v as u32, 0));
+            v += step;
+        }
+        points
+    }
+
+    fn range(&self) -> Range<NaiveTime> {
+        self.range.clone()
+    }
+}
+
+/// A coordinate that can read a series as its values.
+pub trait SeriesCoord: Ranged + ValueFormatter<<Self as Ranged>::ValueType> + 'static {
+    fn values<'a>(
+        &self,
+        py: Python<'a>,
+        series: &'a Series,
+    ) -> PyResult<Box<dyn Iterator<Item = Self::ValueType> + 'a>>;
+
+    /// The default tick label format.
+    fn tick_formatter(&self) -> Box<dyn Fn(&Self::ValueType) -> String> {
+        Box::new(|v| Self::format(v))
+    }
+}
+
+impl SeriesCoord for F64Coord {
+    fn values<'a>(
+        &self,
+        py: Python<'a>,
+        series: &'a Series,
+    ) -> PyResult<Box<dyn Iterator<Item = f64> + 'a>> {
+        series.iter_f64(py)
+    }
+}
+
+impl SeriesCoord for RangedCoordf32 {
+    fn values<'a>(
+        &self,
+        py: Python<'a>,
+        series: &'a Series,
+    ) -> PyResult<Box<dyn Iterator<Item = f32> + 'a>> {
+        series.iter_f32(py)
+    }
+}
+
+impl SeriesCoord for RangedCoordi64 {
+    fn values<'a>(
+        &self,
+        py: Python<'a>,
+        series: &'a Series,
+    ) -> PyResult<Box<dyn Iterator<Item = i64> + 'a>> {
+        series.iter_i64(py)
+    }
+}
+
+impl SeriesCoord for RangedCoordi32 {
+    fn values<'a>(
+        &self,
+        py: Python<'a>,
+        series: &'a Series,
+    ) -> PyResult<Box<dyn Iterator<Item = i32> + 'a>> {
+        series.iter_i32(py)
+    }
+}
+
+impl SeriesCoord for RangedDateTime<NaiveDateTime> {
+    fn values<'a>(
+        &self,
+        py: Python<'a>,
+        series: &'a Series,
+    ) -> PyResult<Box<dyn Iterator<Item = NaiveDateTime> + 'a>> {
+        series.iter_datetime(py)
+    }
+}
+
+impl SeriesCoord for RangedDate<NaiveDate> {
+    fn values<'a>(
+        &self,
+        py: Python<'a>,
+        series: &'a Series,
+    ) -> PyResult<Box<dyn Iterator<Item = NaiveDate> + 'a>> {
+        series.iter_date(py)
+    }
+}
+
+impl SeriesCoord for RangedTime {
+    fn values<'a>(
+        &self,
+        py: Python<'a>,
+        series: &'a Series,
+    ) -> PyResult<Box<dyn Iterator<Item = NaiveTime> + 'a>> {
+        series.iter_time(py)
+    }
+}
+
+impl SeriesCoord for RangedDuration {
+    fn values<'a>(
+        &self,
+        py: Python<'a>,
+        series: &'a Series,
+    ) -> PyResult<Box<dyn Iterator<Item = chrono::Duration> + 'a>> {
+        series.iter_duration(py)
+    }
+}
+
+/// String categories at 0, 1, 2, .. in the center of their segments.
+/// Values between the centers, e.g. the edges of a bar, are placed linearly.
+pub struct CategoryCoord {
+    segmented: SegmentedCoord<RangedCoordi32>,
+    names: Rc<Vec<String>>,
+}
+
+impl CategoryCoord {
+    pub fn new(names: Rc<Vec<String>>) -> Self {
+        let last = names.len().max(1) as i32 - 1;
+        Self {
+            segmented: (0..last).into_segmented(),
+            names,
+        }
+    }
+
+    fn size(&self) -> usize {
+        self.names.len().max(1)
+    }
+}
+
+impl Ranged for CategoryCoord {
+    type FormatOption = NoDefaultFormatting;
+    type ValueType = f64;
+
+    fn map(&self, value: &f64, limit: (i32, i32)) -> i32 {
+        let i = value.round().max(0.0).min(self.size() as f64 - 1.0);
+        let center = self.segmented.map(&SegmentValue::CenterOf(i as i32), limit);
+        let segment = (limit.1 - limit.0) as f64 / self.size() as f64;
+        center + ((value - i) * segment).round() as i32
+    }
+
+    fn key_points<Hint: KeyPointHint>(&self, hint: Hint) -> Vec<f64> {
+        self.segmented
+            .key_points(hint)
+            .into_iter()
+            .filter_map(|v| match v {
+                SegmentValue::CenterOf(i) if (i as usize) < self.names.len() => Some(i as f64),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn range(&self) -> Range<f64> {
+        -0.5..self.size() as f64 - 0.5
+    }
+}
+
+impl ValueFormatter<f64> for CategoryCoord {
+    /// Only used without the coordinate. `tick_formatter` prints the names.
+    fn format(value: &f64) -> String {
+        format!("{}", value)
+    }
+}
+
+impl SeriesCoord for CategoryCoord {
+    /// Reads a list of str as the positions of the categories.
+    /// Integers are read as the indices of the categories, e.g. the default positions of a box plot.
+    fn values<'a>(
+        &self,
+        py: Python<'a>,
+        series: &'a Series,
+    ) -> PyResult<Box<dyn Iterator<Item = f64> + 'a>> {
+        if series.dtype().is_integer() {
+            return Ok(Box::new(series.iter_integer(py)?.map(|i| i as f64)));
+        }
+        let index: HashMap<&str, usize> = self
+            .names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| (name.as_str(), i))
+            .collect();
+        let values = series
+            .iter_str(py)?
+            .map(|name| match index.get(name) {
+                Some(&i) => Ok(i as f64),
+                None => Err(pyo3::exceptions::PyValueError::new_err(format!(
+                    "{:?} is not one of the axis categories",
+                    name
+                ))),
+            })
+            .collect::<PyResult<Vec<f64>>>()?;
+        Ok(Box::new(values.into_iter()))
+    }
+
+    fn tick_formatter(&self) -> Box<dyn Fn(&f64) -> String> {
+        let names = self.names.clone();
+        Box::new(move |v| {
+            let i = v.round();
+            match names.get(i as usize) {
+                Some(name) if i >= 0.0 => name.clone(),
+                _ => String::new(),
+            }
+        })
+    }
+}
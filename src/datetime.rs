@@ -18,10 +18,100 @@
 //! ezel.DateTime.iso8601(arr)
 //! ezel.DateTime.parse(arr, "%Y %b %d %H %M %S")
 
+use crate::error::Error;
 use crate::Series;
 use pyo3::prelude::*;
 use std::str::FromStr;
 
+/// A timestamp in `unit`s per second.
+fn from_timestamp(x: i64, unit: i64) -> PyResult<chrono::NaiveDateTime> {
+    let nanos = (x.rem_euclid(unit) * (1_000_000_000 / unit)) as u32;
+    chrono::NaiveDateTime::from_timestamp_opt(x.div_euclid(unit), nanos).ok_or_else(|| {
+        Error::OutOfRange {
+            what: format!("the timestamp {}", x),
+        }
+        .into()
+    })
+}
+
+/// Parses every string of the series. `format` is only used in the error message.
+fn parse_all<T, E: std::fmt::Display>(
+    py: Python,
+    series: &Series,
+    format: &str,
+    parse: impl Fn(&str) -> Result<T, E>,
+) -> PyResult<Vec<T>> {
+    series
+        .iter_str(py)?
+        .map(|x| {
+            parse(x).map_err(|e| {
+                Error::Parse {
+                    input: x.to_string(),
+                    format: format.to_string(),
+                    reason: e.to_string(),
+                }
+                .into()
+            })
+        })
+        .collect()
+}
+
+/// Parses an ISO 8601 duration like "P1DT2H30M" or "PT0.5S".
+/// Years and months have no fixed length and are rejected.
+fn parse_iso8601_duration(s: &str) -> Result<chrono::Duration, String> {
+    let rest = s.strip_prefix('P').ok_or("expected a leading 'P'")?;
+    let mut seconds = 0.0;
+    let mut in_time = false;
+    let mut number = String::new();
+    let mut any = false;
+    // a 'T' should be followed by an hour, minute or second
+    let mut after_t = false;
+    for c in rest.chars() {
+        let unit = match (in_time, c) {
+            (false, 'T') => {
+                in_time = true;
+                continue;
+            }
+            (_, '0'..='9') | (_, '.') => {
+                number.push(c);
+                continue;
+            }
+            (_, ',') => {
+                number.push('.');
+                continue;
+            }
+            (false, 'W') => 604800.0,
+            (false, 'D') => 86400.0,
+            (true, 'H') => 3600.0,
+            (true, 'M') => 60.0,
+            (true, 'S') => 1.0,
+            (false, 'Y') | (false, 'M') => {
+                return Err("years and months have no fixed length".to_string())
+            }
+            _ => return Err(format!("unexpected {:?}", c)),
+        };
+        let value = f64::from_str(&number).map_err(|_| format!("missing number before {:?}", c))?;
+        seconds += value * unit;
+        number.clear();
+        any = true;
+        after_t = in_time;
+    }
+    if !number.is_empty() {
+        return Err(format!("missing unit after {:?}", number));
+    }
+    if !any {
+        return Err("no duration components".to_string());
+    }
+    if in_time && !after_t {
+        return Err("missing time after 'T'".to_string());
+    }
+    let ns = (seconds * 1e9).round();
+    if ns >= i64::MAX as f64 {
+        return Err("the duration is too long".to_string());
+    }
+    Ok(chrono::Duration::nanoseconds(ns as i64))
+}
+
 #[pyclass]
 pub struct DateTime {
     pub vec: Vec<chrono::NaiveDateTime>,
@@ -37,78 +127,61 @@ impl DateTime {
 impl DateTime {
     #[staticmethod]
     #[text_signature = "(series)"]
-    pub fn timestamp_ns(py: Python, series: Series) -> Self {
-        assert!(series.dtype().is_integer());
-        Self {
+    pub fn timestamp_ns(py: Python, series: Series) -> PyResult<Self> {
+        Ok(Self {
             vec: series
-                .iter_i64(py)
-                .map(|x| {
-                    chrono::NaiveDateTime::from_timestamp(
-                        x / 1_000_000_000,
-                        (x % 1_000_000_000) as u32,
-                    )
-                })
-                .collect(),
-        }
+                .iter_integer(py)?
+                .map(|x| from_timestamp(x, 1_000_000_000))
+                .collect::<PyResult<_>>()?,
+        })
     }
     #[staticmethod]
     #[text_signature = "(series)"]
-    pub fn timestamp_us(py: Python, series: Series) -> Self {
-        assert!(series.dtype().is_integer());
-        Self {
+    pub fn timestamp_us(py: Python, series: Series) -> PyResult<Self> {
+        Ok(Self {
             vec: series
-                .iter_i64(py)
-                .map(|x| {
-                    chrono::NaiveDateTime::from_timestamp(x / 1_000_000, (x % 1_000_000) as u32)
-                })
-                .collect(),
-        }
+                .iter_integer(py)?
+                .map(|x| from_timestamp(x, 1_000_000))
+                .collect::<PyResult<_>>()?,
+        })
     }
     #[staticmethod]
     #[text_signature = "(series)"]
-    pub fn timestamp_ms(py: Python, series: Series) -> Self {
-        assert!(series.dtype().is_integer());
-        Self {
+    pub fn timestamp_ms(py: Python, series: Series) -> PyResult<Self> {
+        Ok(Self {
             vec: series
-                .iter_i64(py)
-                .map(|x| chrono::NaiveDateTime::from_timestamp(x / 1_000, (x % 1_000) as u32))
-                .collect(),
-        }
+                .iter_integer(py)?
+                .map(|x| from_timestamp(x, 1_000))
+                .collect::<PyResult<_>>()?,
+        })
     }
     #[staticmethod]
     #[text_signature = "(series)"]
-    pub fn timestamp_sec(py: Python, series: Series) -> Self {
-        assert!(series.dtype().is_integer());
-        Self {
+    pub fn timestamp_sec(py: Python, series: Series) -> PyResult<Self> {
+        Ok(Self {
             vec: series
-                .iter_i64(py)
-                .map(|x| chrono::NaiveDateTime::from_timestamp(x, 0))
-                .collect(),
-        }
+                .iter_integer(py)?
+                .map(|x| from_timestamp(x, 1))
+                .collect::<PyResult<_>>()?,
+        })
     }
     #[staticmethod]
     #[text_signature = "(series)"]
-    pub fn iso8601(py: Python, series: Series) -> Self {
-        assert!(series.dtype().is_string());
-        Self {
-            vec: series
-                .iter_str(py)
-                .map(|x| chrono::NaiveDateTime::from_str(x).unwrap())
-                .collect(),
-        }
+    pub fn iso8601(py: Python, series: Series) -> PyResult<Self> {
+        Ok(Self {
+            vec: parse_all(py, &series, "iso8601", chrono::NaiveDateTime::from_str)?,
+        })
     }
 
     /// The format-string syntax follows Rust's format::strftime fuction.
     #[staticmethod]
     #[text_signature = "(series, fmt)"]
-    pub fn parse(py: Python, series: Series, fmt: &str) -> Self {
-        assert!(series.dtype().is_string());
-        Self {
-            vec: series
-                .iter_str(py)
-                .map(|x| chrono::NaiveDateTime::parse_from_str(x, fmt).unwrap())
-                .collect(),
-        }
+    pub fn parse(py: Python, series: Series, fmt: &str) -> PyResult<Self> {
+        Ok(Self {
+            vec: parse_all(py, &series, fmt, |x| {
+                chrono::NaiveDateTime::parse_from_str(x, fmt)
+            })?,
+        })
     }
 }
 
@@ -121,47 +194,43 @@ pub struct Date {
 impl Date {
     #[staticmethod]
     #[text_signature = "(series)"]
-    pub fn timestamp_ns(py: Python, series: Series) -> Self {
-        assert!(series.dtype().is_integer());
-        Self {
+    pub fn timestamp_ns(py: Python, series: Series) -> PyResult<Self> {
+        Ok(Self {
             vec: series
-                .iter_i64(py)
-                .map(|x| chrono::NaiveDateTime::from_timestamp(x / 1_000_000_000, 0).date())
-                .collect(),
-        }
+                .iter_integer(py)?
+                .map(|x| Ok(from_timestamp(x, 1_000_000_000)?.date()))
+                .collect::<PyResult<_>>()?,
+        })
     }
     #[staticmethod]
     #[text_signature = "(series)"]
-    pub fn timestamp_us(py: Python, series: Series) -> Self {
-        assert!(series.dtype().is_integer());
-        Self {
+    pub fn timestamp_us(py: Python, series: Series) -> PyResult<Self> {
+        Ok(Self {
             vec: series
-                .iter_i64(py)
-                .map(|x| chrono::NaiveDateTime::from_timestamp(x / 1_000_000, 0).date())
-                .collect(),
-        }
+                .iter_integer(py)?
+                .map(|x| Ok(from_timestamp(x, 1_000_000)?.date()))
+                .collect::<PyResult<_>>()?,
+        })
     }
     #[staticmethod]
     #[text_signature = "(series)"]
-    pub fn timestamp_ms(py: Python, series: Series) -> Self {
-        assert!(series.dtype().is_integer());
-        Self {
+    pub fn timestamp_ms(py: Python, series: Series) -> PyResult<Self> {
+        Ok(Self {
             vec: series
-                .iter_i64(py)
-                .map(|x| chrono::NaiveDateTime::from_timestamp(x / 1_000, 0).date())
-                .collect(),
-        }
+                .iter_integer(py)?
+                .map(|x| Ok(from_timestamp(x, 1_000)?.date()))
+                .collect::<PyResult<_>>()?,
+        })
     }
     #[staticmethod]
     #[text_signature = "(series)"]
-    pub fn timestamp_sec(py: Python, series: Series) -> Self {
-        assert!(series.dtype().is_integer());
-        Self {
+    pub fn timestamp_sec(py: Python, series: Series) -> PyResult<Self> {
+        Ok(Self {
             vec: series
-                .iter_i64(py)
-                .map(|x| chrono::NaiveDateTime::from_timestamp(x, 0).date())
-                .collect(),
-        }
+                .iter_integer(py)?
+                .map(|x| Ok(from_timestamp(x, 1)?.date()))
+                .collect::<PyResult<_>>()?,
+        })
     }
     // #[staticmethod]
     // pub fn iso8601(series: Series) -> Self {
@@ -181,93 +250,73 @@ impl Date {
 
 #[pyclass]
 pub struct Time {
-    vec: Vec<chrono::NaiveTime>,
+    pub vec: Vec<chrono::NaiveTime>,
 }
 
 #[pymethods]
 impl Time {
     #[staticmethod]
     #[text_signature = "(series)"]
-    pub fn timestamp_ns(py: Python, series: Series) -> Self {
-        assert!(series.dtype().is_integer());
-        Self {
+    pub fn timestamp_ns(py: Python, series: Series) -> PyResult<Self> {
+        Ok(Self {
             vec: series
-                .iter_i64(py)
-                .map(|x| {
-                    chrono::NaiveDateTime::from_timestamp(
-                        x / 1_000_000_000,
-                        (x % 1_000_000_000) as u32,
-                    )
-                    .time()
-                })
-                .collect(),
-        }
+                .iter_integer(py)?
+                .map(|x| Ok(from_timestamp(x, 1_000_000_000)?.time()))
+                .collect::<PyResult<_>>()?,
+        })
     }
     #[staticmethod]
     #[text_signature = "(series)"]
-    pub fn timestamp_us(py: Python, series: Series) -> Self {
-        assert!(series.dtype().is_integer());
-        Self {
+    pub fn timestamp_us(py: Python, series: Series) -> PyResult<Self> {
+        Ok(Self {
             vec: series
-                .iter_i64(py)
-                .map(|x| {
-                    chrono::NaiveDateTime::from_timestamp(x / 1_000_000, (x % 1_000_000) as u32)
-                        .time()
-                })
-                .collect(),
-        }
+                .iter_integer(py)?
+                .map(|x| Ok(from_timestamp(x, 1_000_000)?.time()))
+                .collect::<PyResult<_>>()?,
+        })
     }
     #[staticmethod]
     #[text_signature = "(series)"]
-    pub fn timestamp_ms(py: Python, series: Series) -> Self {
-        assert!(series.dtype().is_integer());
-        Self {
+    pub fn timestamp_ms(py: Python, series: Series) -> PyResult<Self> {
+        Ok(Self {
             vec: series
-                .iter_i64(py)
-                .map(|x| {
-                    chrono::NaiveDateTime::from_timestamp(x / 1_000, (x % 1_000) as u32).time()
-                })
-                .collect(),
-        }
+                .iter_integer(py)?
+                .map(|x| Ok(from_timestamp(x, 1_000)?.time()))
+                .collect::<PyResult<_>>()?,
+        })
     }
     #[staticmethod]
     #[text_signature = "(series)"]
-    pub fn timestamp_sec(py: Python, series: Series) -> Self {
-        assert!(series.dtype().is_integer());
-        Self {
+    pub fn timestamp_sec(py: Python, series: Series) -> PyResult<Self> {
+        Ok(Self {
             vec: series
-                .iter_i64(py)
-                .map(|x| chrono::NaiveDateTime::from_timestamp(x, 0).time())
-                .collect(),
-        }
+                .iter_integer(py)?
+                .map(|x| Ok(from_timestamp(x, 1)?.time()))
+                .collect::<PyResult<_>>()?,
+        })
     }
+    /// Accepts a time ("12:30:00") or a datetime, whose date is ignored.
     #[staticmethod]
     #[text_signature = "(series)"]
-    pub fn iso8601(py: Python, series: Series) -> Self {
-        assert!(series.dtype().is_string());
-        Self {
-            vec: series
-                .iter_str(py)
-                .map(|x| chrono::NaiveDateTime::from_str(x).unwrap().time())
-                .collect(),
-        }
+    pub fn iso8601(py: Python, series: Series) -> PyResult<Self> {
+        Ok(Self {
+            vec: parse_all(py, &series, "iso8601", |x| {
+                chrono::NaiveTime::from_str(x)
+                    .or_else(|_| chrono::NaiveDateTime::from_str(x).map(|t| t.time()))
+            })?,
+        })
     }
 
     /// The format-string syntax follows Rust's format::strftime fuction.
+    /// Date fields in the format are parsed but ignored.
     #[staticmethod]
     #[text_signature = "(series, fmt)"]
-    pub fn parse(py: Python, series: Series, fmt: &str) -> Self {
-        assert!(series.dtype().is_string());
-        Self {
-            vec: series
-                .iter_str(py)
-                .map(|x| {
-                    chrono::NaiveDateTime::parse_from_str(x, fmt)
-                        .unwrap()
-                        .time()
-                })
-                .collect(),
-        }
+    pub fn parse(py: Python, series: Series, fmt: &str) -> PyResult<Self> {
+        Ok(Self {
+            vec: parse_all(py, &series, fmt, |x| {
+                chrono::NaiveTime::parse_from_str(x, fmt)
+            })?,
+        })
     }
 }
 
@@ -286,61 +335,65 @@ pub struct Duration {
 impl Duration {
     #[staticmethod]
     #[text_signature = "(series)"]
-    pub fn ns(py: Python, series: Series) -> Self {
-        assert!(series.dtype().is_integer());
-        Self {
+    pub fn ns(py: Python, series: Series) -> PyResult<Self> {
+        Ok(Self {
             vec: series
-                .iter_i64(py)
-                .map(|x| chrono::Duration::nanoseconds(x))
+                .iter_integer(py)?
+                .map(chrono::Duration::nanoseconds)
                 .collect(),
-        }
+        })
     }
     #[staticmethod]
     #[text_signature = "(series)"]
-    pub fn us(py: Python, series: Series) -> Self {
-        assert!(series.dtype().is_integer());
-        Self {
+    pub fn us(py: Python, series: Series) -> PyResult<Self> {
+        Ok(Self {
             vec: series
-                .iter_i64(py)
-                .map(|x| chrono::Duration::microseconds(x))
+                .iter_integer(py)?
+                .map(chrono::Duration::microseconds)
                 .collect(),
-        }
+        })
     }
     #[staticmethod]
     #[text_signature = "(series)"]
-    pub fn ms(py: Python, series: Series) -> Self {
-        assert!(series.dtype().is_integer());
-        Self {
+    pub fn ms(py: Python, series: Series) -> PyResult<Self> {
+        Ok(Self {
             vec: series
-                .iter_i64(py)
-                .map(|x| chrono::Duration::milliseconds(x))
+                .iter_integer(py)?
+                .map(chrono::Duration::milliseconds)
                 .collect(),
-        }
+        })
     }
     #[staticmethod]
     #[text_signature = "(series)"]
-    pub fn sec(py: Python, series: Series) -> Self {
-        assert!(series.dtype().is_integer());
-        Self {
+    pub fn sec(py: Python, series: Series) -> PyResult<Self> {
+        Ok(Self {
             vec: series
-                .iter_i64(py)
-                .map(|x| chrono::Duration::seconds(x))
+                .iter_integer(py)?
+                .map(chrono::Duration::seconds)
                 .collect(),
-        }
+        })
     }
+    /// Parses ISO 8601 durations like "P1DT2H30M" or "PT0.5S".
+    /// Weeks, days, hours, minutes and seconds are accepted; years and months are not.
     #[staticmethod]
     #[text_signature = "(series)"]
-    pub fn iso8601(py: Python, series: Series) -> Self {
-        assert!(series.dtype().is_string());
-        todo!();
+    pub fn iso8601(py: Python, series: Series) -> PyResult<Self> {
+        Ok(Self {
+            vec: parse_all(py, &series, "iso8601", parse_iso8601_duration)?,
+        })
     }
 
     /// The format-string syntax follows Rust's format::strftime fuction.
+    /// The duration is read as a time of day, so it must be shorter than a day.
     #[staticmethod]
     #[text_signature = "(series, fmt)"]
-    pub fn parse(py: Python, series: Series, fmt: &str) -> Self {
-        assert!(series.dtype().is_string());
-        todo!();
+    pub fn parse(py: Python, series: Series, fmt: &str) -> PyResult<Self> {
+        Ok(Self {
+            vec: parse_all(py, &series, fmt, |x| {
+                chrono::NaiveTime::parse_from_str(x, fmt)
+                    .map(|t| t - chrono::NaiveTime::from_hms(0, 0, 0))
+            })?,
+        })
     }
 }
 
@@ -349,3 +402,52 @@ impl Duration {
         self.vec.len()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::parse_iso8601_duration as parse;
+    use chrono::Duration;
+
+    #[test]
+    fn components() {
+        assert_eq!(parse("P1W"), Ok(Duration::weeks(1)));
+        assert_eq!(parse("P2D"), Ok(Duration::days(2)));
+        assert_eq!(
+            parse("P1DT2H30M"),
+            Ok(Duration::days(1) + Duration::hours(2) + Duration::minutes(30))
+        );
+        assert_eq!(parse("PT45S"), Ok(Duration::seconds(45)));
+        assert_eq!(parse("PT0S"), Ok(Duration::zero()));
+    }
+
+    #[test]
+    fn fractions() {
+        assert_eq!(parse("PT0.5S"), Ok(Duration::milliseconds(500)));
+        assert_eq!(parse("PT0,25S"), Ok(Duration::milliseconds(250)));
+        assert_eq!(parse("P0.5D"), Ok(Duration::hours(12)));
+        assert_eq!(parse("PT1.5M"), Ok(Duration::seconds(90)));
+    }
+
+    #[test]
+    fn minutes_and_months() {
+        // M is months before the T and minutes after it
+        assert_eq!(parse("PT1M"), Ok(Duration::minutes(1)));
+        assert!(parse("P1M").is_err());
+        assert!(parse("P1Y").is_err());
+    }
+
+    #[test]
+    fn invalid() {
+        for s in &[
+            "", "1D", "P", "PT", "P1", "PT1", "PD", "P1H", "PT1D", "P1DT", "P1X", "P1TT1H",
+        ] {
+            assert!(parse(s).is_err(), "{:?} should be invalid", s);
+        }
+    }
+
+    #[test]
+    fn too_long() {
+        assert!(parse("P100000000000D").is_err());
+        assert!(parse("P100000D").is_ok());
+    }
+}
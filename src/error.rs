@@ -0,0 +1,95 @@
+//! Errors raised to Python.
+//!
+//! Every error is a subclass of `ezel.EzelError`.
+//!
+//! - `ezel.DtypeMismatchError`: a series can't be read as the expected dtype.
+//! - `ezel.LengthMismatchError`: series drawn together have different lengths.
+//! - `ezel.ParseError`: a string can't be parsed into a chrono type.
+//! - `ezel.OutOfRangeError`: a value is outside of the range of its chrono type.
+//! - `ezel.RenderError`: plotters failed to draw.
+//! - `ezel.IoError`: an image can't be encoded or saved.
+//!
+//! Invalid arguments (unknown names, out-of-range numbers, ..) raise Python's ValueError.
+
+use pyo3::create_exception;
+use pyo3::prelude::*;
+use snafu::Snafu;
+
+create_exception!(ezel, EzelError, pyo3::exceptions::PyException);
+create_exception!(ezel, DtypeMismatchError, EzelError);
+create_exception!(ezel, LengthMismatchError, EzelError);
+create_exception!(ezel, ParseError, EzelError);
+create_exception!(ezel, OutOfRangeError, EzelError);
+create_exception!(ezel, RenderError, EzelError);
+create_exception!(ezel, IoError, EzelError);
+
+#[derive(Debug, Snafu)]
+#[snafu(visibility = "pub(crate)")]
+pub enum Error {
+    #[snafu(display("expected {}, got {}", expected, actual))]
+    DtypeMismatch { expected: String, actual: String },
+
+    #[snafu(display("{} should have {} values, got {}", what, expected, actual))]
+    LengthMismatch {
+        what: String,
+        expected: usize,
+        actual: usize,
+    },
+
+    #[snafu(display("failed to parse {:?} as {}: {}", input, format, reason))]
+    Parse {
+        input: String,
+        format: String,
+        reason: String,
+    },
+
+    #[snafu(display("{} is out of the supported range", what))]
+    OutOfRange { what: String },
+
+    #[snafu(display("failed to draw: {}", message))]
+    Render { message: String },
+
+    #[snafu(display("failed to write {}: {}", path, source))]
+    Io {
+        path: String,
+        source: std::io::Error,
+    },
+
+    #[snafu(display("failed to encode the image: {}", source))]
+    Image { source: image::ImageError },
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Wraps a plotters error.
+pub fn render<E: std::fmt::Display>(e: E) -> Error {
+    Error::Render {
+        message: e.to_string(),
+    }
+}
+
+impl From<Error> for PyErr {
+    fn from(e: Error) -> PyErr {
+        let message = e.to_string();
+        match e {
+            Error::DtypeMismatch { .. } => DtypeMismatchError::new_err(message),
+            Error::LengthMismatch { .. } => LengthMismatchError::new_err(message),
+            Error::Parse { .. } => ParseError::new_err(message),
+            Error::OutOfRange { .. } => OutOfRangeError::new_err(message),
+            Error::Render { .. } => RenderError::new_err(message),
+            Error::Io { .. } | Error::Image { .. } => IoError::new_err(message),
+        }
+    }
+}
+
+/// Registers the exception classes to the module.
+pub fn add_exceptions(py: Python, m: &PyModule) -> PyResult<()> {
+    m.add("EzelError", py.get_type::<EzelError>())?;
+    m.add("DtypeMismatchError", py.get_type::<DtypeMismatchError>())?;
+    m.add("LengthMismatchError", py.get_type::<LengthMismatchError>())?;
+    m.add("ParseError", py.get_type::<ParseError>())?;
+    m.add("OutOfRangeError", py.get_type::<OutOfRangeError>())?;
+    m.add("RenderError", py.get_type::<RenderError>())?;
+    m.add("IoError", py.get_type::<IoError>())?;
+    Ok(())
+}
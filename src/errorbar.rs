@@ -0,0 +1,215 @@
+//! Error bars. `Chart.errorbar` draws a bar from `v - lower` to `v + upper` at each point,
+//! vertically for yerr and horizontally for xerr.
+//!
+//! An error is a number for every point, a series of symmetric errors,
+//! or a pair of series (lower, upper), e.g. a 2xN numpy array.
+//! Errors are in axis units, and days on chrono axes.
+
+use crate::bar;
+use crate::error::{self, Error};
+use crate::range::{self, RangeEnum};
+use crate::{Dtype, Series};
+use chrono::{Duration, NaiveDate, NaiveDateTime, NaiveTime};
+use pyo3::prelude::*;
+
+pub enum ErrArg {
+    Scalar(f64),
+    Symmetric(Series),
+    Asymmetric(Series, Series),
+}
+
+impl<'source> FromPyObject<'source> for ErrArg {
+    fn extract(x: &'source PyAny) -> PyResult<Self> {
+        if let Ok(v) = x.extract::<f64>() {
+            return Ok(ErrArg::Scalar(v));
+        }
+        if let Ok(s) = x.extract::<Series>() {
+            return Ok(ErrArg::Symmetric(s));
+        }
+        let rows = x
+            .iter()?
+            .map(|row| row?.extract::<Series>())
+            .collect::<PyResult<Vec<Series>>>();
+        match rows {
+            Ok(mut rows) if rows.len() == 2 => {
+                let upper = rows.pop().unwrap();
+                let lower = rows.pop().unwrap();
+                Ok(ErrArg::Asymmetric(lower, upper))
+            }
+            _ => Err(pyo3::exceptions::PyValueError::new_err(
+                "an error should be a number, a series, or a pair of series (lower, upper)",
+            )),
+        }
+    }
+}
+
+impl ErrArg {
+    /// The (lower, upper) errors of `n` points. `what` names the argument in errors.
+    pub fn bounds(&self, py: Python, n: usize, what: &str) -> PyResult<Vec<(f64, f64)>> {
+        let check = |s: &Series| {
+            if s.len(py) != n {
+                return Err(Error::LengthMismatch {
+                    what: what.to_string(),
+                    expected: n,
+                    actual: s.len(py),
+                });
+            }
+            Ok(())
+        };
+        let bounds: Vec<(f64, f64)> = match self {
+            ErrArg::Scalar(v) => vec![(*v, *v); n],
+            ErrArg::Symmetric(s) => {
+                check(s)?;
+                s.iter_f64(py)?.map(|v| (v, v)).collect()
+            }
+            ErrArg::Asymmetric(lower, upper) => {
+                check(lower)?;
+                check(upper)?;
+                lower.iter_f64(py)?.zip(upper.iter_f64(py)?).collect()
+            }
+        };
+        if bounds.iter().any(|&(lo, hi)| lo < 0.0 || hi < 0.0) {
+            return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                "{} should not be negative",
+                what
+            )));
+        }
+        Ok(bounds)
+    }
+}
+
+/// Points with errors. `xerr` and `yerr` have a (lower, upper) pair for every point.
+pub struct ErrorBarSeries {
+    pub x: Series,
+    pub y: Series,
+    pub xerr: Option<Vec<(f64, f64)>>,
+    pub yerr: Option<Vec<(f64, f64)>>,
+}
+
+/// A value of an axis with error bars.
+pub trait ErrorValue: Sized {
+    /// The value moved by `d` axis units. Fails past the end of a chrono type.
+    fn offset(&self, d: f64) -> error::Result<Self>;
+    /// NaN is left out of the range.
+    fn is_nan(&self) -> bool {
+        false
+    }
+}
+
+impl ErrorValue for f64 {
+    fn offset(&self, d: f64) -> error::Result<Self> {
+        Ok(self + d)
+    }
+    fn is_nan(&self) -> bool {
+        f64::is_nan(*self)
+    }
+}
+
+impl ErrorValue for f32 {
+    fn offset(&self, d: f64) -> error::Result<Self> {
+        Ok(self + d as f32)
+    }
+    fn is_nan(&self) -> bool {
+        f32::is_nan(*self)
+    }
+}
+
+impl ErrorValue for i64 {
+    fn offset(&self, d: f64) -> error::Result<Self> {
+        Ok((*self as f64 + d).round() as i64)
+    }
+}
+
+impl ErrorValue for i32 {
+    fn offset(&self, d: f64) -> error::Result<Self> {
+        Ok((*self as f64 + d).round() as i32)
+    }
+}
+
+fn out_of_range(v: impl std::fmt::Display, d: f64) -> Error {
+    Error::OutOfRange {
+        what: format!("{} moved by {} days", v, d),
+    }
+}
+
+impl ErrorValue for NaiveDateTime {
+    fn offset(&self, d: f64) -> error::Result<Self> {
+        self.checked_add_signed(bar::days(d)?)
+            .ok_or_else(|| out_of_range(self, d))
+    }
+}
+
+impl ErrorValue for NaiveDate {
+    fn offset(&self, d: f64) -> error::Result<Self> {
+        self.checked_add_signed(bar::whole_days(d.round() as i64)?)
+            .ok_or_else(|| out_of_range(self, d))
+    }
+}
+
+/// A time axis can't cross midnight, so an offset past it fails.
+impl ErrorValue for NaiveTime {
+    fn offset(&self, d: f64) -> error::Result<Self> {
+        match self.overflowing_add_signed(bar::days(d)?) {
+            (t, 0) => Ok(t),
+            _ => Err(out_of_range(self, d)),
+        }
+    }
+}
+
+impl ErrorValue for Duration {
+    fn offset(&self, d: f64) -> error::Result<Self> {
+        self.checked_add(&bar::days(d)?)
+            .ok_or_else(|| out_of_range(self, d))
+    }
+}
+
+/// The (min, max) of the values spread by their errors.
+fn spread<T: ErrorValue + PartialOrd + Copy>(
+    values: impl Iterator<Item = T>,
+    bounds: &[(f64, f64)],
+) -> error::Result<Option<(T, T)>> {
+    let mut ends = vec![];
+    for (v, &(lo, hi)) in values.zip(bounds).filter(|(v, _)| !v.is_nan()) {
+        ends.push(v.offset(-lo)?);
+        ends.push(v.offset(hi)?);
+    }
+    Ok(range::min_max(ends.into_iter().filter(|v| !v.is_nan())))
+}
+
+/// The range of an axis covered by the values and their error bars.
+pub fn extent(
+    py: Python,
+    values: &Series,
+    bounds: &Option<Vec<(f64, f64)>>,
+    dtype: Dtype,
+) -> PyResult<Option<RangeEnum>> {
+    let bounds = match bounds {
+        Some(bounds) => bounds,
+        None => return range::series_extent(py, values, dtype),
+    };
+    Ok(match dtype {
+        Dtype::F64 => spread(values.iter_f64(py)?, bounds)?.map(|(a, b)| RangeEnum::F64(a, b)),
+        Dtype::F32 => spread(values.iter_f32(py)?, bounds)?.map(|(a, b)| RangeEnum::F32(a, b)),
+        Dtype::I64 => spread(values.iter_i64(py)?, bounds)?.map(|(a, b)| RangeEnum::I64(a, b)),
+        Dtype::I32 => spread(values.iter_i32(py)?, bounds)?.map(|(a, b)| RangeEnum::I32(a, b)),
+        Dtype::NaiveDateTime => {
+            spread(values.iter_datetime(py)?, bounds)?.map(|(a, b)| RangeEnum::DateTime(a, b))
+        }
+        Dtype::NaiveDate => {
+            spread(values.iter_date(py)?, bounds)?.map(|(a, b)| RangeEnum::Date(a, b))
+        }
+        Dtype::NaiveTime => {
+            spread(values.iter_time(py)?, bounds)?.map(|(a, b)| RangeEnum::Time(a, b))
+        }
+        Dtype::Duration => {
+            spread(values.iter_duration(py)?, bounds)?.map(|(a, b)| RangeEnum::Duration(a, b))
+        }
+        Dtype::String => {
+            return Err(Error::DtypeMismatch {
+                expected: "a numeric or chrono axis for error bars".to_string(),
+                actual: "a categorical axis".to_string(),
+            }
+            .into())
+        }
+    })
+}
@@ -0,0 +1,96 @@
+//! Filled regions between two curves. `Chart.area` fills between y and a baseline,
+//! and `Chart.fill_between` between y1 and y2.
+//!
+//! A region is drawn as a polygon along y1 and back along y2.
+//! `where` masks out points, so that each run of selected points is a separate polygon.
+
+use crate::bar::Baseline;
+use crate::error::Error;
+use crate::range::{self, RangeEnum};
+use crate::{Dtype, Series};
+use pyo3::prelude::*;
+
+/// Selects the points to fill. Accepts a list or a numpy array of bools.
+pub struct Mask(pub Vec<bool>);
+
+impl<'source> FromPyObject<'source> for Mask {
+    fn extract(x: &'source PyAny) -> PyResult<Self> {
+        Ok(Mask(
+            x.iter()?
+                .map(|v| v?.is_true())
+                .collect::<PyResult<Vec<bool>>>()?,
+        ))
+    }
+}
+
+/// A region to fill. `x`, `y1`, `y2` and `mask` have the same length.
+pub struct FillSeries {
+    pub x: Series,
+    pub y1: Series,
+    pub y2: Baseline,
+    pub mask: Option<Mask>,
+    /// y1 is also drawn as a line.
+    pub outline: bool,
+}
+
+impl FillSeries {
+    /// The index ranges of the polygons to draw.
+    pub fn runs(&self, len: usize) -> Vec<std::ops::Range<usize>> {
+        let selected = |i: usize| self.mask.as_ref().map_or(true, |mask| mask.0[i]);
+        let mut runs = vec![];
+        let mut start = None;
+        for i in 0..len {
+            match (selected(i), start) {
+                (true, None) => start = Some(i),
+                (false, Some(s)) => {
+                    runs.push(s..i);
+                    start = None;
+                }
+                _ => {}
+            }
+        }
+        if let Some(s) = start {
+            runs.push(s..len);
+        }
+        runs
+    }
+
+    /// The y range covered by the region.
+    pub fn y_extent(&self, py: Python, dtype: Dtype) -> PyResult<Option<RangeEnum>> {
+        extent_between(py, &self.y1, &self.y2, dtype)
+    }
+}
+
+/// The range covered by y1 and y2.
+pub fn extent_between(
+    py: Python,
+    y1: &Series,
+    y2: &Baseline,
+    dtype: Dtype,
+) -> PyResult<Option<RangeEnum>> {
+    let y1 = range::series_extent(py, y1, dtype)?;
+    let y2 = match y2 {
+        Baseline::Scalar(v) => Some(scalar_extent(*v, dtype)?),
+        Baseline::Series(s) => range::series_extent(py, s, dtype)?,
+    };
+    Ok(match (y1, y2) {
+        (Some(a), Some(b)) => Some(a.union(b)),
+        (a, b) => a.or(b),
+    })
+}
+
+fn scalar_extent(v: f64, dtype: Dtype) -> PyResult<RangeEnum> {
+    Ok(match dtype {
+        Dtype::F64 => RangeEnum::F64(v, v),
+        Dtype::F32 => RangeEnum::F32(v as f32, v as f32),
+        Dtype::I64 => RangeEnum::I64(v.floor() as i64, v.ceil() as i64),
+        Dtype::I32 => RangeEnum::I32(v.floor() as i32, v.ceil() as i32),
+        _ => {
+            return Err(Error::DtypeMismatch {
+                expected: "a numeric y-axis for a scalar baseline".to_string(),
+                actual: format!("a {:?} y-axis", dtype),
+            }
+            .into())
+        }
+    })
+}
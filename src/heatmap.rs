@@ -0,0 +1,273 @@
+//! Heatmaps and images. `Chart.heatmap` colors the cells of a 2D array through a colormap,
+//! and `Chart.imshow` draws the pixels of an RGB(A) image.
+//!
+//! Cell j of a row spans x_edges[j]..x_edges[j + 1]. Edges can be numeric or chrono.
+//! Edges of the length of the row are read as cell centers instead, with cells 1 unit wide,
+//! which places the cells on categories, or at 0, 1, .. by default. On a date axis, the cell
+//! of a date is the whole day.
+
+use crate::color::CmapArg;
+use crate::error;
+use crate::errorbar::{self, ErrorValue};
+use crate::hist;
+use crate::range::{self, RangeEnum};
+use crate::{Dtype, Series};
+use chrono::{Duration, NaiveDate, NaiveDateTime, NaiveTime};
+use plotters::prelude::*;
+use plotters::style::RGBAColor;
+use pyo3::prelude::*;
+
+/// A 2D array of values in row-major order.
+/// Accepts a 2D numpy.ndarray of float or int, or a list of lists of numbers.
+pub struct Grid {
+    pub rows: usize,
+    pub cols: usize,
+    pub values: Vec<f64>,
+}
+
+impl<'source> FromPyObject<'source> for Grid {
+    fn extract(x: &'source PyAny) -> PyResult<Self> {
+        macro_rules! numpy {
+            ($($t:ty),*) => {$(
+                if let Ok(arr) = x.extract::<&numpy::PyArray2<$t>>() {
+                    let arr = arr.readonly();
+                    let view = arr.as_array();
+                    return Ok(Grid {
+                        rows: view.nrows(),
+                        cols: view.ncols(),
+                        values: view.iter().map(|v| *v as f64).collect(),
+                    });
+                }
+            )*};
+        }
+        numpy!(f64, f32, i64, i32);
+        if let Ok(rows) = x.extract::<Vec<Vec<f64>>>() {
+            let cols = rows.first().map_or(0, |row| row.len());
+            if rows.iter().any(|row| row.len() != cols) {
+                return Err(pyo3::exceptions::PyValueError::new_err(
+                    "every row should have the same length",
+                ));
+            }
+            return Ok(Grid {
+                rows: rows.len(),
+                cols,
+                values: rows.into_iter().flatten().collect(),
+            });
+        }
+        Err(pyo3::exceptions::PyValueError::new_err(
+            "expected a 2D numpy.ndarray or a list of lists of numbers",
+        ))
+    }
+}
+
+/// The pixels of an image in row-major order, from the top row.
+/// Accepts a numpy.ndarray of shape (height, width, 3) or (height, width, 4),
+/// either uint8 or float in [0, 1].
+pub struct Image {
+    pub rows: usize,
+    pub cols: usize,
+    pub pixels: Vec<RGBAColor>,
+}
+
+impl<'source> FromPyObject<'source> for Image {
+    fn extract(x: &'source PyAny) -> PyResult<Self> {
+        macro_rules! numpy {
+            ($($t:ty => $unit:expr),*) => {$(
+                if let Ok(arr) = x.extract::<&numpy::PyArray3<$t>>() {
+                    let arr = arr.readonly();
+                    let view = arr.as_array();
+                    let (rows, cols, channels) = view.dim();
+                    if channels != 3 && channels != 4 {
+                        return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                            "an image should have 3 or 4 channels, got {}",
+                            channels
+                        )));
+                    }
+                    let unit = |i: usize, j: usize, k: usize| {
+                        (view[[i, j, k]] as f64 / $unit).max(0.0).min(1.0)
+                    };
+                    let pixel = |(i, j): (usize, usize)| {
+                        let c = |k: usize| (unit(i, j, k) * 255.0).round() as u8;
+                        let a = if channels == 4 { unit(i, j, 3) } else { 1.0 };
+                        RGBColor(c(0), c(1), c(2)).mix(a)
+                    };
+                    let pixels = (0..rows)
+                        .flat_map(|i| (0..cols).map(move |j| (i, j)))
+                        .map(pixel)
+                        .collect();
+                    return Ok(Image { rows, cols, pixels });
+                }
+            )*};
+        }
+        numpy!(u8 => 255.0, f64 => 1.0, f32 => 1.0);
+        Err(pyo3::exceptions::PyValueError::new_err(
+            "expected a numpy.ndarray of shape (height, width, 3) or (height, width, 4) of uint8 or float",
+        ))
+    }
+}
+
+/// Colored cells. `colors` has `rows` x `cols` colors in row-major order from the bottom row,
+/// and None for cells that are not drawn.
+pub struct CellSeries {
+    pub x: Series,
+    pub y: Series,
+    pub rows: usize,
+    pub cols: usize,
+    pub colors: Vec<Option<RGBAColor>>,
+}
+
+impl CellSeries {
+    /// Colors the values through `cmap`. NaN cells are left out.
+    /// vmin and vmax default to the min and max of the values.
+    pub fn heatmap(
+        x: Series,
+        y: Series,
+        z: &Grid,
+        cmap: CmapArg,
+        vmin: Option<f64>,
+        vmax: Option<f64>,
+    ) -> Self {
+        CellSeries {
+            x,
+            y,
+            rows: z.rows,
+            cols: z.cols,
+            colors: cmap.map(&z.values, vmin, vmax),
+        }
+    }
+
+    /// The image with the top row at the top, i.e. the last row of the cells.
+    pub fn image(x: Series, y: Series, image: Image) -> Self {
+        let colors = image
+            .pixels
+            .chunks(image.cols.max(1))
+            .rev()
+            .flatten()
+            .cloned()
+            .map(Some)
+            .collect();
+        CellSeries {
+            x,
+            y,
+            rows: image.rows,
+            cols: image.cols,
+            colors,
+        }
+    }
+}
+
+/// A value of an axis with cells. The cell of a center is 1 unit wide.
+pub trait CellValue: ErrorValue + Clone {
+    /// The (start, end) of the cell centered at the value.
+    fn cell(&self) -> error::Result<(Self, Self)> {
+        Ok((self.offset(-0.5)?, self.offset(0.5)?))
+    }
+}
+
+impl CellValue for f64 {}
+impl CellValue for f32 {}
+impl CellValue for i64 {}
+impl CellValue for i32 {}
+impl CellValue for NaiveDateTime {}
+impl CellValue for Duration {}
+
+/// A date has no half days, so its cell is the whole day.
+impl CellValue for NaiveDate {
+    fn cell(&self) -> error::Result<(Self, Self)> {
+        Ok((*self, self.offset(1.0)?))
+    }
+}
+
+/// A time axis can't cross midnight, so the cell is clamped to the day.
+impl CellValue for NaiveTime {
+    fn cell(&self) -> error::Result<(Self, Self)> {
+        let ns = hist::time_to_ns(*self);
+        let half = 43_200 * 1_000_000_000;
+        Ok((hist::ns_to_time(ns - half), hist::ns_to_time(ns + half)))
+    }
+}
+
+/// The (start, end) of each cell from its edges, or its centers.
+pub fn spans<T: CellValue>(values: Vec<T>, cells: usize) -> error::Result<Vec<(T, T)>> {
+    if values.len() == cells + 1 {
+        Ok(values
+            .windows(2)
+            .map(|w| (w[0].clone(), w[1].clone()))
+            .collect())
+    } else {
+        values.iter().map(CellValue::cell).collect()
+    }
+}
+
+/// The range of an axis covered by the cells.
+pub fn extent(
+    py: Python,
+    edges: &Series,
+    cells: usize,
+    dtype: Dtype,
+) -> PyResult<Option<RangeEnum>> {
+    if edges.len(py) == cells + 1 || dtype == Dtype::String {
+        range::series_extent(py, edges, dtype)
+    } else if dtype == Dtype::NaiveTime {
+        // the cells are clamped to the day
+        let spans = spans(edges.iter_time(py)?.collect(), cells)?;
+        let start = spans.iter().map(|s| s.0).min();
+        let end = spans.iter().map(|s| s.1).max();
+        Ok(start.zip(end).map(|(a, b)| RangeEnum::Time(a, b)))
+    } else {
+        // as far below and above the centers as `CellValue::cell`
+        let bounds = match dtype {
+            Dtype::NaiveDate => (0.0, 1.0),
+            _ => (0.5, 0.5),
+        };
+        errorbar::extent(py, edges, &Some(vec![bounds; cells]), dtype)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cells_from_edges() {
+        assert_eq!(
+            spans(vec![0.0, 1.0, 3.0], 2).unwrap(),
+            vec![(0.0, 1.0), (1.0, 3.0)]
+        );
+    }
+
+    #[test]
+    fn cells_from_centers() {
+        assert_eq!(
+            spans(vec![0.0, 1.0], 2).unwrap(),
+            vec![(-0.5, 0.5), (0.5, 1.5)]
+        );
+        let t = NaiveDate::from_ymd(2021, 3, 1).and_hms(12, 0, 0);
+        assert_eq!(
+            spans(vec![t], 1).unwrap(),
+            vec![(t - Duration::hours(12), t + Duration::hours(12))]
+        );
+    }
+
+    #[test]
+    fn date_cells_are_whole_days() {
+        let d = NaiveDate::from_ymd(2021, 3, 1);
+        assert_eq!(
+            spans(vec![d, d.succ()], 2).unwrap(),
+            vec![(d, d.succ()), (d.succ(), d.succ().succ())]
+        );
+        assert!(spans(vec![chrono::naive::MAX_DATE], 1).is_err());
+    }
+
+    #[test]
+    fn time_cells_are_clamped_to_the_day() {
+        let t = NaiveTime::from_hms(18, 0, 0);
+        assert_eq!(
+            spans(vec![t], 1).unwrap(),
+            vec![(
+                NaiveTime::from_hms(6, 0, 0),
+                NaiveTime::from_hms_nano(23, 59, 59, 999_999_999)
+            )]
+        );
+    }
+}
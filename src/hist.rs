@@ -0,0 +1,848 @@
+//! Binning for `Chart.hist`.
+//! Data is binned in a single pass over the series without sorting or collecting it,
+//! so it stays fast for 10M points.
+//!
+//! Numeric data is split into equal-width bins.
+//! Integers and dates are split into bins of a whole number of units, so that every value
+//! falls in exactly one bin.
+//! Chrono data can also be binned by a calendar unit ("month", "day", ..).
+
+use crate::error::{Error, Result};
+use crate::range::RangeEnum;
+use crate::Dtype;
+use crate::Series;
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+use pyo3::prelude::*;
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+
+/// `bins=` argument of `Chart.hist`.
+pub enum Bins {
+    /// the number of equal-width bins
+    Count(usize),
+    /// one bin per calendar unit, only for chrono axes
+    Unit(CalendarUnit),
+}
+
+impl<'source> FromPyObject<'source> for Bins {
+    fn extract(x: &'source PyAny) -> PyResult<Self> {
+        if let Ok(n) = x.extract::<usize>() {
+            if n == 0 {
+                return Err(pyo3::exceptions::PyValueError::new_err(
+                    "bins should be positive",
+                ));
+            }
+            if n as i128 > MAX_BINS as i128 {
+                return Err(too_many_bins(n as i128));
+            }
+            return Ok(Bins::Count(n));
+        }
+        if let Ok(s) = x.extract::<&str>() {
+            return CalendarUnit::parse(s).map(Bins::Unit).ok_or_else(|| {
+                pyo3::exceptions::PyValueError::new_err(format!(
+                    "unknown calendar unit {:?}, expected one of year, month, week, day, hour, minute, second",
+                    s
+                ))
+            });
+        }
+        Err(pyo3::exceptions::PyValueError::new_err(
+            "bins should be an int or a calendar unit",
+        ))
+    }
+}
+
+/// The most bins of a histogram. Every bin between the first and last value is kept,
+/// so a short unit over a long span would otherwise exhaust the memory.
+pub const MAX_BINS: i64 = 1_000_000;
+
+fn too_many_bins(bins: i128) -> PyErr {
+    pyo3::exceptions::PyValueError::new_err(format!(
+        "{} bins are more than the limit of {}, use a longer unit or fewer bins",
+        bins, MAX_BINS
+    ))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CalendarUnit {
+    Year,
+    Month,
+    Week,
+    Day,
+    Hour,
+    Minute,
+    Second,
+}
+
+impl CalendarUnit {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "year" | "Y" => Some(CalendarUnit::Year),
+            "month" | "M" => Some(CalendarUnit::Month),
+            "week" | "W" => Some(CalendarUnit::Week),
+            "day" | "D" => Some(CalendarUnit::Day),
+            "hour" | "h" => Some(CalendarUnit::Hour),
+            "minute" | "m" => Some(CalendarUnit::Minute),
+            "second" | "s" => Some(CalendarUnit::Second),
+            _ => None,
+        }
+    }
+
+    /// The length of the unit. Year and month have no fixed length.
+    pub fn fixed_duration(&self) -> Option<Duration> {
+        match self {
+            CalendarUnit::Year | CalendarUnit::Month => None,
+            CalendarUnit::Week => Some(Duration::weeks(1)),
+            CalendarUnit::Day => Some(Duration::days(1)),
+            CalendarUnit::Hour => Some(Duration::hours(1)),
+            CalendarUnit::Minute => Some(Duration::minutes(1)),
+            CalendarUnit::Second => Some(Duration::seconds(1)),
+        }
+    }
+
+    /// Whether the unit is at least a day long, i.e. usable on a date axis.
+    pub fn is_date_unit(&self) -> bool {
+        match self {
+            CalendarUnit::Year | CalendarUnit::Month | CalendarUnit::Week | CalendarUnit::Day => {
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// The start of the unit containing `t`. Weeks start on Monday.
+    pub fn floor(&self, t: NaiveDateTime) -> Result<NaiveDateTime> {
+        let d = t.date();
+        Ok(match self {
+            CalendarUnit::Hour => d.and_hms(t.hour(), 0, 0),
+            CalendarUnit::Minute => d.and_hms(t.hour(), t.minute(), 0),
+            CalendarUnit::Second => d.and_hms(t.hour(), t.minute(), t.second()),
+            _ => self.floor_date(d)?.and_hms(0, 0, 0),
+        })
+    }
+
+    /// The start of the next unit. `t` should be the start of a unit.
+    pub fn next(&self, t: NaiveDateTime) -> Result<NaiveDateTime> {
+        match self {
+            CalendarUnit::Year | CalendarUnit::Month => {
+                Ok(self.next_date(t.date())?.and_hms(0, 0, 0))
+            }
+            _ => t
+                .checked_add_signed(self.fixed_duration().unwrap())
+                .ok_or_else(|| self.out_of_range(t)),
+        }
+    }
+
+    /// The first day of the unit containing `d`. Units shorter than a day start on `d`.
+    pub fn floor_date(&self, d: NaiveDate) -> Result<NaiveDate> {
+        let start = match self {
+            CalendarUnit::Year => NaiveDate::from_ymd_opt(d.year(), 1, 1),
+            CalendarUnit::Month => NaiveDate::from_ymd_opt(d.year(), d.month(), 1),
+            CalendarUnit::Week => {
+                d.checked_sub_signed(Duration::days(d.weekday().num_days_from_monday() as i64))
+            }
+            _ => Some(d),
+        };
+        start.ok_or_else(|| self.out_of_range(d))
+    }
+
+    /// The first day of the next unit. `d` should be the first day of a unit at least a day long.
+    pub fn next_date(&self, d: NaiveDate) -> Result<NaiveDate> {
+        let next = match self {
+            CalendarUnit::Year => NaiveDate::from_ymd_opt(d.year() + 1, 1, 1),
+            CalendarUnit::Month if d.month() == 12 => NaiveDate::from_ymd_opt(d.year() + 1, 1, 1),
+            CalendarUnit::Month => NaiveDate::from_ymd_opt(d.year(), d.month() + 1, 1),
+            _ => d.checked_add_signed(self.fixed_duration().unwrap()),
+        };
+        next.ok_or_else(|| self.out_of_range(d))
+    }
+
+    /// The number of units from the unit starting at `first` to the one starting at `last`.
+    pub fn count(&self, first: NaiveDateTime, last: NaiveDateTime) -> i64 {
+        let years = last.year() as i64 - first.year() as i64;
+        match self {
+            CalendarUnit::Year => years + 1,
+            CalendarUnit::Month => years * 12 + last.month() as i64 - first.month() as i64 + 1,
+            // at most a few hundred thousand years in seconds, so it can't overflow
+            _ => (last - first).num_seconds() / self.fixed_duration().unwrap().num_seconds() + 1,
+        }
+    }
+
+    fn out_of_range(&self, t: impl std::fmt::Display) -> Error {
+        Error::OutOfRange {
+            what: format!("the {:?} of {}", self, t),
+        }
+    }
+}
+
+/// Bin edges and counts. `edges.len() == counts.len() + 1`.
+pub struct Histogram<T> {
+    pub edges: Vec<T>,
+    pub counts: Vec<f64>,
+}
+
+impl<T: Clone> Histogram<T> {
+    pub fn map_edges<U>(self, f: impl Fn(T) -> U) -> Histogram<U> {
+        Histogram {
+            edges: self.edges.into_iter().map(f).collect(),
+            counts: self.counts,
+        }
+    }
+
+    pub fn try_map_edges<U>(self, f: impl Fn(T) -> Result<U>) -> Result<Histogram<U>> {
+        Ok(Histogram {
+            edges: self.edges.into_iter().map(f).collect::<Result<_>>()?,
+            counts: self.counts,
+        })
+    }
+
+    /// Bar heights as (left, right, height).
+    ///
+    /// density: normalize so that the total area is 1. `width` gives the bin width in the axis unit.
+    /// cumulative: each bar is the sum of all bars on its left.
+    ///     With density, the last bar is 1.
+    pub fn bars(
+        &self,
+        density: bool,
+        cumulative: bool,
+        width: impl Fn(&T, &T) -> f64,
+    ) -> Vec<(T, T, f64)> {
+        let total: f64 = self.counts.iter().sum();
+        let total = if total == 0.0 { 1.0 } else { total };
+        let mut acc = 0.0;
+        self.edges
+            .windows(2)
+            .zip(&self.counts)
+            .map(|(e, &count)| {
+                let h = if cumulative {
+                    acc += count;
+                    if density {
+                        acc / total
+                    } else {
+                        acc
+                    }
+                } else if density {
+                    count / total / width(&e[0], &e[1])
+                } else {
+                    count
+                };
+                (e[0].clone(), e[1].clone(), h)
+            })
+            .collect()
+    }
+}
+
+/// Split [lo, hi] into `n` equal-width bins.
+/// If `range` is None, it's the min and max of the data.
+/// NaN and values outside of the range are ignored.
+///
+/// `values` is called twice when the range is inferred.
+pub fn uniform<I: Iterator<Item = f64>, E>(
+    values: impl Fn() -> Result<I, E>,
+    n: usize,
+    range: Option<(f64, f64)>,
+) -> Result<Histogram<f64>, E> {
+    let (lo, hi) = match range {
+        Some(range) => range,
+        None => values()?
+            .filter(|v| !v.is_nan())
+            .fold(None, |acc: Option<(f64, f64)>, v| match acc {
+                None => Some((v, v)),
+                Some((lo, hi)) => Some((lo.min(v), hi.max(v))),
+            })
+            .unwrap_or((0.0, 1.0)),
+    };
+    let (lo, hi) = if lo == hi {
+        (lo - 0.5, hi + 0.5)
+    } else {
+        (lo, hi)
+    };
+    let width = (hi - lo) / n as f64;
+
+    let mut counts = vec![0.0; n];
+    for v in values()? {
+        if !(lo <= v && v <= hi) {
+            continue; // also skips NaN
+        }
+        let idx = (((v - lo) / width) as usize).min(n - 1);
+        counts[idx] += 1.0;
+    }
+    Ok(Histogram {
+        edges: (0..=n).map(|i| lo + width * i as f64).collect(),
+        counts,
+    })
+}
+
+/// Split [lo, hi] of integers into at most `n` bins of a whole number of units.
+/// A bin [a, a + width) holds `width` integers, and the last bin ends after hi, so that
+/// no bin is empty of integers even if the range is narrower than `n`.
+/// If `range` is None, it's the min and max of the data.
+/// Values outside of the range are ignored.
+///
+/// `values` is called twice when the range is inferred.
+pub fn integer<I: Iterator<Item = i64>, E>(
+    values: impl Fn() -> Result<I, E>,
+    n: usize,
+    range: Option<(i64, i64)>,
+) -> Result<Histogram<i64>, E> {
+    let (lo, hi) = match range {
+        Some(range) => range,
+        None => values()?
+            .fold(None, |acc: Option<(i64, i64)>, v| match acc {
+                None => Some((v, v)),
+                Some((lo, hi)) => Some((lo.min(v), hi.max(v))),
+            })
+            .unwrap_or((0, 0)),
+    };
+    let (lo, hi) = (lo as i128, (hi as i128).max(lo as i128));
+    let span = hi - lo + 1;
+    let width = (span + n as i128 - 1) / n as i128;
+    let n = ((span + width - 1) / width) as usize;
+
+    let mut counts = vec![0.0; n];
+    for v in values()? {
+        let v = v as i128;
+        if lo <= v && v <= hi {
+            counts[((v - lo) / width) as usize] += 1.0;
+        }
+    }
+    // edges past i64::MAX saturate, which only happens for data at the very end of i64
+    Ok(Histogram {
+        edges: (0..=n)
+            .map(|i| (lo + width * i as i128).min(i64::MAX as i128) as i64)
+            .collect(),
+        counts,
+    })
+}
+
+/// One bin per `width`, aligned to multiples of `width`.
+/// Bins between the first and last non-empty bin are kept even if empty.
+/// Fails if there are more than `MAX_BINS` bins, or if the last edge is past i64.
+pub fn fixed_width(values: impl Iterator<Item = i64>, width: i64) -> PyResult<Histogram<i64>> {
+    let mut map = BTreeMap::<i64, f64>::new();
+    for v in values {
+        *map.entry(v.div_euclid(width)).or_insert(0.0) += 1.0;
+    }
+    let (first, last) = match (map.keys().next(), map.keys().next_back()) {
+        (Some(&first), Some(&last)) => (first, last),
+        _ => {
+            return Ok(Histogram {
+                edges: vec![],
+                counts: vec![],
+            })
+        }
+    };
+    let bins = last as i128 - first as i128 + 1;
+    if bins > MAX_BINS as i128 {
+        return Err(too_many_bins(bins));
+    }
+    let edge = |k: i128| {
+        let edge = k * width as i128;
+        i64::try_from(edge).map_err(|_| Error::OutOfRange {
+            what: format!("the bin edge {}", edge),
+        })
+    };
+    Ok(Histogram {
+        edges: (first as i128..=last as i128 + 1)
+            .map(edge)
+            .collect::<Result<_>>()?,
+        counts: (first..=last)
+            .map(|k| map.get(&k).copied().unwrap_or(0.0))
+            .collect(),
+    })
+}
+
+/// One bin per calendar unit.
+/// Bins between the first and last non-empty bin are kept even if empty.
+/// Fails if there are more than `MAX_BINS` bins.
+pub fn calendar(
+    values: impl Iterator<Item = NaiveDateTime>,
+    unit: CalendarUnit,
+) -> PyResult<Histogram<NaiveDateTime>> {
+    let mut map = BTreeMap::<NaiveDateTime, f64>::new();
+    for v in values {
+        *map.entry(unit.floor(v)?).or_insert(0.0) += 1.0;
+    }
+    let (first, last) = match (map.keys().next(), map.keys().next_back()) {
+        (Some(&first), Some(&last)) => (first, last),
+        _ => {
+            return Ok(Histogram {
+                edges: vec![],
+                counts: vec![],
+            })
+        }
+    };
+    let bins = unit.count(first, last);
+    if bins > MAX_BINS {
+        return Err(too_many_bins(bins as i128));
+    }
+    let mut edges = vec![first];
+    let mut counts = vec![];
+    let mut t = first;
+    while t <= last {
+        counts.push(map.get(&t).copied().unwrap_or(0.0));
+        t = unit.next(t)?;
+        edges.push(t);
+    }
+    Ok(Histogram { edges, counts })
+}
+
+/// Nanoseconds since the epoch. A f64 covers every datetime, unlike i64 nanoseconds.
+pub fn datetime_to_ns(t: NaiveDateTime) -> f64 {
+    t.timestamp() as f64 * 1e9 + t.timestamp_subsec_nanos() as f64
+}
+
+pub fn ns_to_datetime(ns: f64) -> Result<NaiveDateTime> {
+    let secs = (ns / 1e9).floor();
+    let nanos = ((ns - secs * 1e9) as u32).min(999_999_999);
+    NaiveDateTime::from_timestamp_opt(secs as i64, nanos).ok_or_else(|| Error::OutOfRange {
+        what: format!("{}s since the epoch", secs),
+    })
+}
+
+/// Nanoseconds since midnight.
+pub fn time_to_ns(t: NaiveTime) -> i64 {
+    t.num_seconds_from_midnight() as i64 * 1_000_000_000 + t.nanosecond() as i64
+}
+
+/// The time `ns` nanoseconds after midnight. The end of the day is the last nanosecond.
+pub fn ns_to_time(ns: i64) -> NaiveTime {
+    let ns = ns.clamp(0, 86_400 * 1_000_000_000 - 1);
+    NaiveTime::from_num_seconds_from_midnight(
+        (ns / 1_000_000_000) as u32,
+        (ns % 1_000_000_000) as u32,
+    )
+}
+
+/// Bars as (left, right, height), typed by the x-axis dtype.
+pub enum Bars {
+    F64(Vec<(f64, f64, f64)>),
+    F32(Vec<(f32, f32, f64)>),
+    I64(Vec<(i64, i64, f64)>),
+    I32(Vec<(i32, i32, f64)>),
+    DateTime(Vec<(NaiveDateTime, NaiveDateTime, f64)>),
+    Date(Vec<(NaiveDate, NaiveDate, f64)>),
+    Time(Vec<(NaiveTime, NaiveTime, f64)>),
+    Duration(Vec<(Duration, Duration, f64)>),
+}
+
+impl Bars {
+    pub fn x_dtype(&self) -> Dtype {
+        match self {
+            Bars::F64(..) => Dtype::F64,
+            Bars::F32(..) => Dtype::F32,
+            Bars::I64(..) => Dtype::I64,
+            Bars::I32(..) => Dtype::I32,
+            Bars::DateTime(..) => Dtype::NaiveDateTime,
+            Bars::Date(..) => Dtype::NaiveDate,
+            Bars::Time(..) => Dtype::NaiveTime,
+            Bars::Duration(..) => Dtype::Duration,
+        }
+    }
+
+    /// The x range and the (min, max) height covered by the bars. The heights always include 0.
+    pub fn extent(&self) -> Option<(RangeEnum, (f64, f64))> {
+        fn extent<T: Copy>(bars: &[(T, T, f64)]) -> Option<((T, T), (f64, f64))> {
+            let first = bars.first()?;
+            let last = bars.last()?;
+            let heights = bars
+                .iter()
+                .fold((0.0f64, 0.0f64), |(lo, hi), b| (lo.min(b.2), hi.max(b.2)));
+            Some(((first.0, last.1), heights))
+        }
+        match self {
+            Bars::F64(bars) => extent(bars).map(|((a, b), y)| (RangeEnum::F64(a, b), y)),
+            Bars::F32(bars) => extent(bars).map(|((a, b), y)| (RangeEnum::F32(a, b), y)),
+            Bars::I64(bars) => extent(bars).map(|((a, b), y)| (RangeEnum::I64(a, b), y)),
+            Bars::I32(bars) => extent(bars).map(|((a, b), y)| (RangeEnum::I32(a, b), y)),
+            Bars::DateTime(bars) => extent(bars).map(|((a, b), y)| (RangeEnum::DateTime(a, b), y)),
+            Bars::Date(bars) => extent(bars).map(|((a, b), y)| (RangeEnum::Date(a, b), y)),
+            Bars::Time(bars) => extent(bars).map(|((a, b), y)| (RangeEnum::Time(a, b), y)),
+            Bars::Duration(bars) => extent(bars).map(|((a, b), y)| (RangeEnum::Duration(a, b), y)),
+        }
+    }
+}
+
+/// A value of an x-axis that bars are binned on.
+pub trait BinValue: Sized {
+    /// The bars if they're binned on an axis of this type.
+    fn bars(bars: &Bars) -> Option<&[(Self, Self, f64)]>;
+}
+
+macro_rules! impl_bin_value {
+    ($($t:ty => $variant:ident),*) => {$(
+        impl BinValue for $t {
+            fn bars(bars: &Bars) -> Option<&[(Self, Self, f64)]> {
+                match bars {
+                    Bars::$variant(bars) => Some(bars),
+                    _ => None,
+                }
+            }
+        }
+    )*};
+}
+
+impl_bin_value!(
+    f64 => F64,
+    f32 => F32,
+    i64 => I64,
+    i32 => I32,
+    NaiveDateTime => DateTime,
+    NaiveDate => Date,
+    NaiveTime => Time,
+    Duration => Duration
+);
+
+/// Bins `x` for an x-axis of `x_dtype`.
+/// See `Chart.hist` for the arguments.
+pub fn bin(
+    py: Python,
+    x_dtype: Dtype,
+    x: &Series,
+    bins: Bins,
+    range: Option<RangeEnum>,
+    density: bool,
+    cumulative: bool,
+) -> PyResult<Bars> {
+    let range_err =
+        || pyo3::exceptions::PyValueError::new_err("range should have the dtype of the x-axis");
+    let unit_err = |unit: CalendarUnit| {
+        pyo3::exceptions::PyValueError::new_err(format!(
+            "{:?} bins are not supported on this axis",
+            unit
+        ))
+    };
+    let count = |bins: Bins| match bins {
+        Bins::Count(n) => Ok(n),
+        Bins::Unit(unit) => Err(unit_err(unit)),
+    };
+    let numeric_range = |range: Option<RangeEnum>| match range {
+        None => Ok(None),
+        Some(RangeEnum::F64(a, b)) => Ok(Some((a, b))),
+        Some(RangeEnum::F32(a, b)) => Ok(Some((a as f64, b as f64))),
+        Some(RangeEnum::I64(a, b)) => Ok(Some((a as f64, b as f64))),
+        Some(RangeEnum::I32(a, b)) => Ok(Some((a as f64, b as f64))),
+        Some(_) => Err(range_err()),
+    };
+    let integer_range = |range: Option<RangeEnum>| {
+        numeric_range(range).map(|r| r.map(|(a, b)| (a.ceil() as i64, b.floor() as i64)))
+    };
+
+    match x_dtype {
+        Dtype::F64 => {
+            let h = uniform(|| x.iter_f64(py), count(bins)?, numeric_range(range)?)?;
+            Ok(Bars::F64(h.bars(density, cumulative, |a, b| b - a)))
+        }
+        Dtype::F32 => {
+            let h = uniform(|| x.iter_f64(py), count(bins)?, numeric_range(range)?)?
+                .map_edges(|v| v as f32);
+            Ok(Bars::F32(
+                h.bars(density, cumulative, |a, b| (b - a) as f64),
+            ))
+        }
+        Dtype::I64 => {
+            let h = integer(|| x.iter_i64(py), count(bins)?, integer_range(range)?)?;
+            Ok(Bars::I64(
+                h.bars(density, cumulative, |a, b| (b - a) as f64),
+            ))
+        }
+        Dtype::I32 => {
+            let range = integer_range(range)?;
+            let h = integer(|| x.iter_i64(py), count(bins)?, range)?
+                .map_edges(|v| v.clamp(i32::MIN as i64, i32::MAX as i64) as i32);
+            Ok(Bars::I32(h.bars(density, cumulative, |a, b| {
+                (*b as f64) - (*a as f64)
+            })))
+        }
+        Dtype::NaiveDateTime => {
+            let range = match range {
+                None => None,
+                Some(RangeEnum::DateTime(a, b)) => Some((a, b)),
+                Some(_) => return Err(range_err()),
+            };
+            let h = match bins {
+                Bins::Count(n) => uniform(
+                    || Ok::<_, PyErr>(x.iter_datetime(py)?.map(datetime_to_ns)),
+                    n,
+                    range.map(|(a, b)| (datetime_to_ns(a), datetime_to_ns(b))),
+                )?
+                .try_map_edges(ns_to_datetime)?,
+                Bins::Unit(unit) => calendar(
+                    x.iter_datetime(py)?.filter(|t| match range {
+                        Some((a, b)) => a <= *t && *t <= b,
+                        None => true,
+                    }),
+                    unit,
+                )?,
+            };
+            Ok(Bars::DateTime(h.bars(density, cumulative, |a, b| {
+                (*b - *a).num_milliseconds() as f64 / 1000.0
+            })))
+        }
+        Dtype::NaiveDate => {
+            let range = match range {
+                None => None,
+                Some(RangeEnum::Date(a, b)) => Some((a, b)),
+                Some(_) => return Err(range_err()),
+            };
+            let to_days = |d: NaiveDate| d.num_days_from_ce() as i64;
+            let h = match bins {
+                Bins::Count(n) => integer(
+                    || Ok::<_, PyErr>(x.iter_date(py)?.map(to_days)),
+                    n,
+                    range.map(|(a, b)| (to_days(a), to_days(b))),
+                )?
+                .try_map_edges(|d| {
+                    NaiveDate::from_num_days_from_ce_opt(d as i32).ok_or_else(|| {
+                        Error::OutOfRange {
+                            what: format!("the day {} of the common era", d),
+                        }
+                    })
+                })?,
+                Bins::Unit(unit) if unit.is_date_unit() => calendar(
+                    x.iter_date(py)?
+                        .filter(|d| match range {
+                            Some((a, b)) => a <= *d && *d <= b,
+                            None => true,
+                        })
+                        .map(|d| d.and_hms(0, 0, 0)),
+                    unit,
+                )?
+                .map_edges(|t| t.date()),
+                Bins::Unit(unit) => return Err(unit_err(unit)),
+            };
+            Ok(Bars::Date(h.bars(density, cumulative, |a, b| {
+                (*b - *a).num_seconds() as f64
+            })))
+        }
+        Dtype::NaiveTime => {
+            let range = match range {
+                None => None,
+                Some(RangeEnum::Time(a, b)) => Some((a, b)),
+                Some(_) => return Err(range_err()),
+            };
+            let h = match bins {
+                Bins::Count(n) => uniform(
+                    || Ok::<_, PyErr>(x.iter_time(py)?.map(|t| time_to_ns(t) as f64)),
+                    n,
+                    range.map(|(a, b)| (time_to_ns(a) as f64, time_to_ns(b) as f64)),
+                )?
+                .map_edges(|ns| ns_to_time(ns.round() as i64)),
+                Bins::Unit(unit) if !unit.is_date_unit() => fixed_width(
+                    x.iter_time(py)?
+                        .filter(|t| match range {
+                            Some((a, b)) => a <= *t && *t <= b,
+                            None => true,
+                        })
+                        .map(time_to_ns),
+                    unit.fixed_duration().unwrap().num_nanoseconds().unwrap(),
+                )?
+                .map_edges(ns_to_time),
+                Bins::Unit(unit) => return Err(unit_err(unit)),
+            };
+            Ok(Bars::Time(h.bars(density, cumulative, |a, b| {
+                (*b - *a).num_milliseconds() as f64 / 1000.0
+            })))
+        }
+        Dtype::Duration => {
+            let range = match range {
+                None => None,
+                Some(RangeEnum::Duration(a, b)) => Some((a, b)),
+                Some(_) => return Err(range_err()),
+            };
+            let to_ns = |d: Duration| d.num_nanoseconds().unwrap_or(i64::MAX);
+            let h = match bins {
+                Bins::Count(n) => uniform(
+                    || Ok::<_, PyErr>(x.iter_duration(py)?.map(|d| to_ns(d) as f64)),
+                    n,
+                    range.map(|(a, b)| (to_ns(a) as f64, to_ns(b) as f64)),
+                )?
+                .map_edges(|ns| Duration::nanoseconds(ns as i64)),
+                Bins::Unit(unit) => match unit.fixed_duration() {
+                    Some(width) => fixed_width(
+                        x.iter_duration(py)?
+                            .filter(|d| match range {
+                                Some((a, b)) => a <= *d && *d <= b,
+                                None => true,
+                            })
+                            .map(to_ns),
+                        to_ns(width),
+                    )?
+                    .map_edges(Duration::nanoseconds),
+                    None => return Err(unit_err(unit)),
+                },
+            };
+            Ok(Bars::Duration(h.bars(density, cumulative, |a, b| {
+                (*b - *a).num_milliseconds() as f64 / 1000.0
+            })))
+        }
+        _ => Err(pyo3::exceptions::PyValueError::new_err(format!(
+            "hist is not supported on a {:?} axis",
+            x_dtype
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values<'a, T: Copy>(
+        v: &'a [T],
+    ) -> impl Fn() -> Result<std::iter::Copied<std::slice::Iter<'a, T>>> + 'a {
+        move || Ok(v.iter().copied())
+    }
+
+    #[test]
+    fn uniform_bins() {
+        let h = uniform(values(&[0.0, 1.0, 2.5, 4.0, f64::NAN]), 4, None).unwrap();
+        assert_eq!(h.edges, vec![0.0, 1.0, 2.0, 3.0, 4.0]);
+        // the max is in the last bin
+        assert_eq!(h.counts, vec![1.0, 1.0, 1.0, 1.0]);
+
+        let h = uniform(values(&[3.0, 3.0]), 2, None).unwrap();
+        assert_eq!(h.edges, vec![2.5, 3.0, 3.5]);
+        assert_eq!(h.counts, vec![0.0, 2.0]);
+    }
+
+    #[test]
+    fn integer_bins_stay_distinct() {
+        // 3 values can't fill 10 bins
+        let h = integer(values(&[5, 6, 7, 7]), 10, None).unwrap();
+        assert_eq!(h.edges, vec![5, 6, 7, 8]);
+        assert_eq!(h.counts, vec![1.0, 1.0, 2.0]);
+
+        let h = integer(values(&[0, 9, 10]), 3, None).unwrap();
+        assert_eq!(h.edges, vec![0, 4, 8, 12]);
+        assert_eq!(h.counts, vec![1.0, 0.0, 2.0]);
+
+        let h = integer(values(&[-5, 0, 5, 100]), 2, Some((0, 5))).unwrap();
+        assert_eq!(h.edges, vec![0, 3, 6]);
+        assert_eq!(h.counts, vec![1.0, 1.0]);
+
+        let h = integer(values(&[i64::MIN, i64::MAX]), 2, None).unwrap();
+        assert_eq!(h.counts, vec![1.0, 1.0]);
+    }
+
+    #[test]
+    fn fixed_width_bins() {
+        let h = fixed_width(vec![-1, 0, 25, 29].into_iter(), 10).unwrap();
+        assert_eq!(h.edges, vec![-10, 0, 10, 20, 30]);
+        assert_eq!(h.counts, vec![1.0, 1.0, 0.0, 2.0]);
+    }
+
+    #[test]
+    fn fixed_width_bins_are_bounded() {
+        assert!(fixed_width(vec![0, MAX_BINS * 10 - 1].into_iter(), 10).is_ok());
+        assert!(fixed_width(vec![0, MAX_BINS * 10].into_iter(), 10).is_err());
+        assert!(fixed_width(vec![0, MAX_BINS * 10].into_iter(), 1).is_err());
+        assert!(fixed_width(vec![i64::MIN, i64::MAX].into_iter(), 1_000_000_000).is_err());
+        // the edges around the ends of i64 are past i64
+        assert!(fixed_width(vec![i64::MAX].into_iter(), 10).is_err());
+        assert!(fixed_width(vec![i64::MIN].into_iter(), 10).is_err());
+        assert!(fixed_width(vec![i64::MIN].into_iter(), 2).is_ok());
+    }
+
+    #[test]
+    fn bars_density_and_cumulative() {
+        let h = Histogram {
+            edges: vec![0.0, 1.0, 3.0],
+            counts: vec![2.0, 2.0],
+        };
+        assert_eq!(
+            h.bars(true, false, |a, b| b - a),
+            vec![(0.0, 1.0, 0.5), (1.0, 3.0, 0.25)]
+        );
+        assert_eq!(
+            h.bars(true, true, |a, b| b - a),
+            vec![(0.0, 1.0, 0.5), (1.0, 3.0, 1.0)]
+        );
+        assert_eq!(
+            h.bars(false, true, |a, b| b - a),
+            vec![(0.0, 1.0, 2.0), (1.0, 3.0, 4.0)]
+        );
+    }
+
+    #[test]
+    fn calendar_units() {
+        let t = NaiveDate::from_ymd(2021, 12, 15).and_hms(13, 45, 30);
+        let floor = |unit: CalendarUnit| unit.floor(t).unwrap();
+        assert_eq!(
+            floor(CalendarUnit::Year),
+            NaiveDate::from_ymd(2021, 1, 1).and_hms(0, 0, 0)
+        );
+        assert_eq!(
+            floor(CalendarUnit::Month),
+            NaiveDate::from_ymd(2021, 12, 1).and_hms(0, 0, 0)
+        );
+        // 2021-12-15 is a Wednesday
+        assert_eq!(
+            floor(CalendarUnit::Week),
+            NaiveDate::from_ymd(2021, 12, 13).and_hms(0, 0, 0)
+        );
+        assert_eq!(
+            floor(CalendarUnit::Minute),
+            NaiveDate::from_ymd(2021, 12, 15).and_hms(13, 45, 0)
+        );
+        assert_eq!(
+            CalendarUnit::Month
+                .next(floor(CalendarUnit::Month))
+                .unwrap(),
+            NaiveDate::from_ymd(2022, 1, 1).and_hms(0, 0, 0)
+        );
+        assert_eq!(
+            CalendarUnit::Hour.next(floor(CalendarUnit::Hour)).unwrap(),
+            NaiveDate::from_ymd(2021, 12, 15).and_hms(14, 0, 0)
+        );
+    }
+
+    #[test]
+    fn calendar_units_out_of_range() {
+        let last = chrono::naive::MAX_DATE.and_hms(0, 0, 0);
+        assert!(CalendarUnit::Year.next(last).is_err());
+        assert!(CalendarUnit::Day.next(last).is_err());
+        let first = chrono::naive::MIN_DATE.and_hms(0, 0, 0);
+        assert!(CalendarUnit::Year.floor(first).is_ok());
+    }
+
+    #[test]
+    fn calendar_bins() {
+        let d = |m, d| NaiveDate::from_ymd(2021, m, d).and_hms(0, 0, 0);
+        let h = calendar(
+            vec![d(1, 31), d(3, 1), d(3, 2)].into_iter(),
+            CalendarUnit::Month,
+        )
+        .unwrap();
+        assert_eq!(h.edges, vec![d(1, 1), d(2, 1), d(3, 1), d(4, 1)]);
+        assert_eq!(h.counts, vec![1.0, 0.0, 2.0]);
+    }
+
+    #[test]
+    fn calendar_bins_are_bounded() {
+        let t = |y| NaiveDate::from_ymd(y, 1, 1).and_hms(0, 0, 0);
+        assert_eq!(CalendarUnit::Month.count(t(2020), t(2021)), 13);
+        assert_eq!(CalendarUnit::Day.count(t(2020), t(2021)), 367);
+        let years = vec![t(2000), t(2003)];
+        assert!(calendar(years.clone().into_iter(), CalendarUnit::Hour).is_ok());
+        assert!(calendar(years.into_iter(), CalendarUnit::Second).is_err());
+    }
+
+    #[test]
+    fn datetimes_beyond_i64_nanoseconds() {
+        for &year in &[1, 1500, 1970, 3000, 9999] {
+            let t = NaiveDate::from_ymd(year, 6, 1).and_hms_milli(12, 0, 0, 500);
+            let back = ns_to_datetime(datetime_to_ns(t)).unwrap();
+            assert!((back - t).num_milliseconds().abs() <= 1, "{}", t);
+        }
+        assert!(ns_to_datetime(1e30).is_err());
+    }
+
+    #[test]
+    fn times_are_clamped_to_the_day() {
+        assert_eq!(ns_to_time(3_600_000_000_000), NaiveTime::from_hms(1, 0, 0));
+        assert_eq!(
+            ns_to_time(86_400_000_000_000),
+            NaiveTime::from_hms_nano(23, 59, 59, 999_999_999)
+        );
+        assert_eq!(time_to_ns(NaiveTime::from_hms(1, 0, 0)), 3_600_000_000_000);
+    }
+}
@@ -0,0 +1,222 @@
+//! Pixel layouts of sub-canvases and charts. `Canvas.grid` places rows and columns of cells,
+//! sized by ratios and separated by gaps.
+//!
+//! A `Size` is in pixels or relative to the canvas, so that a layout keeps its proportions
+//! when the canvas size changes.
+
+use pyo3::prelude::*;
+use std::ops::Range;
+
+/// The pixel range of each of `n` tracks in `length` pixels, `space` pixels apart.
+/// A track is sized by its ratio, or evenly without ratios. `what` is "row" or "column".
+pub fn tracks(
+    length: u32,
+    n: usize,
+    space: u32,
+    ratios: Option<&[f64]>,
+    what: &str,
+) -> PyResult<Vec<Range<i32>>> {
+    if n == 0 {
+        return Err(pyo3::exceptions::PyValueError::new_err(format!(
+            "the number of {}s should be positive",
+            what
+        )));
+    }
+    let ratios = match ratios {
+        Some(ratios) if ratios.len() != n => {
+            return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                "expected {} {} ratios, got {}",
+                n,
+                what,
+                ratios.len()
+            )))
+        }
+        Some(ratios) if ratios.iter().any(|r| !r.is_finite() || *r <= 0.0) => {
+            return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                "{} ratios should be positive",
+                what
+            )))
+        }
+        Some(ratios) => ratios.to_vec(),
+        None => vec![1.0; n],
+    };
+    let gaps = space as u64 * (n as u64 - 1);
+    if gaps > length as u64 {
+        return Err(pyo3::exceptions::PyValueError::new_err(format!(
+            "the space between {}s ({}px) is larger than the canvas ({}px)",
+            what, gaps, length
+        )));
+    }
+    // rounding the cumulative sums keeps the tracks within a pixel of their ratios
+    let available = (length as u64 - gaps) as f64;
+    let total: f64 = ratios.iter().sum();
+    let mut sum = 0.0;
+    let mut start = 0;
+    Ok(ratios
+        .iter()
+        .enumerate()
+        .map(|(i, r)| {
+            sum += r;
+            let end = (available * sum / total).round() as i32;
+            let offset = (space as usize * i) as i32;
+            let track = start + offset..end + offset;
+            start = end;
+            track
+        })
+        .collect())
+}
+
+/// A length in pixels, or a fraction of the canvas width or height, up to the whole canvas.
+/// Accepts an int of pixels, a float between 0 and 1, or a percentage str like "30%".
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Size {
+    Pixels(i32),
+    Fraction(f64),
+}
+
+impl<'source> FromPyObject<'source> for Size {
+    fn extract(x: &'source PyAny) -> PyResult<Self> {
+        if let Ok(v) = x.extract::<i32>() {
+            if v < 0 {
+                return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                    "a size should not be negative, got {}px",
+                    v
+                )));
+            }
+            return Ok(Size::Pixels(v));
+        }
+        let fraction = match x.extract::<&str>() {
+            Ok(s) => match s.strip_suffix('%').map(|v| v.trim().parse::<f64>()) {
+                Some(Ok(v)) => v / 100.0,
+                _ => {
+                    return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                        "expected a percentage like \"30%\", got {:?}",
+                        s
+                    )))
+                }
+            },
+            Err(_) => x.extract::<f64>()?,
+        };
+        if !(0.0..=1.0).contains(&fraction) {
+            return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                "a size should be an int of pixels, or between 0 and 1 (\"0%\" and \"100%\"), got {}",
+                fraction
+            )));
+        }
+        Ok(Size::Fraction(fraction))
+    }
+}
+
+impl Size {
+    /// The pixels of the size in `length` pixels. Fails if it's larger than `length`.
+    pub fn in_pixels(&self, length: u32) -> PyResult<i32> {
+        match self {
+            Size::Pixels(v) if *v as i64 > length as i64 => {
+                Err(pyo3::exceptions::PyValueError::new_err(format!(
+                    "a size of {}px is larger than the canvas ({}px)",
+                    v, length
+                )))
+            }
+            Size::Pixels(v) => Ok(*v),
+            Size::Fraction(f) => Ok((length as f64 * f).round() as i32),
+        }
+    }
+}
+
+/// Where to split a canvas. Accepts a `Size` of the first part, or a list of ratios of N parts,
+/// e.g. [1, 2, 1].
+pub enum Split {
+    At(Size),
+    Ratios(Vec<f64>),
+}
+
+impl<'source> FromPyObject<'source> for Split {
+    fn extract(x: &'source PyAny) -> PyResult<Self> {
+        if let Ok(ratios) = x.extract::<Vec<f64>>() {
+            return Ok(Split::Ratios(ratios));
+        }
+        Ok(Split::At(x.extract()?))
+    }
+}
+
+/// The pixels where `length` pixels are split, evenly in two parts by default.
+pub fn breakpoints(length: u32, split: Option<Split>) -> PyResult<Vec<i32>> {
+    Ok(match split {
+        None => vec![(length / 2) as i32],
+        Some(Split::At(size)) => vec![size.in_pixels(length)?],
+        Some(Split::Ratios(ratios)) => tracks(length, ratios.len(), 0, Some(&ratios), "part")?
+            .into_iter()
+            .skip(1)
+            .map(|part| part.start)
+            .collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn even_tracks() {
+        assert_eq!(
+            tracks(100, 4, 0, None, "row").unwrap(),
+            vec![0..25, 25..50, 50..75, 75..100]
+        );
+        assert_eq!(
+            tracks(100, 3, 5, None, "row").unwrap(),
+            vec![0..30, 35..65, 70..100]
+        );
+    }
+
+    #[test]
+    fn tracks_by_ratios() {
+        assert_eq!(
+            tracks(100, 3, 0, Some(&[2.0, 1.0, 1.0]), "column").unwrap(),
+            vec![0..50, 50..75, 75..100]
+        );
+        // rounding never leaves a gap or a pixel past the end
+        let odd = tracks(100, 3, 0, None, "column").unwrap();
+        assert_eq!(odd, vec![0..33, 33..67, 67..100]);
+    }
+
+    #[test]
+    fn invalid_tracks() {
+        assert!(tracks(100, 0, 0, None, "row").is_err());
+        assert!(tracks(100, 2, 0, Some(&[1.0]), "row").is_err());
+        assert!(tracks(100, 2, 0, Some(&[1.0, 0.0]), "row").is_err());
+        assert!(tracks(100, 2, 0, Some(&[1.0, f64::NAN]), "row").is_err());
+        assert!(tracks(100, 3, 60, None, "row").is_err());
+        assert_eq!(
+            tracks(100, 3, 50, None, "row").unwrap(),
+            vec![0..0, 50..50, 100..100]
+        );
+    }
+
+    #[test]
+    fn sizes() {
+        assert_eq!(Size::Pixels(40).in_pixels(100).unwrap(), 40);
+        assert_eq!(Size::Pixels(100).in_pixels(100).unwrap(), 100);
+        assert!(Size::Pixels(101).in_pixels(100).is_err());
+        assert_eq!(Size::Fraction(0.25).in_pixels(100).unwrap(), 25);
+        assert_eq!(Size::Fraction(1.0).in_pixels(100).unwrap(), 100);
+    }
+
+    #[test]
+    fn split_breakpoints() {
+        assert_eq!(breakpoints(101, None).unwrap(), vec![50]);
+        assert_eq!(
+            breakpoints(100, Some(Split::At(Size::Pixels(30)))).unwrap(),
+            vec![30]
+        );
+        assert_eq!(
+            breakpoints(100, Some(Split::At(Size::Fraction(0.3)))).unwrap(),
+            vec![30]
+        );
+        assert!(breakpoints(100, Some(Split::At(Size::Pixels(130)))).is_err());
+        assert_eq!(
+            breakpoints(100, Some(Split::Ratios(vec![1.0, 2.0, 1.0]))).unwrap(),
+            vec![25, 75]
+        );
+        assert!(breakpoints(100, Some(Split::Ratios(vec![]))).is_err());
+    }
+}
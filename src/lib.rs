@@ -1,13 +1,27 @@
+mod annotation;
 mod backend;
+mod bar;
+mod boxplot;
 mod canvas;
 mod chart;
 mod color;
+mod coord;
 mod datetime;
 mod dtype;
+mod error;
+mod errorbar;
+mod fill;
 mod hack;
+mod heatmap;
 mod help;
+mod hist;
+mod layout;
+mod marker;
+mod ohlc;
 mod range;
 mod series;
+mod step;
+mod tick;
 
 use help::module as help_pymodule;
 
@@ -24,7 +38,7 @@ use pyo3::prelude::*;
 /// ezel is a fast Python plotting library.
 /// Its main focus is a quick visualization for daily data-science and analysis.
 #[pymodule]
-fn ezel(_py: Python, m: &PyModule) -> PyResult<()> {
+fn ezel(py: Python, m: &PyModule) -> PyResult<()> {
     // m.add_function(pyo3::wrap_pyfunction!(count, m)?).unwrap();
     // m.add_class()
 
@@ -37,6 +51,13 @@ fn ezel(_py: Python, m: &PyModule) -> PyResult<()> {
     m.add_class::<DateTime>()?;
     m.add_class::<Date>()?;
     m.add_class::<Time>()?;
+    m.add_class::<Duration>()?;
+    m.add_class::<color::Color>()?;
+    m.add_class::<color::Colormap>()?;
+    m.add_class::<color::Palette>()?;
+
+    // exception list
+    error::add_exceptions(py, m)?;
     Ok(())
 }
 
@@ -0,0 +1,194 @@
+//! Scatter markers. `Chart.scatter` draws a marker per point,
+//! with an optional color and size for each point.
+//!
+//! `MarkerElement` draws its shape directly on the backend,
+//! so that a million points don't allocate a composed element each.
+
+use crate::color::ColorArg;
+use crate::Series;
+use plotters::element::{Drawable, PointCollection};
+use plotters::prelude::*;
+use plotters::style::RGBAColor;
+use plotters_backend::{BackendCoord, DrawingErrorKind};
+use pyo3::prelude::*;
+
+/// A marker shape. Accepts "circle", "square", "triangle", "cross", "x" or "diamond".
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Marker {
+    Circle,
+    Square,
+    Triangle,
+    /// +
+    Cross,
+    X,
+    Diamond,
+}
+
+impl<'source> FromPyObject<'source> for Marker {
+    fn extract(x: &'source PyAny) -> PyResult<Self> {
+        Ok(match x.extract::<&str>()? {
+            "circle" => Marker::Circle,
+            "square" => Marker::Square,
+            "triangle" => Marker::Triangle,
+            "cross" => Marker::Cross,
+            "x" => Marker::X,
+            "diamond" => Marker::Diamond,
+            name => {
+                return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                    "unknown marker {:?}, expected \"circle\", \"square\", \"triangle\", \"cross\", \"x\" or \"diamond\"",
+                    name
+                )))
+            }
+        })
+    }
+}
+
+/// A marker at a point. `size` is the distance from the center to the edge in pixels.
+pub struct MarkerElement<Coord> {
+    center: Coord,
+    marker: Marker,
+    size: u32,
+    style: ShapeStyle,
+}
+
+impl<Coord> MarkerElement<Coord> {
+    pub fn new(center: Coord, marker: Marker, size: u32, style: ShapeStyle) -> Self {
+        MarkerElement {
+            center,
+            marker,
+            size,
+            style,
+        }
+    }
+}
+
+impl<'a, Coord: 'a> PointCollection<'a, Coord> for &'a MarkerElement<Coord> {
+    type Point = &'a Coord;
+    type IntoIter = std::iter::Once<&'a Coord>;
+    fn point_iter(self) -> Self::IntoIter {
+        std::iter::once(&self.center)
+    }
+}
+
+impl<Coord, DB: DrawingBackend> Drawable<DB> for MarkerElement<Coord> {
+    fn draw<I: Iterator<Item = BackendCoord>>(
+        &self,
+        mut points: I,
+        backend: &mut DB,
+        _: (u32, u32),
+    ) -> Result<(), DrawingErrorKind<DB::ErrorType>> {
+        let (x, y) = match points.next() {
+            Some(p) => p,
+            None => return Ok(()),
+        };
+        let (s, style, filled) = (self.size as i32, &self.style, self.style.filled);
+        let polygon = |backend: &mut DB, vertices: Vec<BackendCoord>| {
+            if filled {
+                backend.fill_polygon(vertices, style)
+            } else {
+                let first = vertices[0];
+                backend.draw_path(vertices.into_iter().chain(Some(first)), style)
+            }
+        };
+        match self.marker {
+            Marker::Circle => backend.draw_circle((x, y), self.size, style, filled),
+            Marker::Square => backend.draw_rect((x - s, y - s), (x + s, y + s), style, filled),
+            Marker::Triangle => {
+                // pointing up, centered at the centroid
+                let vertices = [-90.0f64, 30.0, 150.0]
+                    .iter()
+                    .map(|deg| {
+                        let rad = deg.to_radians();
+                        (
+                            x + (s as f64 * rad.cos()) as i32,
+                            y + (s as f64 * rad.sin()) as i32,
+                        )
+                    })
+                    .collect();
+                polygon(backend, vertices)
+            }
+            Marker::Diamond => polygon(
+                backend,
+                vec![(x, y - s), (x + s, y), (x, y + s), (x - s, y)],
+            ),
+            Marker::Cross => {
+                backend.draw_line((x - s, y), (x + s, y), style)?;
+                backend.draw_line((x, y - s), (x, y + s), style)
+            }
+            Marker::X => {
+                backend.draw_line((x - s, y - s), (x + s, y + s), style)?;
+                backend.draw_line((x - s, y + s), (x + s, y - s), style)
+            }
+        }
+    }
+}
+
+/// The marker size of every point, or each point. Accepts a number, or a series of numbers.
+pub enum SizeArg {
+    Scalar(u32),
+    Series(Series),
+}
+
+impl<'source> FromPyObject<'source> for SizeArg {
+    fn extract(x: &'source PyAny) -> PyResult<Self> {
+        if let Ok(v) = x.extract::<u32>() {
+            return Ok(SizeArg::Scalar(v));
+        }
+        Ok(SizeArg::Series(x.extract()?))
+    }
+}
+
+/// The color of every point, or a value for each point mapped through a colormap.
+/// A tuple is a color, so values should be a list or a numpy array.
+pub enum PointColorArg {
+    Color(ColorArg),
+    Values(Series),
+}
+
+impl<'source> FromPyObject<'source> for PointColorArg {
+    fn extract(x: &'source PyAny) -> PyResult<Self> {
+        if let Ok(color) = x.extract::<ColorArg>() {
+            return Ok(PointColorArg::Color(color));
+        }
+        match x.extract::<Series>() {
+            Ok(values) => Ok(PointColorArg::Values(values)),
+            Err(_) => Err(pyo3::exceptions::PyValueError::new_err(
+                "color should be a color, or a series of numbers to map through cmap",
+            )),
+        }
+    }
+}
+
+/// Points to draw with markers. `sizes` and `colors` have a value for each point.
+pub struct ScatterSeries {
+    pub x: Series,
+    pub y: Series,
+    pub marker: Marker,
+    /// The size of every point, used in the legend.
+    pub size: u32,
+    pub sizes: Option<Vec<u32>>,
+    /// A point without a color is not drawn.
+    pub colors: Option<Vec<Option<RGBAColor>>>,
+}
+
+impl ScatterSeries {
+    /// The markers at the points, styled by `style` unless a point has its own size or color.
+    pub fn markers<'a, T: 'a>(
+        &'a self,
+        points: impl Iterator<Item = T> + 'a,
+        style: &'a ShapeStyle,
+    ) -> impl Iterator<Item = MarkerElement<T>> + 'a {
+        points.enumerate().filter_map(move |(i, p)| {
+            let size = self.sizes.as_ref().map_or(self.size, |sizes| sizes[i]);
+            let color = match &self.colors {
+                Some(colors) => colors[i].clone()?,
+                None => style.color.clone(),
+            };
+            let style = ShapeStyle {
+                color,
+                ..style.clone()
+            };
+            Some(MarkerElement::new(p, self.marker, size, style))
+        })
+    }
+}
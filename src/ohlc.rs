@@ -0,0 +1,118 @@
+//! Financial glyphs of open, high, low and close prices.
+//! `Chart.candlestick` draws plotters' `CandleStick`, and `Chart.ohlc` draws a bar from low to high
+//! with a tick at open on the left and a tick at close on the right.
+//!
+//! A glyph is drawn in the up style when close > open, and in the down style otherwise.
+//! Widths are in pixels, 70% of the gap between the closest glyphs by default.
+
+use crate::range::{self, RangeEnum};
+use crate::{Dtype, Series};
+use plotters::element::{Drawable, PointCollection};
+use plotters::prelude::*;
+use plotters_backend::{BackendCoord, DrawingErrorKind};
+use pyo3::prelude::*;
+use std::cmp::Ordering;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Glyph {
+    Candlestick,
+    Ohlc,
+}
+
+/// Prices at each time. `open`, `high`, `low` and `close` have the length of `t`.
+pub struct OhlcSeries {
+    pub t: Series,
+    pub open: Series,
+    pub high: Series,
+    pub low: Series,
+    pub close: Series,
+    pub glyph: Glyph,
+    /// in pixels.
+    pub width: Option<u32>,
+}
+
+impl OhlcSeries {
+    /// The y range covered by the prices.
+    pub fn y_extent(&self, py: Python, dtype: Dtype) -> PyResult<Option<RangeEnum>> {
+        let mut extent: Option<RangeEnum> = None;
+        for prices in [&self.open, &self.high, &self.low, &self.close].iter() {
+            extent = match (extent, range::series_extent(py, prices, dtype)?) {
+                (Some(a), Some(b)) => Some(a.union(b)),
+                (a, b) => a.or(b),
+            };
+        }
+        Ok(extent)
+    }
+}
+
+/// 70% of the smallest gap between the x pixels, or 7 pixels for a single glyph.
+pub fn default_width(mut xs: Vec<i32>) -> u32 {
+    xs.sort_unstable();
+    let gap = xs.windows(2).map(|w| w[1] - w[0]).filter(|d| *d > 0).min();
+    gap.map_or(7, |gap| ((gap as f64 * 0.7) as u32).max(1))
+}
+
+/// An OHLC bar, the counterpart of plotters' `CandleStick`.
+pub struct OhlcBar<X, Y> {
+    style: ShapeStyle,
+    width: u32,
+    /// open, high, low, close
+    points: [(X, Y); 4],
+}
+
+impl<X: Clone, Y: PartialOrd> OhlcBar<X, Y> {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        x: X,
+        open: Y,
+        high: Y,
+        low: Y,
+        close: Y,
+        up_style: &ShapeStyle,
+        down_style: &ShapeStyle,
+        width: u32,
+    ) -> Self {
+        OhlcBar {
+            style: match open.partial_cmp(&close) {
+                Some(Ordering::Less) => up_style.clone(),
+                _ => down_style.clone(),
+            },
+            width,
+            points: [
+                (x.clone(), open),
+                (x.clone(), high),
+                (x.clone(), low),
+                (x, close),
+            ],
+        }
+    }
+}
+
+impl<'a, X: 'a, Y: 'a> PointCollection<'a, (X, Y)> for &'a OhlcBar<X, Y> {
+    type Point = &'a (X, Y);
+    type IntoIter = &'a [(X, Y)];
+    fn point_iter(self) -> Self::IntoIter {
+        &self.points
+    }
+}
+
+impl<X, Y, DB: DrawingBackend> Drawable<DB> for OhlcBar<X, Y> {
+    fn draw<I: Iterator<Item = BackendCoord>>(
+        &self,
+        points: I,
+        backend: &mut DB,
+        _: (u32, u32),
+    ) -> Result<(), DrawingErrorKind<DB::ErrorType>> {
+        let points: Vec<BackendCoord> = points.take(4).collect();
+        if let [open, high, low, close] = points[..] {
+            let (l, r) = (
+                self.width as i32 / 2,
+                self.width as i32 - self.width as i32 / 2,
+            );
+            backend.draw_line(high, low, &self.style)?;
+            backend.draw_line((open.0 - l, open.1), open, &self.style)?;
+            backend.draw_line(close, (close.0 + r, close.1), &self.style)?;
+        }
+        Ok(())
+    }
+}
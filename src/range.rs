@@ -1,48 +1,126 @@
 use crate::Dtype;
+use crate::Series;
 use chrono::{Duration, NaiveDate, NaiveDateTime, NaiveTime};
 use pyo3::prelude::*;
+use std::rc::Rc;
 
 /// Range represents a start and end.
-/// It has no concept such as step or log scale.
+/// A f64 range can be drawn on a linear, log, or symlog scale.
 ///
 /// ```py
 /// ez.Chart(.., x_range=ez.Range.f64(-10, 10), ..)
+/// ez.Chart(.., y_range=ez.Range.f64(1e-3, 1e3, scale="log"), ..)
+/// ez.Chart(.., x_range=ez.Range.categories(["a", "b", "c"]), ..)
 /// ```
-#[pyclass]
-#[derive(Clone, Copy)]
+#[pyclass(unsendable)]
+#[derive(Clone)]
 pub struct Range {
     pub range: RangeEnum,
+    pub scale: Scale,
 }
 
 impl Range {
     pub fn dtype(&self) -> Dtype {
-        match self.range {
-            RangeEnum::I64(..) => Dtype::I64,
-            RangeEnum::I32(..) => Dtype::I32,
-            RangeEnum::F64(..) => Dtype::F64,
-            RangeEnum::F32(..) => Dtype::F32,
-            RangeEnum::DateTime(..) => Dtype::NaiveDateTime,
-            RangeEnum::Date(..) => Dtype::NaiveDate,
-            RangeEnum::Time(..) => Dtype::NaiveTime,
-            RangeEnum::Duration(..) => Dtype::Duration,
+        self.range.dtype()
+    }
+}
+
+/// How values are mapped onto an axis.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Scale {
+    Linear,
+    Log {
+        base: f64,
+    },
+    /// Linear within [-linthresh, linthresh] and log scale outside of it, like the symlog of matplotlib.
+    /// Each power of base past linthresh takes as much of the axis as the whole linear part on one side.
+    SymLog {
+        base: f64,
+        linthresh: f64,
+    },
+}
+
+impl Scale {
+    /// The position of `v` on a linear axis.
+    pub fn transform(&self, v: f64) -> f64 {
+        match *self {
+            Scale::Linear => v,
+            Scale::Log { base } => v.log(base),
+            Scale::SymLog { linthresh, .. } if v.abs() <= linthresh => v / linthresh,
+            Scale::SymLog { base, linthresh } => {
+                v.signum() * (1.0 + (v.abs() / linthresh).log(base))
+            }
+        }
+    }
+
+    /// The value at the position `t` on a linear axis, the inverse of `transform`.
+    pub fn inverse(&self, t: f64) -> f64 {
+        match *self {
+            Scale::Linear => t,
+            Scale::Log { base } => base.powf(t),
+            Scale::SymLog { linthresh, .. } if t.abs() <= 1.0 => t * linthresh,
+            Scale::SymLog { base, linthresh } => t.signum() * linthresh * base.powf(t.abs() - 1.0),
         }
     }
 }
 
 #[pymethods]
 impl Range {
+    /// scale: "linear" (default), "log", or "symlog".
+    /// base: the log base (default 10). Used by "log" and "symlog".
+    /// linthresh: "symlog" is linear in [-linthresh, linthresh] (default 1).
     #[staticmethod]
-    #[text_signature = "(a, b)"]
-    pub fn f64(a: f64, b: f64) -> Self {
-        Self {
-            range: RangeEnum::F64(a, b),
+    #[text_signature = "(a, b, scale=\"linear\", base=10, linthresh=1)"]
+    pub fn f64(
+        a: f64,
+        b: f64,
+        scale: Option<&str>,
+        base: Option<f64>,
+        linthresh: Option<f64>,
+    ) -> PyResult<Self> {
+        let base = base.unwrap_or(10.0);
+        let linthresh = linthresh.unwrap_or(1.0);
+        if !(base > 1.0) {
+            return Err(pyo3::exceptions::PyValueError::new_err(
+                "base should be greater than 1",
+            ));
         }
+        let scale = match scale.unwrap_or("linear") {
+            "linear" => Scale::Linear,
+            "log" => {
+                if !(a > 0.0 && b > 0.0) {
+                    return Err(pyo3::exceptions::PyValueError::new_err(
+                        "a log range should be positive",
+                    ));
+                }
+                Scale::Log { base }
+            }
+            "symlog" => {
+                if !(linthresh > 0.0) {
+                    return Err(pyo3::exceptions::PyValueError::new_err(
+                        "linthresh should be positive",
+                    ));
+                }
+                Scale::SymLog { base, linthresh }
+            }
+            other => {
+                return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                    "unknown scale {:?}, expected \"linear\", \"log\" or \"symlog\"",
+                    other
+                )))
+            }
+        };
+        Ok(Self {
+            range: RangeEnum::F64(a, b),
+            scale,
+        })
     }
     #[staticmethod]
     #[text_signature = "(a, b)"]
     pub fn f32(a: f32, b: f32) -> Self {
         Self {
             range: RangeEnum::F32(a, b),
+            scale: Scale::Linear,
         }
     }
     #[staticmethod]
@@ -50,6 +128,7 @@ impl Range {
     pub fn i64(a: i64, b: i64) -> Self {
         Self {
             range: RangeEnum::I64(a, b),
+            scale: Scale::Linear,
         }
     }
     #[staticmethod]
@@ -57,6 +136,7 @@ impl Range {
     pub fn i32(a: i32, b: i32) -> Self {
         Self {
             range: RangeEnum::I32(a, b),
+            scale: Scale::Linear,
         }
     }
     #[staticmethod]
@@ -64,6 +144,7 @@ impl Range {
     pub fn date(a: pyo3_chrono::NaiveDate, b: pyo3_chrono::NaiveDate) -> Self {
         Self {
             range: RangeEnum::Date(a.0, b.0),
+            scale: Scale::Linear,
         }
     }
     #[staticmethod]
@@ -71,6 +152,7 @@ impl Range {
     pub fn datetime(a: pyo3_chrono::NaiveDateTime, b: pyo3_chrono::NaiveDateTime) -> Self {
         Self {
             range: RangeEnum::DateTime(a.0, b.0),
+            scale: Scale::Linear,
         }
     }
     #[staticmethod]
@@ -78,6 +160,7 @@ impl Range {
     pub fn time(a: pyo3_chrono::NaiveTime, b: pyo3_chrono::NaiveTime) -> Self {
         Self {
             range: RangeEnum::Time(a.0, b.0),
+            scale: Scale::Linear,
         }
     }
     #[staticmethod]
@@ -85,11 +168,29 @@ impl Range {
     pub fn duration(a: pyo3_chrono::Duration, b: pyo3_chrono::Duration) -> Self {
         Self {
             range: RangeEnum::Duration(a.0, b.0),
+            scale: Scale::Linear,
+        }
+    }
+    /// String categories, placed in this order along the axis.
+    #[staticmethod]
+    #[text_signature = "(names)"]
+    pub fn categories(names: Vec<String>) -> PyResult<Self> {
+        for (i, name) in names.iter().enumerate() {
+            if names[..i].contains(name) {
+                return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                    "duplicate category {:?}",
+                    name
+                )));
+            }
         }
+        Ok(Self {
+            range: RangeEnum::Category(Rc::new(names)),
+            scale: Scale::Linear,
+        })
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 pub enum RangeEnum {
     F64(f64, f64),
     F32(f32, f32),
@@ -99,4 +200,180 @@ pub enum RangeEnum {
     DateTime(NaiveDateTime, NaiveDateTime),
     Time(NaiveTime, NaiveTime),
     Duration(Duration, Duration),
+    Category(Rc<Vec<String>>),
+}
+
+impl RangeEnum {
+    pub fn dtype(&self) -> Dtype {
+        match self {
+            RangeEnum::I64(..) => Dtype::I64,
+            RangeEnum::I32(..) => Dtype::I32,
+            RangeEnum::F64(..) => Dtype::F64,
+            RangeEnum::F32(..) => Dtype::F32,
+            RangeEnum::DateTime(..) => Dtype::NaiveDateTime,
+            RangeEnum::Date(..) => Dtype::NaiveDate,
+            RangeEnum::Time(..) => Dtype::NaiveTime,
+            RangeEnum::Duration(..) => Dtype::Duration,
+            RangeEnum::Category(..) => Dtype::String,
+        }
+    }
+
+    /// The smallest range containing both. `other` is ignored if the dtypes differ.
+    pub fn union(self, other: Self) -> Self {
+        use std::cmp::{max, min};
+        match (self, other) {
+            (RangeEnum::F64(a, b), RangeEnum::F64(c, d)) => RangeEnum::F64(a.min(c), b.max(d)),
+            (RangeEnum::F32(a, b), RangeEnum::F32(c, d)) => RangeEnum::F32(a.min(c), b.max(d)),
+            (RangeEnum::I64(a, b), RangeEnum::I64(c, d)) => RangeEnum::I64(min(a, c), max(b, d)),
+            (RangeEnum::I32(a, b), RangeEnum::I32(c, d)) => RangeEnum::I32(min(a, c), max(b, d)),
+            (RangeEnum::Date(a, b), RangeEnum::Date(c, d)) => RangeEnum::Date(min(a, c), max(b, d)),
+            (RangeEnum::DateTime(a, b), RangeEnum::DateTime(c, d)) => {
+                RangeEnum::DateTime(min(a, c), max(b, d))
+            }
+            (RangeEnum::Time(a, b), RangeEnum::Time(c, d)) => RangeEnum::Time(min(a, c), max(b, d)),
+            (RangeEnum::Duration(a, b), RangeEnum::Duration(c, d)) => {
+                RangeEnum::Duration(min(a, c), max(b, d))
+            }
+            (RangeEnum::Category(a), RangeEnum::Category(b)) => {
+                let mut names = (*a).clone();
+                names.extend(b.iter().filter(|name| !a.contains(name)).cloned());
+                RangeEnum::Category(Rc::new(names))
+            }
+            (r, _) => r,
+        }
+    }
+
+    /// Extends both ends by `ratio` of the span.
+    /// An empty range is extended by one unit (1, 1 day, 1 hour, 1 second, ..) instead.
+    /// Categories already have half a segment of margin, and are not padded.
+    pub fn pad(self, ratio: f64) -> Self {
+        let scale = |d: Duration| match d.num_nanoseconds() {
+            Some(ns) => Duration::nanoseconds((ns as f64 * ratio) as i64),
+            None => Duration::milliseconds((d.num_milliseconds() as f64 * ratio) as i64),
+        };
+        match self {
+            RangeEnum::F64(a, b) if a == b => RangeEnum::F64(a - 0.5, b + 0.5),
+            RangeEnum::F64(a, b) => RangeEnum::F64(a - (b - a) * ratio, b + (b - a) * ratio),
+            RangeEnum::F32(a, b) if a == b => RangeEnum::F32(a - 0.5, b + 0.5),
+            RangeEnum::F32(a, b) => {
+                let d = (b - a) * ratio as f32;
+                RangeEnum::F32(a - d, b + d)
+            }
+            RangeEnum::I64(a, b) => {
+                let d = (((b - a) as f64 * ratio).ceil() as i64).max(1);
+                RangeEnum::I64(a.saturating_sub(d), b.saturating_add(d))
+            }
+            RangeEnum::I32(a, b) => {
+                let d = (((b - a) as f64 * ratio).ceil() as i32).max(1);
+                RangeEnum::I32(a.saturating_sub(d), b.saturating_add(d))
+            }
+            RangeEnum::Date(a, b) => {
+                let d = Duration::days((((b - a).num_days() as f64 * ratio).ceil() as i64).max(1));
+                RangeEnum::Date(a - d, b + d)
+            }
+            RangeEnum::DateTime(a, b) if a == b => {
+                RangeEnum::DateTime(a - Duration::hours(1), b + Duration::hours(1))
+            }
+            RangeEnum::DateTime(a, b) => {
+                let d = scale(b - a);
+                RangeEnum::DateTime(a - d, b + d)
+            }
+            RangeEnum::Time(a, b) => {
+                // a time axis can't cross midnight
+                let d = if a == b {
+                    Duration::minutes(1)
+                } else {
+                    scale(b - a)
+                };
+                let (lo, lo_wrapped) = a.overflowing_sub_signed(d);
+                let (hi, hi_wrapped) = b.overflowing_add_signed(d);
+                RangeEnum::Time(
+                    if lo_wrapped != 0 {
+                        NaiveTime::from_hms(0, 0, 0)
+                    } else {
+                        lo
+                    },
+                    if hi_wrapped != 0 {
+                        NaiveTime::from_hms_nano(23, 59, 59, 999_999_999)
+                    } else {
+                        hi
+                    },
+                )
+            }
+            RangeEnum::Duration(a, b) if a == b => {
+                RangeEnum::Duration(a - Duration::seconds(1), b + Duration::seconds(1))
+            }
+            RangeEnum::Duration(a, b) => {
+                let d = scale(b - a);
+                RangeEnum::Duration(a - d, b + d)
+            }
+            RangeEnum::Category(names) => RangeEnum::Category(names),
+        }
+    }
+}
+
+/// (min, max) of the values. None if there is no value.
+pub fn min_max<T: PartialOrd + Copy>(values: impl Iterator<Item = T>) -> Option<(T, T)> {
+    values.fold(None, |acc, v| match acc {
+        None => Some((v, v)),
+        Some((lo, hi)) => Some((if v < lo { v } else { lo }, if v > hi { v } else { hi })),
+    })
+}
+
+/// The range of a series as `dtype`. NaN is skipped.
+/// The range of a list of str is its distinct values in the order they first appear.
+pub fn series_extent(py: Python, series: &Series, dtype: Dtype) -> PyResult<Option<RangeEnum>> {
+    Ok(match dtype {
+        Dtype::F64 => {
+            min_max(series.iter_f64(py)?.filter(|v| !v.is_nan())).map(|(a, b)| RangeEnum::F64(a, b))
+        }
+        Dtype::F32 => {
+            min_max(series.iter_f32(py)?.filter(|v| !v.is_nan())).map(|(a, b)| RangeEnum::F32(a, b))
+        }
+        Dtype::I64 => min_max(series.iter_i64(py)?).map(|(a, b)| RangeEnum::I64(a, b)),
+        Dtype::I32 => min_max(series.iter_i32(py)?).map(|(a, b)| RangeEnum::I32(a, b)),
+        Dtype::NaiveDateTime => {
+            min_max(series.iter_datetime(py)?).map(|(a, b)| RangeEnum::DateTime(a, b))
+        }
+        Dtype::NaiveDate => min_max(series.iter_date(py)?).map(|(a, b)| RangeEnum::Date(a, b)),
+        Dtype::NaiveTime => min_max(series.iter_time(py)?).map(|(a, b)| RangeEnum::Time(a, b)),
+        Dtype::Duration => {
+            min_max(series.iter_duration(py)?).map(|(a, b)| RangeEnum::Duration(a, b))
+        }
+        Dtype::String => {
+            let mut names: Vec<String> = vec![];
+            for name in series.iter_str(py)? {
+                if !names.iter().any(|n| n == name) {
+                    names.push(name.to_string());
+                }
+            }
+            if names.is_empty() {
+                None
+            } else {
+                Some(RangeEnum::Category(Rc::new(names)))
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn symlog_is_linear_within_linthresh() {
+        let scale = Scale::SymLog {
+            base: 10.0,
+            linthresh: 2.0,
+        };
+        let close = |a: f64, b: f64| (a - b).abs() <= 1e-9 * b.abs().max(1.0);
+        assert_eq!(scale.transform(0.0), 0.0);
+        assert_eq!(scale.transform(1.0), 0.5);
+        assert_eq!(scale.transform(-2.0), -1.0);
+        assert!(close(scale.transform(20.0), 2.0));
+        assert!(close(scale.transform(-2000.0), -4.0));
+        for &v in &[-2000.0, -3.0, -2.0, -0.5, 0.0, 1.5, 2.0, 7.0, 1e6] {
+            assert!(close(scale.inverse(scale.transform(v)), v));
+        }
+    }
 }
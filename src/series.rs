@@ -7,26 +7,37 @@
 //!    x=range(5,7),              // this is Series::range
 //!    y=np.array([1.0,2.0,3.0])  // this is Series::numpy
 //! )
-use std::collections::binary_heap::Iter;
-
+use crate::error::Error;
 use crate::Date as EzelDate;
 use crate::DateTime as EzelDateTime;
 use crate::Dtype;
 use crate::Duration as EzelDuration;
 use crate::Time as EzelTime;
-use chrono::{NaiveDate, NaiveDateTime};
 use numpy::array::PyArray1;
 use pyo3::prelude::*;
 use pyo3::types::{PyList, PyString};
+use std::convert::TryFrom;
 
 /// An adapter between Python series types (PyList, PyArray1, pandas.Series) and Rust.
 /// This is used in the Python<->Rust API boundary to
-///     1. avoid copy
+///     1. read every series the same way
 ///     2. easily cast to super type
+///
+/// Numpy arrays are read in place to avoid copy. A chart that is drawn later copies them with
+/// `detach`, so that changing an array afterwards doesn't change the chart.
+/// Lists are copied when they're passed, since their values are read one by one anyway.
+/// Ezel chrono types are immutable and not copied.
 pub enum Series {
     EmptyPyList,
-    String(Py<PyList>),
-    List { dtype: Dtype, list: Py<PyList> },
+    String(Vec<String>),
+    F64(Vec<f64>),
+    F32(Vec<f32>),
+    I64(Vec<i64>),
+    I32(Vec<i32>),
+    DateTime(Vec<chrono::NaiveDateTime>),
+    Date(Vec<chrono::NaiveDate>),
+    Time(Vec<chrono::NaiveTime>),
+    Duration(Vec<chrono::Duration>),
     NumpyF64(Py<PyArray1<f64>>),
     NumpyF32(Py<PyArray1<f32>>),
     NumpyI64(Py<PyArray1<i64>>),
@@ -37,27 +48,128 @@ pub enum Series {
     EzelDuration(Py<EzelDuration>),
 }
 
+/// A number read as `T`. None if `T` can't hold it, e.g. 1.5, NaN or 2^40 as an i32.
+trait Cast<T> {
+    fn cast(self) -> Option<T>;
+}
+
+macro_rules! impl_cast_to_float {
+    ($($from:ty),*) => {$(
+        impl Cast<f64> for $from {
+            fn cast(self) -> Option<f64> {
+                Some(self as f64)
+            }
+        }
+        impl Cast<f32> for $from {
+            fn cast(self) -> Option<f32> {
+                Some(self as f32)
+            }
+        }
+    )*};
+}
+
+impl_cast_to_float!(f64, f32, i64, i32);
+
+macro_rules! impl_cast_to_int {
+    ($($to:ty),*) => {$(
+        impl Cast<$to> for i64 {
+            fn cast(self) -> Option<$to> {
+                <$to>::try_from(self).ok()
+            }
+        }
+        impl Cast<$to> for i32 {
+            fn cast(self) -> Option<$to> {
+                <$to>::try_from(self).ok()
+            }
+        }
+        impl Cast<$to> for f64 {
+            fn cast(self) -> Option<$to> {
+                // -MIN is a power of 2, so it's exact as a float. NaN fails every comparison.
+                let min = <$to>::MIN as f64;
+                if self.fract() == 0.0 && min <= self && self < -min {
+                    Some(self as $to)
+                } else {
+                    None
+                }
+            }
+        }
+        impl Cast<$to> for f32 {
+            fn cast(self) -> Option<$to> {
+                Cast::<$to>::cast(self as f64)
+            }
+        }
+    )*};
+}
+
+impl_cast_to_int!(i64, i32);
+
+/// Reads every value as `T`, or fails if one doesn't fit `T` instead of truncating it.
+/// `values` is called twice, to check the values before reading them.
+fn cast_all<'out, V: Cast<T> + 'out, T: 'out, I: Iterator<Item = V> + 'out>(
+    series: &Series,
+    expected: &str,
+    values: impl Fn() -> PyResult<I>,
+) -> PyResult<Box<dyn Iterator<Item = T> + 'out>> {
+    if values()?.any(|v| Cast::<T>::cast(v).is_none()) {
+        return Err(series.mismatch(expected));
+    }
+    Ok(Box::new(values()?.filter_map(Cast::cast)))
+}
+
+/// Reads each value of a numeric series as `$t`.
+macro_rules! iter_numeric {
+    ($series:expr, $py:expr, $t:ty, $expected:expr) => {
+        match $series {
+            Series::EmptyPyList => Ok(Box::new(std::iter::empty())),
+            Series::F64(v) => cast_all::<_, $t, _>($series, $expected, || Ok(v.iter().copied())),
+            Series::F32(v) => cast_all::<_, $t, _>($series, $expected, || Ok(v.iter().copied())),
+            Series::I64(v) => cast_all::<_, $t, _>($series, $expected, || Ok(v.iter().copied())),
+            Series::I32(v) => cast_all::<_, $t, _>($series, $expected, || Ok(v.iter().copied())),
+            Series::NumpyF64(x) => {
+                cast_all::<_, $t, _>($series, $expected, || Ok(x.as_ref($py).iter()?.copied()))
+            }
+            Series::NumpyF32(x) => {
+                cast_all::<_, $t, _>($series, $expected, || Ok(x.as_ref($py).iter()?.copied()))
+            }
+            Series::NumpyI64(x) => {
+                cast_all::<_, $t, _>($series, $expected, || Ok(x.as_ref($py).iter()?.copied()))
+            }
+            Series::NumpyI32(x) => {
+                cast_all::<_, $t, _>($series, $expected, || Ok(x.as_ref($py).iter()?.copied()))
+            }
+            _ => Err($series.mismatch("a numeric series")),
+        }
+    };
+}
+
 impl Series {
+    /// An empty list has no dtype. It is read as an empty series of any dtype.
     pub fn dtype(&self) -> Dtype {
         match self {
-            Series::EmptyPyList => unreachable!(),
-            Series::String(x) => unreachable!(),
-            Series::List { dtype, .. } => *dtype,
-            Series::NumpyF64(..) => Dtype::F64,
-            Series::NumpyF32(..) => Dtype::F32,
-            Series::NumpyI64(..) => Dtype::I64,
-            Series::NumpyI32(..) => Dtype::I32,
-            Series::EzelDateTime(..) => Dtype::NaiveDateTime,
-            Series::EzelDate(x) => Dtype::NaiveDate,
-            Series::EzelTime(x) => Dtype::NaiveTime,
-            Series::EzelDuration(x) => Dtype::Duration,
+            Series::EmptyPyList => Dtype::F64,
+            Series::String(..) => Dtype::String,
+            Series::F64(..) | Series::NumpyF64(..) => Dtype::F64,
+            Series::F32(..) | Series::NumpyF32(..) => Dtype::F32,
+            Series::I64(..) | Series::NumpyI64(..) => Dtype::I64,
+            Series::I32(..) | Series::NumpyI32(..) => Dtype::I32,
+            Series::DateTime(..) | Series::EzelDateTime(..) => Dtype::NaiveDateTime,
+            Series::Date(..) | Series::EzelDate(..) => Dtype::NaiveDate,
+            Series::Time(..) | Series::EzelTime(..) => Dtype::NaiveTime,
+            Series::Duration(..) | Series::EzelDuration(..) => Dtype::Duration,
         }
     }
     pub fn len(&self, py: Python) -> usize {
         match self {
             Series::EmptyPyList => 0,
-            Series::String(x) => x.as_ref(py).len(),
-            Series::List { dtype: _, list: x } => x.as_ref(py).len(),
+            Series::String(x) => x.len(),
+            Series::F64(x) => x.len(),
+            Series::F32(x) => x.len(),
+            Series::I64(x) => x.len(),
+            Series::I32(x) => x.len(),
+            Series::DateTime(x) => x.len(),
+            Series::Date(x) => x.len(),
+            Series::Time(x) => x.len(),
+            Series::Duration(x) => x.len(),
             Series::NumpyF64(x) => x.as_ref(py).len(),
             Series::NumpyF32(x) => x.as_ref(py).len(),
             Series::NumpyI64(x) => x.as_ref(py).len(),
@@ -68,171 +180,120 @@ impl Series {
             Series::EzelDuration(x) => x.borrow(py).len(),
         }
     }
+    /// 0, 1, .., n - 1 as a list of int.
+    pub fn arange(_py: Python, n: usize) -> Self {
+        if n == 0 {
+            return Series::EmptyPyList;
+        }
+        Series::I64((0..n as i64).collect())
+    }
+    /// Copies a numpy array, so that changing the array doesn't change a chart drawn later.
+    /// Other series are owned or immutable already.
+    pub fn detach(&mut self, py: Python) -> PyResult<()> {
+        let copy = match self {
+            Series::NumpyF64(x) => Series::F64(x.as_ref(py).iter()?.copied().collect()),
+            Series::NumpyF32(x) => Series::F32(x.as_ref(py).iter()?.copied().collect()),
+            Series::NumpyI64(x) => Series::I64(x.as_ref(py).iter()?.copied().collect()),
+            Series::NumpyI32(x) => Series::I32(x.as_ref(py).iter()?.copied().collect()),
+            _ => return Ok(()),
+        };
+        *self = copy;
+        Ok(())
+    }
+    fn mismatch(&self, expected: &str) -> PyErr {
+        Error::DtypeMismatch {
+            expected: expected.to_string(),
+            actual: format!("a series of {:?}", self.dtype()),
+        }
+        .into()
+    }
     pub fn iter_f64<'a: 'out, 'py: 'out, 'out>(
         &'a self,
         py: Python<'py>,
-    ) -> Box<dyn Iterator<Item = f64> + 'out> {
-        match self {
-            Series::EmptyPyList => unreachable!(),
-            Series::String(..) => unreachable!(),
-            Series::List {
-                dtype: _, list: x, ..
-            } => Box::new(
-                x.as_ref(py)
-                    .iter()
-                    .map(|pyany| pyany.extract::<f64>().unwrap()),
-            ),
-            Series::NumpyF64(x) => Box::new(x.as_ref(py).iter().unwrap().map(|x| *x)),
-            Series::NumpyF32(x) => Box::new(x.as_ref(py).iter().unwrap().map(|x| (*x) as f64)),
-            Series::NumpyI64(x) => Box::new(x.as_ref(py).iter().unwrap().map(|x| (*x) as f64)),
-            Series::NumpyI32(x) => Box::new(x.as_ref(py).iter().unwrap().map(|x| (*x) as f64)),
-            Series::EzelDateTime(_) => unreachable!(),
-            Series::EzelDate(..) => unreachable!(),
-            Series::EzelTime(..) => unreachable!(),
-            Series::EzelDuration(..) => unreachable!(),
-        }
+    ) -> PyResult<Box<dyn Iterator<Item = f64> + 'out>> {
+        iter_numeric!(self, py, f64, "a numeric series")
+    }
+    pub fn iter_f32<'a: 'out, 'py: 'out, 'out>(
+        &'a self,
+        py: Python<'py>,
+    ) -> PyResult<Box<dyn Iterator<Item = f32> + 'out>> {
+        iter_numeric!(self, py, f32, "a numeric series")
     }
     pub fn iter_i64<'a: 'out, 'py: 'out, 'out>(
         &'a self,
         py: Python<'py>,
-    ) -> Box<dyn Iterator<Item = i64> + 'out> {
-        match self {
-            Series::EmptyPyList => unreachable!(),
-            Series::String(..) => unreachable!(),
-            Series::List {
-                dtype: _, list: x, ..
-            } => Box::new(
-                x.as_ref(py)
-                    .iter()
-                    .map(|pyany| pyany.extract::<i64>().unwrap()),
-            ),
-            Series::NumpyF64(x) => Box::new(x.as_ref(py).iter().unwrap().map(|x| (*x) as i64)),
-            Series::NumpyF32(x) => Box::new(x.as_ref(py).iter().unwrap().map(|x| (*x) as i64)),
-            Series::NumpyI64(x) => Box::new(x.as_ref(py).iter().unwrap().map(|x| *x)),
-            Series::NumpyI32(x) => Box::new(x.as_ref(py).iter().unwrap().map(|x| (*x) as i64)),
-            Series::EzelDateTime(_) => unreachable!(),
-            Series::EzelDate(..) => unreachable!(),
-            Series::EzelTime(..) => unreachable!(),
-            Series::EzelDuration(..) => unreachable!(),
-        }
+    ) -> PyResult<Box<dyn Iterator<Item = i64> + 'out>> {
+        iter_numeric!(self, py, i64, "a series of whole numbers within i64")
+    }
+    pub fn iter_i32<'a: 'out, 'py: 'out, 'out>(
+        &'a self,
+        py: Python<'py>,
+    ) -> PyResult<Box<dyn Iterator<Item = i32> + 'out>> {
+        iter_numeric!(self, py, i32, "a series of whole numbers within i32")
     }
     pub fn iter_datetime<'a: 'out, 'py: 'out, 'out>(
         &'a self,
         py: Python<'py>,
-    ) -> Box<dyn Iterator<Item = chrono::NaiveDateTime> + 'out> {
+    ) -> PyResult<Box<dyn Iterator<Item = chrono::NaiveDateTime> + 'out>> {
         match self {
-            Series::EmptyPyList => unreachable!(),
-            Series::String(..) => unreachable!(),
-            Series::List {
-                dtype: Dtype::NaiveDateTime,
-                list,
-                ..
-            } => Box::new(
-                list.as_ref(py)
-                    .iter()
-                    .map(|pyany| pyany.extract::<pyo3_chrono::NaiveDateTime>().unwrap().0),
-            ),
-            Series::List { dtype: _, .. } => {
-                unreachable!()
-            }
-            Series::NumpyF64(..) => unreachable!(),
-            Series::NumpyF32(..) => unreachable!(),
-            Series::NumpyI64(..) => unreachable!(),
-            Series::NumpyI32(..) => unreachable!(),
-            Series::EzelDateTime(dt) => {
-                let dt = dt.borrow(py);
-                Box::new(IterDateTime::new(dt))
-            }
-            Series::EzelDate(..) => unreachable!(),
-            Series::EzelTime(..) => unreachable!(),
-            Series::EzelDuration(..) => unreachable!(),
+            Series::EmptyPyList => Ok(Box::new(std::iter::empty())),
+            Series::DateTime(v) => Ok(Box::new(v.iter().copied())),
+            Series::EzelDateTime(x) => Ok(Box::new(IterDateTime::new(x.borrow(py)))),
+            _ => Err(self.mismatch("a datetime series")),
         }
     }
     pub fn iter_date<'a: 'out, 'py: 'out, 'out>(
         &'a self,
         py: Python<'py>,
-    ) -> Box<dyn Iterator<Item = chrono::NaiveDate> + 'out> {
+    ) -> PyResult<Box<dyn Iterator<Item = chrono::NaiveDate> + 'out>> {
         match self {
-            Series::EmptyPyList => unreachable!(),
-            Series::String(..) => unreachable!(),
-            Series::List {
-                dtype: Dtype::NaiveDate,
-                list,
-                ..
-            } => Box::new(
-                list.as_ref(py)
-                    .iter()
-                    .map(|pyany| pyany.extract::<pyo3_chrono::NaiveDate>().unwrap().0),
-            ),
-            Series::List { dtype: _, .. } => {
-                unreachable!()
-            }
-            Series::NumpyF64(..) => unreachable!(),
-            Series::NumpyF32(..) => unreachable!(),
-            Series::NumpyI64(..) => unreachable!(),
-            Series::NumpyI32(..) => unreachable!(),
-            Series::EzelDateTime(dt) => unreachable!(),
-            Series::EzelDate(d) => {
-                let d = d.borrow(py);
-                Box::new(IterDate::new(d))
-            }
-            Series::EzelTime(..) => unreachable!(),
-            Series::EzelDuration(..) => unreachable!(),
+            Series::EmptyPyList => Ok(Box::new(std::iter::empty())),
+            Series::Date(v) => Ok(Box::new(v.iter().copied())),
+            Series::EzelDate(x) => Ok(Box::new(IterDate::new(x.borrow(py)))),
+            _ => Err(self.mismatch("a date series")),
+        }
+    }
+    pub fn iter_time<'a: 'out, 'py: 'out, 'out>(
+        &'a self,
+        py: Python<'py>,
+    ) -> PyResult<Box<dyn Iterator<Item = chrono::NaiveTime> + 'out>> {
+        match self {
+            Series::EmptyPyList => Ok(Box::new(std::iter::empty())),
+            Series::Time(v) => Ok(Box::new(v.iter().copied())),
+            Series::EzelTime(x) => Ok(Box::new(IterTime::new(x.borrow(py)))),
+            _ => Err(self.mismatch("a time series")),
         }
     }
     pub fn iter_duration<'a: 'out, 'py: 'out, 'out>(
         &'a self,
         py: Python<'py>,
-    ) -> Box<dyn Iterator<Item = chrono::Duration> + 'out> {
+    ) -> PyResult<Box<dyn Iterator<Item = chrono::Duration> + 'out>> {
         match self {
-            Series::EmptyPyList => unreachable!(),
-            Series::String(..) => unreachable!(),
-            Series::List {
-                dtype: Dtype::Duration,
-                list,
-                ..
-            } => Box::new(
-                list.as_ref(py)
-                    .iter()
-                    .map(|pyany| pyany.extract::<pyo3_chrono::Duration>().unwrap().0),
-            ),
-            Series::List { dtype: _, .. } => {
-                unreachable!()
-            }
-            Series::NumpyF64(..) => unreachable!(),
-            Series::NumpyF32(..) => unreachable!(),
-            Series::NumpyI64(..) => unreachable!(),
-            Series::NumpyI32(..) => unreachable!(),
-            Series::EzelDateTime(dt) => unreachable!(),
-            Series::EzelDate(..) => unreachable!(),
-            Series::EzelTime(..) => unreachable!(),
-            Series::EzelDuration(d) => {
-                let d = d.borrow(py);
-                Box::new(IterDuration::new(d))
-            }
-            Series::EzelDuration(..) => unreachable!(),
+            Series::EmptyPyList => Ok(Box::new(std::iter::empty())),
+            Series::Duration(v) => Ok(Box::new(v.iter().copied())),
+            Series::EzelDuration(x) => Ok(Box::new(IterDuration::new(x.borrow(py)))),
+            _ => Err(self.mismatch("a duration series")),
         }
     }
-    pub fn iter_str<'a: 'out, 'py: 'out, 'out>(
+    /// Like iter_i64, but rejects float series instead of truncating them.
+    pub fn iter_integer<'a: 'out, 'py: 'out, 'out>(
         &'a self,
         py: Python<'py>,
-    ) -> Box<dyn Iterator<Item = &'out str> + 'out> {
+    ) -> PyResult<Box<dyn Iterator<Item = i64> + 'out>> {
+        match self {
+            Series::EmptyPyList => Ok(Box::new(std::iter::empty())),
+            _ if self.dtype().is_integer() => self.iter_i64(py),
+            _ => Err(self.mismatch("an integer series")),
+        }
+    }
+    pub fn iter_str<'a: 'out, 'py: 'out, 'out>(
+        &'a self,
+        _py: Python<'py>,
+    ) -> PyResult<Box<dyn Iterator<Item = &'out str> + 'out>> {
         match self {
-            Series::EmptyPyList => unreachable!(),
-            Series::String(x) => Box::new(
-                x.as_ref(py)
-                    .iter()
-                    .map(|pyany| pyany.extract::<&PyString>().unwrap().to_str().unwrap()),
-            ),
-            Series::List { .. } => unreachable!(),
-            Series::NumpyF64(..) => unreachable!(),
-            Series::NumpyF32(..) => unreachable!(),
-            Series::NumpyI64(..) => unreachable!(),
-            Series::NumpyI32(..) => unreachable!(),
-            Series::EzelDateTime(..) => unreachable!(),
-            Series::EzelDate(..) => unreachable!(),
-            Series::EzelTime(..) => unreachable!(),
-            Series::EzelDuration(..) => unreachable!(),
+            Series::EmptyPyList => Ok(Box::new(std::iter::empty())),
+            Series::String(x) => Ok(Box::new(x.iter().map(|s| s.as_str()))),
+            _ => Err(self.mismatch("a list of str")),
         }
     }
 }
@@ -277,6 +338,26 @@ impl<'py> Iterator for IterDate<'py> {
     }
 }
 
+pub struct IterTime<'py> {
+    d: PyRef<'py, EzelTime>,
+    idx: usize,
+}
+
+impl<'py> IterTime<'py> {
+    pub fn new(d: PyRef<'py, EzelTime>) -> Self {
+        Self { d, idx: 0 }
+    }
+}
+
+impl<'py> Iterator for IterTime<'py> {
+    type Item = chrono::NaiveTime;
+    fn next(&mut self) -> Option<Self::Item> {
+        let idx = self.idx;
+        self.idx += 1;
+        self.d.vec.get(idx).map(|v| *v)
+    }
+}
+
 pub struct IterDuration<'py> {
     d: PyRef<'py, EzelDuration>,
     idx: usize,
@@ -297,74 +378,113 @@ impl<'py> Iterator for IterDuration<'py> {
     }
 }
 
+/// Copies every value of the list as `T`, or None if a value isn't a `T`.
+fn extract_all<'a, T: FromPyObject<'a>>(list: &'a PyList) -> Option<Vec<T>> {
+    list.iter().map(|pyany| pyany.extract::<T>().ok()).collect()
+}
+
 impl<'source> FromPyObject<'source> for Series {
     fn extract(x: &'source PyAny) -> PyResult<Self> {
         if let Ok(arr) = x.extract::<&PyList>() {
             // infer dtype from PyList
-            if arr.iter().all(|pyany| pyany.extract::<i64>().is_ok()) {
-                return Ok(Series::List {
-                    dtype: Dtype::I64,
-                    list: arr.into(),
-                });
-            } else if arr.iter().all(|pyany| pyany.extract::<f64>().is_ok()) {
-                return Ok(Series::List {
-                    dtype: Dtype::F64,
-                    list: arr.into(),
-                });
-            } else if arr
-                .iter()
-                .all(|pyany| pyany.extract::<pyo3_chrono::NaiveDateTime>().is_ok())
-            {
-                return Ok(Series::List {
-                    dtype: Dtype::NaiveDateTime,
-                    list: arr.into(),
-                });
-            } else if arr
-                .iter()
-                .all(|pyany| pyany.extract::<pyo3_chrono::NaiveDate>().is_ok())
-            {
-                return Ok(Series::List {
-                    dtype: Dtype::NaiveDate,
-                    list: arr.into(),
-                });
-            } else if arr
-                .iter()
-                .all(|pyany| pyany.extract::<pyo3_chrono::NaiveTime>().is_ok())
-            {
-                return Ok(Series::List {
-                    dtype: Dtype::NaiveTime,
-                    list: arr.into(),
-                });
-            } else if arr
-                .iter()
-                .all(|pyany| pyany.extract::<pyo3_chrono::Duration>().is_ok())
-            {
-                return Ok(Series::List {
-                    dtype: Dtype::Duration,
-                    list: arr.into(),
-                });
-            } else {
-                todo!()
+            if arr.is_empty() {
+                return Ok(Series::EmptyPyList);
+            }
+            if let Some(v) = extract_all::<&PyString>(arr) {
+                return Ok(Series::String(
+                    v.iter()
+                        .map(|s| s.to_str().map(|s| s.to_string()))
+                        .collect::<PyResult<_>>()?,
+                ));
+            }
+            if let Some(v) = extract_all::<i64>(arr) {
+                return Ok(Series::I64(v));
+            }
+            if let Some(v) = extract_all::<f64>(arr) {
+                return Ok(Series::F64(v));
+            }
+            if let Some(v) = extract_all::<pyo3_chrono::NaiveDateTime>(arr) {
+                return Ok(Series::DateTime(v.into_iter().map(|t| t.0).collect()));
+            }
+            if let Some(v) = extract_all::<pyo3_chrono::NaiveDate>(arr) {
+                return Ok(Series::Date(v.into_iter().map(|d| d.0).collect()));
+            }
+            if let Some(v) = extract_all::<pyo3_chrono::NaiveTime>(arr) {
+                return Ok(Series::Time(v.into_iter().map(|t| t.0).collect()));
+            }
+            if let Some(v) = extract_all::<pyo3_chrono::Duration>(arr) {
+                return Ok(Series::Duration(v.into_iter().map(|d| d.0).collect()));
+            }
+            return Err(Error::DtypeMismatch {
+                expected: "a list of numbers, str, or chrono values of the same type".to_string(),
+                actual: "a list of mixed types".to_string(),
             }
+            .into());
         }
         if let Ok(arr) = x.extract::<&PyArray1<f64>>() {
-            return Ok(Series::NumpyF64(arr.to_owned()));
+            return Ok(Series::NumpyF64(arr.into()));
         }
         if let Ok(arr) = x.extract::<&PyArray1<f32>>() {
-            return Ok(Series::NumpyF32(arr.to_owned()));
+            return Ok(Series::NumpyF32(arr.into()));
         }
         if let Ok(arr) = x.extract::<&PyArray1<i64>>() {
-            return Ok(Series::NumpyI64(arr.to_owned()));
+            return Ok(Series::NumpyI64(arr.into()));
         }
         if let Ok(arr) = x.extract::<&PyArray1<i32>>() {
-            return Ok(Series::NumpyI32(arr.to_owned()));
+            return Ok(Series::NumpyI32(arr.into()));
         }
         if let Ok(dt) = x.extract::<Py<EzelDateTime>>() {
             return Ok(Series::EzelDateTime(dt));
         }
+        if let Ok(d) = x.extract::<Py<EzelDate>>() {
+            return Ok(Series::EzelDate(d));
+        }
+        if let Ok(t) = x.extract::<Py<EzelTime>>() {
+            return Ok(Series::EzelTime(t));
+        }
+        if let Ok(d) = x.extract::<Py<EzelDuration>>() {
+            return Ok(Series::EzelDuration(d));
+        }
+
+        Err(Error::DtypeMismatch {
+            expected: "a list, a 1D numpy array of f64, f32, i64 or i32, or an ezel series"
+                .to_string(),
+            actual: x.get_type().to_string(),
+        }
+        .into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Cast;
+
+    #[test]
+    fn floats_are_cast_to_ints_only_if_whole() {
+        assert_eq!(Cast::<i64>::cast(3.0f64), Some(3));
+        assert_eq!(Cast::<i64>::cast(-2.0f32), Some(-2));
+        assert_eq!(Cast::<i64>::cast(1.5f64), None);
+        assert_eq!(Cast::<i64>::cast(f64::NAN), None);
+        assert_eq!(Cast::<i64>::cast(f64::INFINITY), None);
+        assert_eq!(Cast::<i64>::cast(9.3e18f64), None);
+        assert_eq!(
+            Cast::<i64>::cast(-9.223372036854775808e18f64),
+            Some(i64::MIN)
+        );
+        assert_eq!(Cast::<i32>::cast(3e9f64), None);
+    }
+
+    #[test]
+    fn ints_are_cast_to_ints_only_in_range() {
+        assert_eq!(Cast::<i32>::cast(7i64), Some(7));
+        assert_eq!(Cast::<i32>::cast(1i64 << 40), None);
+        assert_eq!(Cast::<i64>::cast(i32::MIN), Some(i32::MIN as i64));
+    }
 
-        Err(pyo3::exceptions::PyValueError::new_err(
-            "failed to convert sequence to series",
-        ))
+    #[test]
+    fn numbers_are_cast_to_floats() {
+        assert_eq!(Cast::<f64>::cast(3i64), Some(3.0));
+        assert_eq!(Cast::<f32>::cast(0.5f64), Some(0.5));
+        assert!(Cast::<f64>::cast(f64::NAN).unwrap().is_nan());
     }
 }
@@ -0,0 +1,107 @@
+//! Step lines and stem plots. `Chart.step` holds each y until the next point,
+//! and `Chart.stem` draws a line from the baseline to each point.
+//!
+//! `StepPath` finds its corners in pixels, so that a mid step works on chrono and categorical axes
+//! without arithmetic on the values.
+
+use crate::bar::Baseline;
+use crate::fill;
+use crate::range::RangeEnum;
+use crate::{Dtype, Series};
+use plotters::element::{Drawable, PointCollection};
+use plotters::prelude::*;
+use plotters_backend::{BackendCoord, DrawingErrorKind};
+use pyo3::prelude::*;
+
+/// Where y changes between two points. Accepts "pre", "post" or "mid".
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Step {
+    /// y[i] spans (x[i - 1], x[i]].
+    Pre,
+    /// y[i] spans [x[i], x[i + 1]).
+    Post,
+    /// y changes halfway between the points.
+    Mid,
+}
+
+impl<'source> FromPyObject<'source> for Step {
+    fn extract(x: &'source PyAny) -> PyResult<Self> {
+        Ok(match x.extract::<&str>()? {
+            "pre" => Step::Pre,
+            "post" => Step::Post,
+            "mid" => Step::Mid,
+            name => {
+                return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                    "unknown step {:?}, expected \"pre\", \"post\" or \"mid\"",
+                    name
+                )))
+            }
+        })
+    }
+}
+
+/// A step line through the points.
+pub struct StepPath<X, Y> {
+    points: Vec<(X, Y)>,
+    step: Step,
+    style: ShapeStyle,
+}
+
+impl<X, Y> StepPath<X, Y> {
+    pub fn new(points: Vec<(X, Y)>, step: Step, style: ShapeStyle) -> Self {
+        StepPath {
+            points,
+            step,
+            style,
+        }
+    }
+}
+
+impl<'a, X: 'a, Y: 'a> PointCollection<'a, (X, Y)> for &'a StepPath<X, Y> {
+    type Point = &'a (X, Y);
+    type IntoIter = &'a [(X, Y)];
+    fn point_iter(self) -> Self::IntoIter {
+        &self.points
+    }
+}
+
+impl<X, Y, DB: DrawingBackend> Drawable<DB> for StepPath<X, Y> {
+    fn draw<I: Iterator<Item = BackendCoord>>(
+        &self,
+        points: I,
+        backend: &mut DB,
+        _: (u32, u32),
+    ) -> Result<(), DrawingErrorKind<DB::ErrorType>> {
+        let points: Vec<BackendCoord> = points.collect();
+        let mut path = Vec::with_capacity(points.len() * 2);
+        path.extend(points.first());
+        for w in points.windows(2) {
+            let ((x0, y0), (x1, y1)) = (w[0], w[1]);
+            match self.step {
+                Step::Pre => path.push((x0, y1)),
+                Step::Post => path.push((x1, y0)),
+                Step::Mid => {
+                    let mid = x0 + (x1 - x0) / 2;
+                    path.push((mid, y0));
+                    path.push((mid, y1));
+                }
+            }
+            path.push((x1, y1));
+        }
+        backend.draw_path(path, &self.style)
+    }
+}
+
+/// Stems from the baseline to each point. `x`, `y` and a baseline series have the same length.
+pub struct StemSeries {
+    pub x: Series,
+    pub y: Series,
+    pub baseline: Baseline,
+}
+
+impl StemSeries {
+    /// The y range covered by the stems.
+    pub fn y_extent(&self, py: Python, dtype: Dtype) -> PyResult<Option<RangeEnum>> {
+        fill::extent_between(py, &self.y, &self.baseline, dtype)
+    }
+}
@@ -0,0 +1,261 @@
+//! Tick label formats of chart axes.
+//!
+//! A format is a string or a Python callable.
+//! The string is a strftime format on chrono axes, e.g. "%m-%d %H:%M",
+//! and a Python format spec on numeric axes, e.g. ".2f", ",.0f" or "{:.1%}".
+//! The callable takes the tick value and returns its label.
+
+use chrono::format::{Fixed, Item, Numeric, Pad, StrftimeItems};
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use pyo3::prelude::*;
+use pyo3::types::PyString;
+use std::cell::RefCell;
+use std::fmt::{Display, Write};
+
+pub enum TickFormat {
+    Str(String),
+    Callable(PyObject),
+}
+
+impl<'source> FromPyObject<'source> for TickFormat {
+    fn extract(x: &'source PyAny) -> PyResult<Self> {
+        if let Ok(s) = x.extract::<&str>() {
+            return Ok(TickFormat::Str(s.to_string()));
+        }
+        if x.is_callable() {
+            return Ok(TickFormat::Callable(x.into()));
+        }
+        Err(pyo3::exceptions::PyValueError::new_err(
+            "tick format should be a string or a callable",
+        ))
+    }
+}
+
+/// A value that can be formatted as a tick label.
+pub trait TickValue {
+    /// Formats the value with a format string.
+    fn format_str(&self, py: Python, format: &str) -> PyResult<String>;
+    /// The value passed to a Python callable.
+    fn to_py(&self, py: Python) -> PyObject;
+}
+
+/// Formats a number with a Python format spec, or a "{...}" template.
+fn format_number(py: Python, value: PyObject, format: &str) -> PyResult<String> {
+    if format.contains('{') {
+        PyString::new(py, format)
+            .call_method1("format", (value,))?
+            .extract()
+    } else {
+        py.import("builtins")?
+            .call1("format", (value, format))?
+            .extract()
+    }
+}
+
+/// Rejects an invalid strftime format, which chrono would panic on while printing.
+fn strftime(format: &str) -> PyResult<StrftimeItems> {
+    let items = StrftimeItems::new(format);
+    if items.clone().any(|item| item == Item::Error) {
+        return Err(pyo3::exceptions::PyValueError::new_err(format!(
+            "invalid strftime format {:?}",
+            format
+        )));
+    }
+    Ok(items)
+}
+
+fn unfit(format: &str) -> PyErr {
+    pyo3::exceptions::PyValueError::new_err(format!(
+        "strftime format {:?} doesn't fit the axis dtype",
+        format
+    ))
+}
+
+/// Prints a chrono value formatted by `strftime`.
+/// It fails when the format asks for a field the value doesn't have, e.g. "%H" of a date.
+fn print(formatted: impl Display, format: &str) -> PyResult<String> {
+    let mut label = String::new();
+    write!(label, "{}", formatted).map_err(|_| unfit(format))?;
+    Ok(label)
+}
+
+/// Formats a duration with the "%d", "%H", "%M", "%S" and "%f" fields of a strftime format.
+/// Without "%d", "%H" counts every hour, so that a duration of a day or more doesn't wrap.
+/// A negative duration has a leading "-".
+fn format_duration(d: chrono::Duration, format: &str) -> PyResult<String> {
+    let items: Vec<Item> = strftime(format)?.collect();
+    let days = items
+        .iter()
+        .any(|item| matches!(item, Item::Numeric(Numeric::Day, _)));
+    let negative = d < chrono::Duration::zero();
+    let abs = if negative { -d } else { d };
+    let secs = abs.num_seconds();
+    // below a second, so it always fits
+    let nanos = (abs - chrono::Duration::seconds(secs))
+        .num_nanoseconds()
+        .unwrap_or(0);
+    let mut label = String::from(if negative { "-" } else { "" });
+    for item in items {
+        match item {
+            Item::Literal(s) | Item::Space(s) => label.push_str(s),
+            Item::OwnedLiteral(s) | Item::OwnedSpace(s) => label.push_str(&s),
+            Item::Numeric(field, pad) => {
+                let (value, width) = match field {
+                    Numeric::Day => (secs / 86400, 2),
+                    Numeric::Hour if days => (secs / 3600 % 24, 2),
+                    Numeric::Hour => (secs / 3600, 2),
+                    Numeric::Minute => (secs / 60 % 60, 2),
+                    Numeric::Second => (secs % 60, 2),
+                    Numeric::Nanosecond => (nanos, 9),
+                    _ => return Err(unfit(format)),
+                };
+                label.push_str(&match pad {
+                    Pad::Zero => format!("{:01$}", value, width),
+                    Pad::Space => format!("{:1$}", value, width),
+                    Pad::None => value.to_string(),
+                });
+            }
+            // like chrono, "%.f" prints as few digits as needed and nothing for whole seconds
+            Item::Fixed(Fixed::Nanosecond) if nanos == 0 => {}
+            Item::Fixed(Fixed::Nanosecond) if nanos % 1_000_000 == 0 => {
+                label.push_str(&format!(".{:03}", nanos / 1_000_000))
+            }
+            Item::Fixed(Fixed::Nanosecond) if nanos % 1_000 == 0 => {
+                label.push_str(&format!(".{:06}", nanos / 1_000))
+            }
+            Item::Fixed(Fixed::Nanosecond) | Item::Fixed(Fixed::Nanosecond9) => {
+                label.push_str(&format!(".{:09}", nanos))
+            }
+            Item::Fixed(Fixed::Nanosecond3) => {
+                label.push_str(&format!(".{:03}", nanos / 1_000_000))
+            }
+            Item::Fixed(Fixed::Nanosecond6) => label.push_str(&format!(".{:06}", nanos / 1_000)),
+            _ => return Err(unfit(format)),
+        }
+    }
+    Ok(label)
+}
+
+macro_rules! impl_number {
+    ($($t:ty),*) => {$(
+        impl TickValue for $t {
+            fn format_str(&self, py: Python, format: &str) -> PyResult<String> {
+                format_number(py, self.to_py(py), format)
+            }
+            fn to_py(&self, py: Python) -> PyObject {
+                (*self).into_py(py)
+            }
+        }
+    )*};
+}
+
+impl_number!(f64, f32, i64, i32);
+
+impl TickValue for NaiveDateTime {
+    fn format_str(&self, _py: Python, format: &str) -> PyResult<String> {
+        print(self.format_with_items(strftime(format)?), format)
+    }
+    fn to_py(&self, py: Python) -> PyObject {
+        pyo3_chrono::NaiveDateTime(*self).into_py(py)
+    }
+}
+
+impl TickValue for NaiveDate {
+    fn format_str(&self, _py: Python, format: &str) -> PyResult<String> {
+        print(self.format_with_items(strftime(format)?), format)
+    }
+    fn to_py(&self, py: Python) -> PyObject {
+        pyo3_chrono::NaiveDate(*self).into_py(py)
+    }
+}
+
+impl TickValue for NaiveTime {
+    fn format_str(&self, _py: Python, format: &str) -> PyResult<String> {
+        print(self.format_with_items(strftime(format)?), format)
+    }
+    fn to_py(&self, py: Python) -> PyObject {
+        pyo3_chrono::NaiveTime(*self).into_py(py)
+    }
+}
+
+/// A duration is formatted like a time of day, e.g. "%H:%M:%S", or "%d %H:%M" with days.
+impl TickValue for chrono::Duration {
+    fn format_str(&self, _py: Python, format: &str) -> PyResult<String> {
+        format_duration(*self, format)
+    }
+    fn to_py(&self, py: Python) -> PyObject {
+        pyo3_chrono::Duration(*self).into_py(py)
+    }
+}
+
+impl TickFormat {
+    /// Formats a tick label. plotters formatters can't fail, so the first error is kept in `error`
+    /// and an empty label is drawn.
+    pub fn label<T: TickValue>(
+        &self,
+        py: Python,
+        value: &T,
+        error: &RefCell<Option<PyErr>>,
+    ) -> String {
+        let label = match self {
+            TickFormat::Str(format) => value.format_str(py, format),
+            TickFormat::Callable(f) => f
+                .call1(py, (value.to_py(py),))
+                .and_then(|label| Ok(label.as_ref(py).str()?.to_str()?.to_string())),
+        };
+        label.unwrap_or_else(|e| {
+            error.borrow_mut().get_or_insert(e);
+            String::new()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::format_duration;
+    use chrono::Duration;
+
+    fn format(d: Duration, format: &str) -> String {
+        format_duration(d, format).unwrap()
+    }
+
+    #[test]
+    fn durations() {
+        let d = Duration::hours(2) + Duration::minutes(3) + Duration::seconds(4);
+        assert_eq!(format(d, "%H:%M:%S"), "02:03:04");
+        assert_eq!(format(d, "%-H h %M min"), "2 h 03 min");
+        assert_eq!(format(Duration::zero(), "%H:%M"), "00:00");
+    }
+
+    #[test]
+    fn durations_beyond_a_day() {
+        let d = Duration::days(2) + Duration::hours(5) + Duration::minutes(30);
+        assert_eq!(format(d, "%H:%M"), "53:30");
+        assert_eq!(format(d, "%d %H:%M"), "02 05:30");
+        assert_eq!(format(d, "%-dd %Hh"), "2d 05h");
+    }
+
+    #[test]
+    fn negative_durations() {
+        let d = -(Duration::hours(25) + Duration::milliseconds(500));
+        assert_eq!(format(d, "%H:%M:%S%.f"), "-25:00:00.500");
+        assert_eq!(format(-Duration::minutes(90), "%H:%M"), "-01:30");
+    }
+
+    #[test]
+    fn fractions_of_a_second() {
+        let d = Duration::seconds(1) + Duration::microseconds(1500);
+        assert_eq!(format(d, "%S%.f"), "01.001500");
+        assert_eq!(format(d, "%S%.3f"), "01.001");
+        assert_eq!(format(d, "%S%.9f"), "01.001500000");
+        assert_eq!(format(d, "%f"), "001500000");
+        assert_eq!(format(Duration::seconds(1), "%S%.f"), "01");
+    }
+
+    #[test]
+    fn fields_of_dates() {
+        for f in &["%Y", "%m", "%H:%M %p", "%A", "%Q"] {
+            assert!(format_duration(Duration::hours(1), f).is_err(), "{:?}", f);
+        }
+    }
+}